@@ -1,24 +1,77 @@
-use std::env;
+use std::{process, sync::Arc, time::Duration};
 
 use askama::Template;
+use auth_middleware::{AuthVerifier, AuthenticatedUser};
 use axum::{
-    http::StatusCode,
+    extract::{FromRef, State},
     response::{Html, IntoResponse},
     routing::get,
     Json, Router,
 };
-use axum_extra::extract::CookieJar;
 use serde::Serialize;
+use settings::{Settings, TokenVerification};
 use tower_http::services::ServeDir;
 
+mod settings;
+
+#[derive(Clone)]
+struct AppState {
+    settings: Arc<Settings>,
+    auth_verifier: AuthVerifier,
+}
+
+// Lets handlers take an `AuthenticatedUser`.
+impl FromRef<AppState> for AuthVerifier {
+    fn from_ref(state: &AppState) -> Self {
+        state.auth_verifier.clone()
+    }
+}
+
 #[tokio::main]
 async fn main() {
+    let settings = Settings::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    let address = settings.address();
+
+    let auth = &settings.auth_service;
+    let http_client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(auth.connect_timeout_seconds))
+        .timeout(Duration::from_secs(auth.request_timeout_seconds))
+        .build()
+        .expect("Failed to build HTTP client");
+
+    let auth_verifier = match auth.token_verification {
+        TokenVerification::Remote => AuthVerifier::remote(&auth.url),
+        TokenVerification::Jwks => AuthVerifier::jwks(&auth.url),
+    };
+    let mut auth_verifier = auth_verifier
+        .with_client(http_client)
+        .with_max_retries(auth.max_retries)
+        .with_circuit_breaker(
+            auth.circuit_breaker_threshold,
+            Duration::from_secs(auth.circuit_breaker_open_seconds),
+        );
+    if auth.verify_token_fallback {
+        auth_verifier = auth_verifier.with_remote_fallback();
+    }
+    // With `jwks` verification the first fetch happens right away; if the auth service
+    // isn't up yet, keys are fetched on the first request or the next refresh.
+    auth_verifier.spawn_jwks_refresh(Duration::from_secs(auth.jwks_refresh_seconds));
+
+    let app_state = AppState {
+        settings: Arc::new(settings),
+        auth_verifier,
+    };
+
     let app = Router::new()
         .nest_service("/assets", ServeDir::new("assets"))
         .route("/", get(root))
-        .route("/protected", get(protected));
+        .route("/protected", get(protected))
+        .with_state(app_state);
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8000").await.unwrap();
+    let listener = tokio::net::TcpListener::bind(address).await.unwrap();
 
     println!("listening on {}", listener.local_addr().unwrap());
     axum::serve(listener, app).await.unwrap();
@@ -31,13 +84,10 @@ struct IndexTemplate {
     logout_link: String,
 }
 
-async fn root() -> impl IntoResponse {
-    let mut address = env::var("AUTH_SERVICE_IP").unwrap_or("localhost".to_owned());
-    if address.is_empty() {
-        address = "localhost".to_owned();
-    }
-    let login_link = format!("http://{}:3000", address);
-    let logout_link = format!("http://{}:3000/logout", address);
+async fn root(State(state): State<AppState>) -> impl IntoResponse {
+    let public_url = &state.settings.auth_service.public_url;
+    let login_link = public_url.clone();
+    let logout_link = format!("{}/logout", public_url);
 
     let template = IndexTemplate {
         login_link,
@@ -46,40 +96,10 @@ async fn root() -> impl IntoResponse {
     Html(template.render().unwrap())
 }
 
-async fn protected(jar: CookieJar) -> impl IntoResponse {
-    let jwt_cookie = match jar.get("jwt") {
-        Some(cookie) => cookie,
-        None => {
-            return StatusCode::UNAUTHORIZED.into_response();
-        }
-    };
-
-    let api_client = reqwest::Client::builder().build().unwrap();
-
-    let verify_token_body = serde_json::json!({
-        "token": &jwt_cookie.value(),
-    });
-
-    let auth_hostname = env::var("AUTH_SERVICE_HOST_NAME").unwrap_or("0.0.0.0".to_owned());
-    let url = format!("http://{}:3000/verify-token", auth_hostname);
-
-    let response = match api_client.post(&url).json(&verify_token_body).send().await {
-        Ok(response) => response,
-        Err(_) => {
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-        }
-    };
-
-    match response.status() {
-        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::BAD_REQUEST => {
-            StatusCode::UNAUTHORIZED.into_response()
-        }
-        reqwest::StatusCode::OK => Json(ProtectedRouteResponse {
-            img_url: "https://i.ibb.co/YP90j68/Light-Live-Bootcamp-Certificate.png".to_owned(),
-        })
-        .into_response(),
-        _ => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    }
+async fn protected(_user: AuthenticatedUser) -> impl IntoResponse {
+    Json(ProtectedRouteResponse {
+        img_url: "https://i.ibb.co/YP90j68/Light-Live-Bootcamp-Certificate.png".to_owned(),
+    })
 }
 
 #[derive(Serialize)]
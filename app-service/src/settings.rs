@@ -0,0 +1,338 @@
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+use config::{Config, ConfigError, Environment, File, Source};
+use serde::Deserialize;
+
+// Settings file read from the working directory when `APP_SETTINGS_FILE` isn't set.
+pub const DEFAULT_SETTINGS_FILE: &str = "settings.toml";
+
+// Environment variables override the settings file, e.g.
+// `APP__AUTH_SERVICE__URL=http://auth-service:3000` sets `auth_service.url`.
+const ENV_PREFIX: &str = "APP";
+const ENV_SEPARATOR: &str = "__";
+
+// Configuration of the app service, merged from (lowest to highest priority) the
+// defaults below, an optional TOML file and `APP__*` environment variables.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub application: ApplicationSettings,
+    pub auth_service: AuthServiceSettings,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApplicationSettings {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthServiceSettings {
+    // Base URL the app service uses to call the auth service.
+    pub url: String,
+    // Base URL of the auth service as seen by the user's browser (login/logout links).
+    pub public_url: String,
+    pub token_verification: TokenVerification,
+    // With `jwks` verification, how often the auth service's JWKS is refetched in the
+    // background.
+    pub jwks_refresh_seconds: u64,
+    // With `jwks` verification, call `/verify-token` when a token can't be verified
+    // locally because the JWKS is unreachable or has no key for the token (e.g. the auth
+    // service signs with HS256).
+    pub verify_token_fallback: bool,
+    pub connect_timeout_seconds: u64,
+    pub request_timeout_seconds: u64,
+    // Retries of a failed call to the auth service, after a random backoff.
+    pub max_retries: u32,
+    // Consecutive failed calls after which calls fail immediately (with 503) for
+    // `circuit_breaker_open_seconds`; 0 disables the circuit breaker.
+    pub circuit_breaker_threshold: u32,
+    pub circuit_breaker_open_seconds: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenVerification {
+    // Ask the auth service's `/verify-token` about every token, so tokens banned by
+    // logout or revoked by a password reset are rejected right away.
+    #[default]
+    Remote,
+    // Verify tokens locally against the auth service's published keys, without a
+    // round-trip per request. Banned and revoked tokens are accepted until they expire.
+    Jwks,
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Load(ConfigError),
+    Invalid { key: &'static str, reason: String },
+}
+
+impl Default for ApplicationSettings {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_owned(),
+            port: 8000,
+        }
+    }
+}
+
+impl Default for AuthServiceSettings {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:3000".to_owned(),
+            public_url: "http://localhost:3000".to_owned(),
+            token_verification: TokenVerification::default(),
+            jwks_refresh_seconds: 300,
+            verify_token_fallback: false,
+            connect_timeout_seconds: 2,
+            request_timeout_seconds: 5,
+            max_retries: 2,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_open_seconds: 30,
+        }
+    }
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Load(e) => write!(f, "cannot load settings: {}", e),
+            SettingsError::Invalid { key, reason } => {
+                write!(f, "invalid setting `{}`: {}", key, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<ConfigError> for SettingsError {
+    fn from(e: ConfigError) -> Self {
+        SettingsError::Load(e)
+    }
+}
+
+impl Settings {
+    // Load the settings file named by `APP_SETTINGS_FILE` (which must then exist),
+    // or `settings.toml` if present, and apply `APP__*` environment overrides.
+    pub fn load() -> Result<Self, SettingsError> {
+        let file = match env::var("APP_SETTINGS_FILE") {
+            Ok(path) if !path.is_empty() => File::from(PathBuf::from(path)).required(true),
+            _ => File::from(Path::new(DEFAULT_SETTINGS_FILE)).required(false),
+        };
+
+        Self::from_sources(file, environment())
+    }
+
+    fn from_sources(
+        file: impl Source + Send + Sync + 'static,
+        environment: Environment,
+    ) -> Result<Self, SettingsError> {
+        let settings: Settings = Config::builder()
+            .add_source(file)
+            .add_source(environment)
+            .build()?
+            .try_deserialize()?;
+
+        settings.validated()
+    }
+
+    // Reject settings the service can't start with, naming the offending key.
+    pub fn validated(mut self) -> Result<Self, SettingsError> {
+        if self.application.host.trim().is_empty() {
+            return Err(SettingsError::Invalid {
+                key: "application.host",
+                reason: "must not be empty".to_owned(),
+            });
+        }
+
+        self.auth_service.url = base_url("auth_service.url", &self.auth_service.url)?;
+        self.auth_service.public_url =
+            base_url("auth_service.public_url", &self.auth_service.public_url)?;
+
+        for (key, value) in [
+            (
+                "auth_service.jwks_refresh_seconds",
+                self.auth_service.jwks_refresh_seconds,
+            ),
+            (
+                "auth_service.connect_timeout_seconds",
+                self.auth_service.connect_timeout_seconds,
+            ),
+            (
+                "auth_service.request_timeout_seconds",
+                self.auth_service.request_timeout_seconds,
+            ),
+        ] {
+            if value == 0 {
+                return Err(SettingsError::Invalid {
+                    key,
+                    reason: "must be greater than 0".to_owned(),
+                });
+            }
+        }
+
+        Ok(self)
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.application.host, self.application.port)
+    }
+}
+
+fn environment() -> Environment {
+    Environment::with_prefix(ENV_PREFIX)
+        .prefix_separator(ENV_SEPARATOR)
+        .separator(ENV_SEPARATOR)
+        .ignore_empty(true)
+}
+
+// Accept `http(s)://host[:port][/path]` and drop the trailing slash so paths
+// can be appended with `format!("{}/verify-token", url)`.
+fn base_url(key: &'static str, url: &str) -> Result<String, SettingsError> {
+    let url = url.trim().trim_end_matches('/');
+    let host = url
+        .strip_prefix("http://")
+        .or_else(|| url.strip_prefix("https://"))
+        .unwrap_or_default();
+
+    if host.is_empty() || host.starts_with('/') || host.contains(char::is_whitespace) {
+        return Err(SettingsError::Invalid {
+            key,
+            reason: format!("{:?} is not an http(s) URL", url),
+        });
+    }
+
+    Ok(url.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use config::FileFormat;
+
+    use super::*;
+
+    fn load(toml: &str, env: &[(&str, &str)]) -> Result<Settings, SettingsError> {
+        let env = env
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        Settings::from_sources(
+            File::from_str(toml, FileFormat::Toml),
+            environment().source(Some(env)),
+        )
+    }
+
+    fn invalid_key(result: Result<Settings, SettingsError>) -> &'static str {
+        match result {
+            Err(SettingsError::Invalid { key, .. }) => key,
+            other => panic!("expected an invalid setting, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let settings = load("", &[]).unwrap();
+
+        assert_eq!(settings.address(), "0.0.0.0:8000");
+        assert_eq!(settings.auth_service.url, "http://localhost:3000");
+        assert_eq!(
+            settings.auth_service.token_verification,
+            TokenVerification::Remote
+        );
+        assert!(!settings.auth_service.verify_token_fallback);
+    }
+
+    #[test]
+    fn test_file_overrides_defaults_and_env_overrides_file() {
+        let toml = r#"
+            [application]
+            port = 9000
+
+            [auth_service]
+            url = "http://auth:3000"
+            token_verification = "jwks"
+            max_retries = 5
+        "#;
+        let settings = load(
+            toml,
+            &[
+                ("APP__APPLICATION__HOST", "127.0.0.1"),
+                ("APP__AUTH_SERVICE__URL", "http://auth-service:3000/"),
+                ("APP__AUTH_SERVICE__VERIFY_TOKEN_FALLBACK", "true"),
+                ("APP__AUTH_SERVICE__MAX_RETRIES", "1"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(settings.address(), "127.0.0.1:9000");
+        // The trailing slash is dropped so paths can be appended.
+        assert_eq!(settings.auth_service.url, "http://auth-service:3000");
+        assert_eq!(
+            settings.auth_service.token_verification,
+            TokenVerification::Jwks
+        );
+        assert!(settings.auth_service.verify_token_fallback);
+        assert_eq!(settings.auth_service.max_retries, 1);
+    }
+
+    #[test]
+    fn test_type_errors_are_reported() {
+        let result = load("", &[("APP__APPLICATION__PORT", "not-a-port")]);
+
+        assert!(matches!(result, Err(SettingsError::Load(_))));
+        assert!(load("[auth_service]\nunknown = 1", &[]).is_err());
+        assert!(load("[auth_service]\ntoken_verification = \"local\"", &[]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_settings_name_the_key() {
+        let cases = [
+            ("APP__APPLICATION__HOST", " ", "application.host"),
+            (
+                "APP__AUTH_SERVICE__URL",
+                "localhost:3000",
+                "auth_service.url",
+            ),
+            (
+                "APP__AUTH_SERVICE__PUBLIC_URL",
+                "ftp://localhost:3000",
+                "auth_service.public_url",
+            ),
+            (
+                "APP__AUTH_SERVICE__JWKS_REFRESH_SECONDS",
+                "0",
+                "auth_service.jwks_refresh_seconds",
+            ),
+            (
+                "APP__AUTH_SERVICE__CONNECT_TIMEOUT_SECONDS",
+                "0",
+                "auth_service.connect_timeout_seconds",
+            ),
+            (
+                "APP__AUTH_SERVICE__REQUEST_TIMEOUT_SECONDS",
+                "0",
+                "auth_service.request_timeout_seconds",
+            ),
+        ];
+
+        for (name, value, expected_key) in cases {
+            assert_eq!(
+                invalid_key(load("", &[(name, value)])),
+                expected_key,
+                "{}={}",
+                name,
+                value
+            );
+        }
+    }
+}
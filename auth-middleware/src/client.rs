@@ -0,0 +1,254 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+
+// Defaults for `AuthVerifier`s that aren't handed a client.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_RETRIES: u32 = 2;
+const BREAKER_FAILURE_THRESHOLD: u32 = 5;
+const BREAKER_OPEN_DURATION: Duration = Duration::from_secs(30);
+
+// Retry `n` waits a random time up to `RETRY_BASE_DELAY * 2^n`, so replicas retrying
+// at once don't hit the auth service in lockstep.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+
+// Calls to the auth service over one shared connection pool. Failed calls are retried a
+// bounded number of times (every call we make is idempotent), and once enough calls in a
+// row have failed the circuit breaker fails calls immediately for a while, so a dead auth
+// service costs requests a quick 503 rather than a timeout each.
+#[derive(Clone)]
+pub(crate) struct AuthServiceClient {
+    http: reqwest::Client,
+    max_retries: u32,
+    breaker: Arc<CircuitBreaker>,
+}
+
+#[derive(Debug)]
+pub(crate) enum RequestError {
+    CircuitOpen,
+    Http(reqwest::Error),
+    Status(reqwest::StatusCode),
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::CircuitOpen => write!(f, "circuit breaker is open"),
+            RequestError::Http(e) => write!(f, "{}", e),
+            RequestError::Status(status) => write!(f, "unexpected status {}", status),
+        }
+    }
+}
+
+impl From<reqwest::Error> for RequestError {
+    fn from(e: reqwest::Error) -> Self {
+        RequestError::Http(e)
+    }
+}
+
+impl Default for AuthServiceClient {
+    fn default() -> Self {
+        let http = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self {
+            http,
+            max_retries: MAX_RETRIES,
+            breaker: Arc::new(CircuitBreaker::new(
+                BREAKER_FAILURE_THRESHOLD,
+                BREAKER_OPEN_DURATION,
+            )),
+        }
+    }
+}
+
+impl AuthServiceClient {
+    pub(crate) fn set_http_client(&mut self, http: reqwest::Client) {
+        self.http = http;
+    }
+
+    pub(crate) fn set_max_retries(&mut self, max_retries: u32) {
+        self.max_retries = max_retries;
+    }
+
+    pub(crate) fn set_circuit_breaker(&mut self, failure_threshold: u32, open_for: Duration) {
+        self.breaker = Arc::new(CircuitBreaker::new(failure_threshold, open_for));
+    }
+
+    // Send the request built by `build`, retrying connection failures, timeouts and 5xx
+    // responses. Any other response is returned as is.
+    pub(crate) async fn send(
+        &self,
+        build: impl Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, RequestError> {
+        if !self.breaker.allow() {
+            return Err(RequestError::CircuitOpen);
+        }
+
+        let mut attempt = 0;
+        loop {
+            let error = match build(&self.http).send().await {
+                Ok(response) if !response.status().is_server_error() => {
+                    self.breaker.record_success();
+                    return Ok(response);
+                }
+                Ok(response) => RequestError::Status(response.status()),
+                Err(e) => RequestError::Http(e),
+            };
+
+            if attempt >= self.max_retries {
+                self.breaker.record_failure();
+                return Err(error);
+            }
+
+            tokio::time::sleep(retry_delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    let max = RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt));
+    rand::thread_rng().gen_range(Duration::ZERO..=max)
+}
+
+pub(crate) struct CircuitBreaker {
+    // Consecutive failed calls after which the breaker opens; 0 disables it.
+    failure_threshold: u32,
+    open_for: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BreakerState {
+    Closed { failures: u32 },
+    Open { until: Instant },
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(failure_threshold: u32, open_for: Duration) -> Self {
+        Self {
+            failure_threshold,
+            open_for,
+            state: Mutex::new(BreakerState::Closed { failures: 0 }),
+        }
+    }
+
+    pub(crate) fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            BreakerState::Closed { .. } => true,
+            // Once the open period is over, let a single trial call through and keep
+            // failing the others for another period; the trial's outcome closes the
+            // breaker or reopens it.
+            BreakerState::Open { until } if Instant::now() >= until => {
+                *state = BreakerState::Open {
+                    until: Instant::now() + self.open_for,
+                };
+                true
+            }
+            BreakerState::Open { .. } => false,
+        }
+    }
+
+    pub(crate) fn record_success(&self) {
+        *self.state.lock().unwrap() = BreakerState::Closed { failures: 0 };
+    }
+
+    pub(crate) fn record_failure(&self) {
+        if self.failure_threshold == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        *state = match *state {
+            BreakerState::Closed { failures } if failures + 1 < self.failure_threshold => {
+                BreakerState::Closed {
+                    failures: failures + 1,
+                }
+            }
+            _ => BreakerState::Open {
+                until: Instant::now() + self.open_for,
+            },
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breaker_should_open_after_threshold_consecutive_failures() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_failure();
+        assert!(breaker.allow());
+
+        breaker.record_failure();
+        assert!(!breaker.allow());
+    }
+
+    #[test]
+    fn success_should_reset_failure_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn breaker_should_allow_one_trial_call_per_open_period() {
+        let open_for = Duration::from_millis(20);
+        let breaker = CircuitBreaker::new(1, open_for);
+        breaker.record_failure();
+        assert!(!breaker.allow());
+
+        std::thread::sleep(open_for);
+        assert!(breaker.allow());
+        assert!(!breaker.allow());
+
+        // A failed trial opens the breaker again, a successful one closes it.
+        breaker.record_failure();
+        assert!(!breaker.allow());
+
+        std::thread::sleep(open_for);
+        assert!(breaker.allow());
+        breaker.record_success();
+        assert!(breaker.allow());
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn zero_threshold_should_disable_breaker() {
+        let breaker = CircuitBreaker::new(0, Duration::from_secs(60));
+
+        for _ in 0..10 {
+            breaker.record_failure();
+        }
+
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn retry_delay_should_stay_within_exponential_bound() {
+        for attempt in 0..5 {
+            let max = RETRY_BASE_DELAY * 2u32.pow(attempt);
+            for _ in 0..100 {
+                assert!(retry_delay(attempt) <= max);
+            }
+        }
+    }
+}
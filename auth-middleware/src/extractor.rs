@@ -0,0 +1,98 @@
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::CookieJar;
+
+use crate::{AuthVerifier, Claims, VerifyError, JWT_COOKIE_NAME};
+
+// The user a request was made by, taken from a verified JWT.
+//
+// Extracting it needs an `AuthVerifier` in the router state (via `FromRef`), unless the
+// route sits behind an `AuthLayer`, which has already verified the token.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub claims: Claims,
+}
+
+impl AuthenticatedUser {
+    pub fn email(&self) -> &str {
+        &self.claims.sub
+    }
+}
+
+#[derive(Debug)]
+pub enum AuthRejection {
+    MissingToken,
+    InvalidToken,
+    AuthServiceUnavailable,
+}
+
+impl IntoResponse for AuthRejection {
+    fn into_response(self) -> Response {
+        match self {
+            AuthRejection::MissingToken | AuthRejection::InvalidToken => (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"))],
+            )
+                .into_response(),
+            AuthRejection::AuthServiceUnavailable => {
+                StatusCode::SERVICE_UNAVAILABLE.into_response()
+            }
+        }
+    }
+}
+
+impl From<VerifyError> for AuthRejection {
+    fn from(e: VerifyError) -> Self {
+        match e {
+            VerifyError::InvalidToken | VerifyError::UnknownKey => AuthRejection::InvalidToken,
+            VerifyError::Unavailable => AuthRejection::AuthServiceUnavailable,
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for AuthenticatedUser
+where
+    AuthVerifier: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(user) = parts.extensions.get::<AuthenticatedUser>() {
+            return Ok(user.clone());
+        }
+
+        authenticate(&AuthVerifier::from_ref(state), &parts.headers).await
+    }
+}
+
+pub(crate) async fn authenticate(
+    verifier: &AuthVerifier,
+    headers: &HeaderMap,
+) -> Result<AuthenticatedUser, AuthRejection> {
+    let token = token_from_headers(headers).ok_or(AuthRejection::MissingToken)?;
+    let claims = verifier.verify(&token).await?;
+
+    Ok(AuthenticatedUser { claims })
+}
+
+// A bearer token takes precedence over the cookie, so API clients holding a token aren't
+// affected by a stale cookie.
+fn token_from_headers(headers: &HeaderMap) -> Option<String> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty());
+
+    match bearer {
+        Some(token) => Some(token.to_owned()),
+        None => CookieJar::from_headers(headers)
+            .get(JWT_COOKIE_NAME)
+            .map(|cookie| cookie.value().to_owned()),
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use tokio::sync::RwLock;
+
+use crate::{
+    client::{AuthServiceClient, RequestError},
+    Claims, VerifyError,
+};
+
+// A token naming an unknown `kid` triggers a refetch, but not more often than this,
+// so forged tokens can't make us hammer the auth service.
+const UNKNOWN_KID_REFETCH_INTERVAL: Duration = Duration::from_secs(10);
+
+// Public keys of the auth service, fetched from its JWKS endpoint and cached, so
+// tokens can be verified without a round-trip per request.
+#[derive(Clone)]
+pub(crate) struct JwksCache {
+    url: String,
+    keys: Arc<RwLock<CachedKeys>>,
+}
+
+#[derive(Default)]
+struct CachedKeys {
+    keys: HashMap<String, (Algorithm, DecodingKey)>,
+    fetched_at: Option<Instant>,
+}
+
+impl JwksCache {
+    pub(crate) fn new(url: String) -> Self {
+        Self {
+            url,
+            keys: Arc::new(RwLock::new(CachedKeys::default())),
+        }
+    }
+
+    // Replace the cached keys with the ones currently published.
+    pub(crate) async fn refresh(&self, client: &AuthServiceClient) -> Result<(), RequestError> {
+        let response = client.send(|http| http.get(&self.url)).await?;
+        if !response.status().is_success() {
+            return Err(RequestError::Status(response.status()));
+        }
+        let jwks: JwkSet = response.json().await?;
+
+        let keys = jwks
+            .keys
+            .iter()
+            .filter_map(|jwk| {
+                let kid = jwk.common.key_id.clone()?;
+                let algorithm = jwk.common.key_algorithm?.to_string().parse().ok()?;
+                let decoding_key = DecodingKey::from_jwk(jwk).ok()?;
+                Some((kid, (algorithm, decoding_key)))
+            })
+            .collect();
+
+        *self.keys.write().await = CachedKeys {
+            keys,
+            fetched_at: Some(Instant::now()),
+        };
+        Ok(())
+    }
+
+    // Keep the cache current in the background, so keys the auth service retires
+    // stop being accepted here as well.
+    pub(crate) fn spawn_refresh(&self, client: AuthServiceClient, period: Duration) {
+        let cache = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if let Err(e) = cache.refresh(&client).await {
+                    eprintln!("failed to fetch JWKS from {}: {}", cache.url, e);
+                }
+            }
+        });
+    }
+
+    pub(crate) async fn verify(
+        &self,
+        client: &AuthServiceClient,
+        token: &str,
+    ) -> Result<Claims, VerifyError> {
+        let kid = decode_header(token)
+            .map_err(|_| VerifyError::InvalidToken)?
+            .kid
+            .ok_or(VerifyError::UnknownKey)?;
+
+        let (algorithm, decoding_key) = self.key(client, &kid).await?;
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_required_spec_claims(&["sub", "exp"]);
+
+        decode::<Claims>(token, &decoding_key, &validation)
+            .map(|data| data.claims)
+            .map_err(|_| VerifyError::InvalidToken)
+    }
+
+    async fn key(
+        &self,
+        client: &AuthServiceClient,
+        kid: &str,
+    ) -> Result<(Algorithm, DecodingKey), VerifyError> {
+        let fetched_at = {
+            let cached = self.keys.read().await;
+            if let Some(key) = cached.keys.get(kid) {
+                return Ok(key.clone());
+            }
+            cached.fetched_at
+        };
+
+        // The key may have been published since the last fetch.
+        let may_refetch = fetched_at.is_none_or(|at| at.elapsed() >= UNKNOWN_KID_REFETCH_INTERVAL);
+        if may_refetch {
+            if let Err(e) = self.refresh(client).await {
+                eprintln!("failed to fetch JWKS from {}: {}", self.url, e);
+            }
+        }
+
+        let cached = self.keys.read().await;
+        match (cached.keys.get(kid), cached.fetched_at) {
+            (Some(key), _) => Ok(key.clone()),
+            (None, Some(_)) => Err(VerifyError::UnknownKey),
+            (None, None) => Err(VerifyError::Unavailable),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{extract::Request, response::IntoResponse, response::Response};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{extractor::authenticate, AuthVerifier};
+
+// Rejects every request without a valid token before it reaches the wrapped service, and
+// hands the `AuthenticatedUser` to handlers through the request extensions:
+//
+//     Router::new()
+//         .route("/protected", get(handler))
+//         .layer(AuthLayer::new(verifier))
+//
+//     async fn handler(user: AuthenticatedUser) -> ... // or Extension<AuthenticatedUser>
+#[derive(Clone)]
+pub struct AuthLayer {
+    verifier: AuthVerifier,
+}
+
+impl AuthLayer {
+    pub fn new(verifier: AuthVerifier) -> Self {
+        Self { verifier }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            verifier: self.verifier.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    verifier: AuthVerifier,
+}
+
+impl<S> Service<Request> for AuthService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request) -> Self::Future {
+        // Call the clone that was driven to readiness, leaving a fresh one in its place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let verifier = self.verifier.clone();
+
+        Box::pin(async move {
+            match authenticate(&verifier, request.headers()).await {
+                Ok(user) => {
+                    request.extensions_mut().insert(user);
+                    inner.call(request).await
+                }
+                Err(rejection) => Ok(rejection.into_response()),
+            }
+        })
+    }
+}
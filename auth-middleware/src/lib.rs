@@ -0,0 +1,18 @@
+// Authentication for services sitting behind the auth service: an `AuthenticatedUser`
+// extractor for individual handlers and an `AuthLayer` for whole routers. Both take the
+// token from the `jwt` cookie or an `Authorization: Bearer` header, verify it with an
+// `AuthVerifier` (locally against the published JWKS, or by asking `/verify-token`) and
+// reject the request with 401 if that fails.
+
+mod client;
+mod extractor;
+mod jwks;
+mod layer;
+mod verifier;
+
+pub use extractor::*;
+pub use layer::*;
+pub use verifier::*;
+
+// Cookie the auth service stores the JWT in after login.
+pub const JWT_COOKIE_NAME: &str = "jwt";
@@ -0,0 +1,70 @@
+use auth_middleware::AuthVerifier;
+use axum::http::{header, StatusCode};
+
+use crate::helpers::{
+    body_text, downstream_app, get_route, spawn_auth_service, Credentials, TestKey, TEST_EMAIL,
+    TEST_KID,
+};
+
+#[tokio::test]
+async fn should_return_200_and_claims_for_valid_cookie() {
+    let key = TestKey::generate();
+    let app = downstream_app(AuthVerifier::jwks(&spawn_auth_service(&key).await));
+
+    let token = key.token(TEST_KID, 600);
+    let response = get_route(&app, "/extractor", Credentials::Cookie(&token)).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_text(response).await, TEST_EMAIL);
+}
+
+#[tokio::test]
+async fn should_return_200_for_valid_bearer_token() {
+    let key = TestKey::generate();
+    let app = downstream_app(AuthVerifier::jwks(&spawn_auth_service(&key).await));
+
+    let token = key.token(TEST_KID, 600);
+    let response = get_route(&app, "/extractor", Credentials::Bearer(&token)).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_text(response).await, TEST_EMAIL);
+}
+
+#[tokio::test]
+async fn should_return_401_if_token_missing() {
+    let key = TestKey::generate();
+    let app = downstream_app(AuthVerifier::jwks(&spawn_auth_service(&key).await));
+
+    let response = get_route(&app, "/extractor", Credentials::None).await;
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Bearer");
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let key = TestKey::generate();
+    let app = downstream_app(AuthVerifier::jwks(&spawn_auth_service(&key).await));
+
+    let expired = key.token(TEST_KID, -600);
+    let unpublished_key = TestKey::generate().token(TEST_KID, 600);
+    let unknown_kid = key.token("unknown-key", 600);
+    let tampered = format!("{}x", key.token(TEST_KID, 600));
+
+    for token in [
+        "invalid",
+        expired.as_str(),
+        unpublished_key.as_str(),
+        unknown_kid.as_str(),
+        tampered.as_str(),
+    ] {
+        let response = get_route(&app, "/extractor", Credentials::Cookie(token)).await;
+
+        assert_eq!(
+            response.status(),
+            StatusCode::UNAUTHORIZED,
+            "Failed for token: {}",
+            token
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use auth_middleware::{AuthLayer, AuthVerifier, AuthenticatedUser, Claims};
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::StatusCode,
+    response::Response,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use ring::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use serde::Deserialize;
+use tower::ServiceExt;
+
+pub const TEST_EMAIL: &str = "test@example.com";
+pub const TEST_KID: &str = "test-key";
+
+// An Ed25519 key pair, the way the auth service signs tokens by default.
+pub struct TestKey {
+    encoding_key: EncodingKey,
+    public_key: Vec<u8>,
+}
+
+impl TestKey {
+    pub fn generate() -> Self {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+
+        Self {
+            encoding_key: EncodingKey::from_ed_der(pkcs8.as_ref()),
+            public_key: key_pair.public_key().as_ref().to_vec(),
+        }
+    }
+
+    pub fn token(&self, kid: &str, ttl_seconds: i64) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let claims = Claims {
+            sub: TEST_EMAIL.to_owned(),
+            iat: now as usize,
+            exp: (now + ttl_seconds) as usize,
+            jti: "test-jti".to_owned(),
+        };
+
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(kid.to_owned());
+        encode(&header, &claims, &self.encoding_key).unwrap()
+    }
+
+    fn jwks(&self) -> serde_json::Value {
+        serde_json::json!({
+            "keys": [{
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "x": URL_SAFE_NO_PAD.encode(&self.public_key),
+                "kid": TEST_KID,
+                "alg": "EdDSA",
+                "use": "sig",
+            }]
+        })
+    }
+}
+
+// Stands in for the auth service: publishes the key and answers `/verify-token`.
+pub async fn spawn_auth_service(key: &TestKey) -> String {
+    #[derive(Deserialize)]
+    struct VerifyTokenRequest {
+        token: String,
+    }
+
+    async fn verify_token(
+        State(public_key): State<Vec<u8>>,
+        Json(request): Json<VerifyTokenRequest>,
+    ) -> StatusCode {
+        let decoding_key = DecodingKey::from_ed_der(&public_key);
+        match decode::<Claims>(
+            &request.token,
+            &decoding_key,
+            &Validation::new(Algorithm::EdDSA),
+        ) {
+            Ok(_) => StatusCode::OK,
+            Err(_) => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    let jwks = key.jwks();
+    let app = Router::new()
+        .route("/.well-known/jwks.json", get(move || async { Json(jwks) }))
+        .route("/verify-token", post(verify_token))
+        .with_state(key.public_key.clone());
+
+    spawn(app).await
+}
+
+// Stands in for an auth service whose `/verify-token` answers 503 to the first `failures`
+// calls and accepts every token after that. Returns its URL and the number of calls made.
+pub async fn spawn_flaky_auth_service(failures: usize) -> (String, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let app = Router::new()
+        .route(
+            "/verify-token",
+            post(move |State(calls): State<Arc<AtomicUsize>>| async move {
+                if calls.fetch_add(1, Ordering::SeqCst) < failures {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::OK
+                }
+            }),
+        )
+        .with_state(calls.clone());
+
+    (spawn(app).await, calls)
+}
+
+// An address nothing listens on.
+pub async fn unreachable_auth_service() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
+
+async fn spawn(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    address
+}
+
+// A downstream service with one route guarded by the extractor and one by the layer;
+// both answer with the authenticated user's email.
+pub fn downstream_app(verifier: AuthVerifier) -> Router {
+    let layered =
+        Router::new()
+            .route(
+                "/layer",
+                get(|Extension(user): Extension<AuthenticatedUser>| async move {
+                    user.email().to_owned()
+                }),
+            )
+            .layer(AuthLayer::new(verifier.clone()));
+
+    Router::new()
+        .route(
+            "/extractor",
+            get(|user: AuthenticatedUser| async move { user.email().to_owned() }),
+        )
+        .with_state(verifier)
+        .merge(layered)
+}
+
+pub enum Credentials<'a> {
+    None,
+    Cookie(&'a str),
+    Bearer(&'a str),
+}
+
+pub async fn get_route(app: &Router, path: &str, credentials: Credentials<'_>) -> Response {
+    let request = Request::get(path);
+    let request = match credentials {
+        Credentials::None => request,
+        Credentials::Cookie(token) => request.header("cookie", format!("jwt={}", token)),
+        Credentials::Bearer(token) => request.header("authorization", format!("Bearer {}", token)),
+    };
+
+    app.clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+pub async fn body_text(response: Response) -> String {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
@@ -0,0 +1,43 @@
+use auth_middleware::AuthVerifier;
+use axum::http::{header, StatusCode};
+
+use crate::helpers::{
+    body_text, downstream_app, get_route, spawn_auth_service, unreachable_auth_service,
+    Credentials, TestKey, TEST_EMAIL, TEST_KID,
+};
+
+#[tokio::test]
+async fn should_pass_authenticated_user_to_handler() {
+    let key = TestKey::generate();
+    let app = downstream_app(AuthVerifier::jwks(&spawn_auth_service(&key).await));
+
+    let token = key.token(TEST_KID, 600);
+    let response = get_route(&app, "/layer", Credentials::Bearer(&token)).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_text(response).await, TEST_EMAIL);
+}
+
+#[tokio::test]
+async fn should_return_401_if_token_missing_or_invalid() {
+    let key = TestKey::generate();
+    let app = downstream_app(AuthVerifier::jwks(&spawn_auth_service(&key).await));
+
+    let response = get_route(&app, "/layer", Credentials::None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Bearer");
+
+    let expired = key.token(TEST_KID, -600);
+    let response = get_route(&app, "/layer", Credentials::Cookie(&expired)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn should_return_503_if_auth_service_unreachable() {
+    let app = downstream_app(AuthVerifier::jwks(&unreachable_auth_service().await));
+
+    let token = TestKey::generate().token(TEST_KID, 600);
+    let response = get_route(&app, "/layer", Credentials::Cookie(&token)).await;
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
@@ -0,0 +1,4 @@
+mod extractor;
+mod helpers;
+mod layer;
+mod verifier;
@@ -0,0 +1,102 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use auth_middleware::{AuthVerifier, VerifyError};
+
+use crate::helpers::{
+    spawn_auth_service, spawn_flaky_auth_service, unreachable_auth_service, TestKey, TEST_EMAIL,
+    TEST_KID,
+};
+
+#[tokio::test]
+async fn remote_verifier_should_ask_auth_service() {
+    let key = TestKey::generate();
+    let verifier = AuthVerifier::remote(&spawn_auth_service(&key).await);
+
+    let claims = verifier.verify(&key.token(TEST_KID, 600)).await.unwrap();
+    assert_eq!(claims.sub, TEST_EMAIL);
+
+    let forged = TestKey::generate().token(TEST_KID, 600);
+    assert!(matches!(
+        verifier.verify(&forged).await,
+        Err(VerifyError::InvalidToken)
+    ));
+}
+
+#[tokio::test]
+async fn jwks_verifier_should_fall_back_to_remote_only_if_configured() {
+    let key = TestKey::generate();
+    let auth_service_url = spawn_auth_service(&key).await;
+
+    // The auth service accepts the token, but it names a key that isn't published.
+    let token = key.token("unpublished-key", 600);
+
+    let verifier = AuthVerifier::jwks(&auth_service_url);
+    assert!(matches!(
+        verifier.verify(&token).await,
+        Err(VerifyError::UnknownKey)
+    ));
+
+    let verifier = AuthVerifier::jwks(&auth_service_url).with_remote_fallback();
+    let claims = verifier.verify(&token).await.unwrap();
+    assert_eq!(claims.sub, TEST_EMAIL);
+}
+
+#[tokio::test]
+async fn should_report_unreachable_auth_service() {
+    let url = unreachable_auth_service().await;
+    let token = TestKey::generate().token(TEST_KID, 600);
+
+    for verifier in [
+        AuthVerifier::remote(&url),
+        AuthVerifier::jwks(&url),
+        AuthVerifier::jwks(&url).with_remote_fallback(),
+    ] {
+        assert!(matches!(
+            verifier.verify(&token).await,
+            Err(VerifyError::Unavailable)
+        ));
+    }
+}
+
+#[tokio::test]
+async fn should_retry_failed_calls() {
+    let (url, calls) = spawn_flaky_auth_service(2).await;
+    let token = TestKey::generate().token(TEST_KID, 600);
+
+    let claims = AuthVerifier::remote(&url).verify(&token).await.unwrap();
+
+    assert_eq!(claims.sub, TEST_EMAIL);
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn should_give_up_after_max_retries() {
+    let (url, calls) = spawn_flaky_auth_service(2).await;
+    let token = TestKey::generate().token(TEST_KID, 600);
+
+    let verifier = AuthVerifier::remote(&url).with_max_retries(1);
+
+    assert!(matches!(
+        verifier.verify(&token).await,
+        Err(VerifyError::Unavailable)
+    ));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn open_circuit_breaker_should_fail_calls_without_contacting_auth_service() {
+    let (url, calls) = spawn_flaky_auth_service(usize::MAX).await;
+    let token = TestKey::generate().token(TEST_KID, 600);
+
+    let verifier = AuthVerifier::remote(&url)
+        .with_max_retries(0)
+        .with_circuit_breaker(2, Duration::from_secs(60));
+
+    for _ in 0..5 {
+        assert!(matches!(
+            verifier.verify(&token).await,
+            Err(VerifyError::Unavailable)
+        ));
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
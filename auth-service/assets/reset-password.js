@@ -0,0 +1,76 @@
+const requestSection = document.getElementById("request-section");
+const confirmSection = document.getElementById("confirm-section");
+
+// The link in the reset email carries the token; without one, ask for the email address.
+const token = new URLSearchParams(window.location.search).get("token");
+if (token !== null) {
+    requestSection.style.display = "none";
+    confirmSection.style.display = "block";
+}
+
+function showError(alert, response) {
+    response.json().then(data => {
+        let error_msg = data.error;
+        if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
+            alert.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
+            alert.style.display = "block";
+        } else {
+            alert.style.display = "none";
+        }
+    });
+}
+
+// -----------------------------------------------------
+
+const requestForm = document.getElementById("request-form");
+const requestButton = document.getElementById("request-form-submit");
+const requestErrAlert = document.getElementById("request-err-alert");
+
+requestButton.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const email = requestForm.email.value;
+
+    fetch('/password-reset/request', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ email }),
+    }).then(response => {
+        if (response.ok) {
+            requestForm.email.value = "";
+            requestErrAlert.style.display = "none";
+            alert("If an account exists for this address, we sent it a link to reset the password.");
+        } else {
+            showError(requestErrAlert, response);
+        }
+    });
+});
+
+const confirmForm = document.getElementById("confirm-form");
+const confirmButton = document.getElementById("confirm-form-submit");
+const confirmErrAlert = document.getElementById("confirm-err-alert");
+
+confirmButton.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const newPassword = confirmForm.password.value;
+
+    fetch('/password-reset/confirm', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ token, newPassword }),
+    }).then(response => {
+        if (response.ok) {
+            confirmForm.password.value = "";
+            confirmErrAlert.style.display = "none";
+            alert("Your password was changed. Please log in again.");
+            window.location.assign("/");
+        } else {
+            showError(confirmErrAlert, response);
+        }
+    });
+});
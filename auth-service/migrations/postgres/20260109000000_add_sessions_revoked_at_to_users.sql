@@ -0,0 +1,2 @@
+-- Set when a password reset signs the user out everywhere.
+ALTER TABLE users ADD COLUMN sessions_revoked_at BIGINT;
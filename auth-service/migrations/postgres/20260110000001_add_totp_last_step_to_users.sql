@@ -0,0 +1,2 @@
+-- Time step of the last TOTP code accepted, to reject replays.
+ALTER TABLE users ADD COLUMN totp_last_step BIGINT;
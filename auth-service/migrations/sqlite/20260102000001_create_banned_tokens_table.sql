@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS banned_tokens (
+    jti TEXT NOT NULL PRIMARY KEY,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS banned_tokens_expires_at ON banned_tokens (expires_at);
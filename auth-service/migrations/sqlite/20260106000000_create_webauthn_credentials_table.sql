@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS webauthn_credentials (
+    credential_id TEXT NOT NULL PRIMARY KEY,
+    email TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    sign_count INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS webauthn_credentials_email ON webauthn_credentials (email);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS webauthn_challenges (
+    challenge TEXT NOT NULL PRIMARY KEY,
+    ceremony TEXT NOT NULL,
+    email TEXT,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS webauthn_challenges_expires_at ON webauthn_challenges (expires_at);
@@ -0,0 +1,3 @@
+-- Accounts that existed before email verification keep working.
+ALTER TABLE users ADD COLUMN email_verified BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE users ADD COLUMN verification_email_sent_at INTEGER;
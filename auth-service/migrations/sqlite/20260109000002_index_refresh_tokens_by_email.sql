@@ -0,0 +1,2 @@
+-- Password resets end every session of the user.
+CREATE INDEX IF NOT EXISTS refresh_tokens_email ON refresh_tokens (email);
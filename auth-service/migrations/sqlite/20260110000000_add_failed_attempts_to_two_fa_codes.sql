@@ -0,0 +1,2 @@
+-- Wrong codes entered for the pending login attempt; it is dropped after too many.
+ALTER TABLE two_fa_codes ADD COLUMN failed_attempts INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+-- Auth tokens now carry the user's session generation, which a password reset bumps.
+-- Users whose sessions were revoked start at generation 1, so their older tokens
+-- stay rejected.
+ALTER TABLE users ADD COLUMN session_generation INTEGER NOT NULL DEFAULT 0;
+UPDATE users SET session_generation = 1 WHERE sessions_revoked_at IS NOT NULL;
+ALTER TABLE users DROP COLUMN sessions_revoked_at;
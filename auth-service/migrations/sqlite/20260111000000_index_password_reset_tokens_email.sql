@@ -0,0 +1,2 @@
+-- A completed reset removes every other pending reset of the user.
+CREATE INDEX IF NOT EXISTS password_reset_tokens_email ON password_reset_tokens (email);
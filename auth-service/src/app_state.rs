@@ -0,0 +1,51 @@
+use sqlx::{PgPool, SqlitePool};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    domain::{
+        BannedTokenStore, EmailClient, EmailDomainPolicy, PasswordPolicy, PasswordResetTokenStore,
+        RecoveryCodeStore, RefreshTokenStore, SigningKeyStore, TwoFACodeStore, UserStore,
+        WebauthnChallengeStore, WebauthnCredentialStore,
+    },
+    settings::Settings,
+    utils::{auth::JwtKeys, email_verification::EmailVerifier, password::Argon2Hasher},
+};
+
+// Using a type alias to improve readability!
+pub type UserStoreType = Arc<RwLock<dyn UserStore + Send + Sync>>;
+pub type BannedTokenStoreType = Arc<RwLock<dyn BannedTokenStore + Send + Sync>>;
+pub type TwoFACodeStoreType = Arc<RwLock<dyn TwoFACodeStore + Send + Sync>>;
+pub type RefreshTokenStoreType = Arc<RwLock<dyn RefreshTokenStore + Send + Sync>>;
+pub type SigningKeyStoreType = Arc<RwLock<dyn SigningKeyStore + Send + Sync>>;
+pub type WebauthnCredentialStoreType = Arc<RwLock<dyn WebauthnCredentialStore + Send + Sync>>;
+pub type WebauthnChallengeStoreType = Arc<RwLock<dyn WebauthnChallengeStore + Send + Sync>>;
+pub type RecoveryCodeStoreType = Arc<RwLock<dyn RecoveryCodeStore + Send + Sync>>;
+pub type PasswordResetTokenStoreType = Arc<RwLock<dyn PasswordResetTokenStore + Send + Sync>>;
+pub type JwtKeysType = Arc<RwLock<JwtKeys>>;
+pub type EmailClientType = Arc<dyn EmailClient + Send + Sync>;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub settings: Arc<Settings>,
+    // Connection pool shared by the Postgres-backed stores, if any are configured.
+    pub pg_pool: Option<PgPool>,
+    // Connection pool shared by the SQLite-backed stores, if any are configured.
+    pub sqlite_pool: Option<SqlitePool>,
+    pub user_store: UserStoreType,
+    pub banned_token_store: BannedTokenStoreType,
+    pub two_fa_code_store: TwoFACodeStoreType,
+    pub refresh_token_store: RefreshTokenStoreType,
+    pub signing_key_store: SigningKeyStoreType,
+    pub webauthn_credential_store: WebauthnCredentialStoreType,
+    pub webauthn_challenge_store: WebauthnChallengeStoreType,
+    pub recovery_code_store: RecoveryCodeStoreType,
+    pub password_reset_token_store: PasswordResetTokenStoreType,
+    pub email_client: EmailClientType,
+    pub password_hasher: Argon2Hasher,
+    pub email_verifier: EmailVerifier,
+    // Reloaded from `signing_key_store` in the background when keys are managed there.
+    pub jwt_keys: JwtKeysType,
+    pub email_policy: EmailDomainPolicy,
+    pub password_policy: PasswordPolicy,
+}
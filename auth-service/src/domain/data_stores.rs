@@ -0,0 +1,382 @@
+use rand::Rng;
+
+use super::{Email, User};
+
+#[async_trait::async_trait]
+pub trait UserStore {
+    async fn add_user(&mut self, user: User) -> Result<(), UserStoreError>;
+    async fn get_user(&self, email: &Email) -> Result<User, UserStoreError>;
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password_hash: String,
+    ) -> Result<(), UserStoreError>;
+    // Replace the stored record of `user.email` with `user`.
+    async fn update_user(&mut self, user: User) -> Result<(), UserStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UserStoreError {
+    UserAlreadyExists,
+    UserNotFound,
+    UnexpectedError,
+}
+
+// Stores the `jti` of revoked JWTs until the tokens themselves would have expired.
+#[async_trait::async_trait]
+pub trait BannedTokenStore {
+    async fn add_token(
+        &mut self,
+        jti: String,
+        expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError>;
+    async fn contains_token(&self, jti: &str) -> Result<bool, BannedTokenStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BannedTokenStoreError {
+    UnexpectedError,
+}
+
+// Server-side record of an issued refresh token. Only a hash of the opaque token is
+// kept. Every token rotated out of the same login shares its `family_id`, so a replay
+// of an old token can revoke the whole chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshToken {
+    pub token_hash: String,
+    pub family_id: String,
+    pub email: Email,
+    pub expires_at: u64,
+    pub used: bool,
+}
+
+#[async_trait::async_trait]
+pub trait RefreshTokenStore {
+    async fn add_token(&mut self, token: RefreshToken) -> Result<(), RefreshTokenStoreError>;
+    async fn get_token(&self, token_hash: &str) -> Result<RefreshToken, RefreshTokenStoreError>;
+    // Atomically marks the token as used and returns it as it was before,
+    // so a first use can be told apart from a replay.
+    async fn use_token(&mut self, token_hash: &str)
+        -> Result<RefreshToken, RefreshTokenStoreError>;
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError>;
+    // Ends every session of the user, e.g. after a password reset.
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RefreshTokenStoreError {
+    TokenNotFound,
+    UnexpectedError,
+}
+
+// Asymmetric key pair used to sign JWTs. Every stored key is published in the JWKS;
+// exactly one of them signs new tokens.
+#[derive(Clone, PartialEq)]
+pub struct SigningKey {
+    // RFC 7638 thumbprint of the public key, sent as the `kid` JWT header.
+    pub kid: String,
+    // `RS256` or `EdDSA`.
+    pub algorithm: String,
+    // PKCS#8 PEM encoded private key.
+    pub private_key: String,
+    pub status: SigningKeyStatus,
+    pub created_at: u64,
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("kid", &self.kid)
+            .field("algorithm", &self.algorithm)
+            .field("status", &self.status)
+            .field("created_at", &self.created_at)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningKeyStatus {
+    // Published for verification only: either generated ahead of its promotion, or
+    // demoted and kept until the tokens it signed have expired.
+    Standby,
+    // Signs new tokens.
+    Signing,
+}
+
+impl SigningKeyStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SigningKeyStatus::Standby => "standby",
+            SigningKeyStatus::Signing => "signing",
+        }
+    }
+
+    pub fn parse(status: &str) -> Result<Self, String> {
+        match status {
+            "standby" => Ok(SigningKeyStatus::Standby),
+            "signing" => Ok(SigningKeyStatus::Signing),
+            other => Err(format!("Unknown signing key status {:?}", other)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait SigningKeyStore {
+    async fn add_key(&mut self, key: SigningKey) -> Result<(), SigningKeyStoreError>;
+    // All keys, oldest first.
+    async fn get_keys(&self) -> Result<Vec<SigningKey>, SigningKeyStoreError>;
+    // Make the key the signing key, putting the current signing key on standby.
+    async fn promote_key(&mut self, kid: &str) -> Result<(), SigningKeyStoreError>;
+    // Stop publishing a standby key. The signing key can't be retired.
+    async fn retire_key(&mut self, kid: &str) -> Result<(), SigningKeyStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SigningKeyStoreError {
+    KeyNotFound,
+    KeyInUse,
+    UnexpectedError,
+}
+
+// Holds the pending 2FA code of a login attempt, keyed by the user's email.
+// A new login attempt replaces any previous pending code for the same user.
+#[async_trait::async_trait]
+pub trait TwoFACodeStore {
+    async fn add_code(
+        &mut self,
+        email: Email,
+        login_attempt_id: LoginAttemptId,
+        code: TwoFACode,
+        expires_at: u64,
+    ) -> Result<(), TwoFACodeStoreError>;
+    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError>;
+    async fn get_code(
+        &self,
+        email: &Email,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError>;
+    // Removes and returns the unexpired pending code, so every code is accepted at most once.
+    async fn take_code(
+        &mut self,
+        email: &Email,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError>;
+    // Counts a wrong code against the pending login attempt, returning how many
+    // there have been. A new code starts again from zero.
+    async fn record_failure(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TwoFACodeStoreError {
+    LoginAttemptIdNotFound,
+    UnexpectedError,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginAttemptId(String);
+
+impl LoginAttemptId {
+    pub fn parse(id: String) -> Result<Self, String> {
+        uuid::Uuid::parse_str(&id)
+            .map(|_| Self(id))
+            .map_err(|_| "Invalid login attempt id".to_owned())
+    }
+}
+
+impl Default for LoginAttemptId {
+    fn default() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+}
+
+impl AsRef<str> for LoginAttemptId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TwoFACode(String);
+
+impl TwoFACode {
+    pub fn parse(code: String) -> Result<Self, String> {
+        if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
+            Ok(Self(code))
+        } else {
+            Err("Invalid 2FA code".to_owned())
+        }
+    }
+}
+
+impl Default for TwoFACode {
+    fn default() -> Self {
+        // Use a cryptographically secure RNG, the code is a login credential.
+        Self(format!("{:06}", rand::thread_rng().gen_range(0..1_000_000)))
+    }
+}
+
+impl AsRef<str> for TwoFACode {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// Public key credential (a passkey or security key) registered by a user for WebAuthn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebauthnCredential {
+    // Base64url encoded id chosen by the authenticator.
+    pub credential_id: String,
+    pub email: Email,
+    // Base64url encoded COSE key the authenticator signs assertions with.
+    pub public_key: String,
+    // Signature counter of the last accepted assertion, 0 if the authenticator has none.
+    pub sign_count: u32,
+    pub created_at: u64,
+}
+
+#[async_trait::async_trait]
+pub trait WebauthnCredentialStore {
+    async fn add_credential(
+        &mut self,
+        credential: WebauthnCredential,
+    ) -> Result<(), WebauthnCredentialStoreError>;
+    async fn get_credential(
+        &self,
+        credential_id: &str,
+    ) -> Result<WebauthnCredential, WebauthnCredentialStoreError>;
+    // All credentials of the user, oldest first.
+    async fn get_credentials(
+        &self,
+        email: &Email,
+    ) -> Result<Vec<WebauthnCredential>, WebauthnCredentialStoreError>;
+    async fn update_sign_count(
+        &mut self,
+        credential_id: &str,
+        sign_count: u32,
+    ) -> Result<(), WebauthnCredentialStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WebauthnCredentialStoreError {
+    CredentialAlreadyExists,
+    CredentialNotFound,
+    UnexpectedError,
+}
+
+// Random challenge handed out for one WebAuthn ceremony. It is looked up by the
+// challenge itself, which the authenticator echoes back in its client data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebauthnChallenge {
+    // Base64url encoded.
+    pub challenge: String,
+    pub ceremony: WebauthnCeremony,
+    // The user the ceremony is for; `None` for a passwordless login that lets the
+    // authenticator pick one of its discoverable credentials.
+    pub email: Option<Email>,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebauthnCeremony {
+    // Registering a new credential for a signed-in user.
+    Registration,
+    // Completing a password login with `/verify-2fa`.
+    SecondFactor,
+    // Signing in with the credential alone.
+    Login,
+}
+
+impl WebauthnCeremony {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebauthnCeremony::Registration => "registration",
+            WebauthnCeremony::SecondFactor => "second_factor",
+            WebauthnCeremony::Login => "login",
+        }
+    }
+
+    pub fn parse(ceremony: &str) -> Result<Self, String> {
+        match ceremony {
+            "registration" => Ok(WebauthnCeremony::Registration),
+            "second_factor" => Ok(WebauthnCeremony::SecondFactor),
+            "login" => Ok(WebauthnCeremony::Login),
+            other => Err(format!("Unknown WebAuthn ceremony {:?}", other)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait WebauthnChallengeStore {
+    async fn add_challenge(
+        &mut self,
+        challenge: WebauthnChallenge,
+    ) -> Result<(), WebauthnChallengeStoreError>;
+    // Removes and returns an unexpired challenge, so every challenge is answered at most once.
+    async fn take_challenge(
+        &mut self,
+        challenge: &str,
+    ) -> Result<WebauthnChallenge, WebauthnChallengeStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WebauthnChallengeStoreError {
+    ChallengeNotFound,
+    UnexpectedError,
+}
+
+// Single-use codes that stand in for the second factor when the user has lost access
+// to it. Only hashes are stored; the codes themselves are shown once when generated.
+#[async_trait::async_trait]
+pub trait RecoveryCodeStore {
+    // Replaces all of the user's codes, so a regenerated set invalidates the old one.
+    async fn replace_codes(
+        &mut self,
+        email: &Email,
+        code_hashes: Vec<String>,
+    ) -> Result<(), RecoveryCodeStoreError>;
+    // Removes the code, so each one is accepted at most once.
+    async fn use_code(
+        &mut self,
+        email: &Email,
+        code_hash: &str,
+    ) -> Result<(), RecoveryCodeStoreError>;
+    async fn count_codes(&self, email: &Email) -> Result<usize, RecoveryCodeStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RecoveryCodeStoreError {
+    CodeNotFound,
+    UnexpectedError,
+}
+
+// Pending password reset, keyed by a hash of the token emailed to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordResetToken {
+    pub token_hash: String,
+    pub email: Email,
+    pub expires_at: u64,
+}
+
+#[async_trait::async_trait]
+pub trait PasswordResetTokenStore {
+    async fn add_token(
+        &mut self,
+        token: PasswordResetToken,
+    ) -> Result<(), PasswordResetTokenStoreError>;
+    // Returns an unexpired token without using it up.
+    async fn get_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<PasswordResetToken, PasswordResetTokenStoreError>;
+    // Removes and returns an unexpired token, so every token resets a password at most once.
+    async fn take_token(
+        &mut self,
+        token_hash: &str,
+    ) -> Result<PasswordResetToken, PasswordResetTokenStoreError>;
+    // Removes every pending token of the user, once one of them was used.
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), PasswordResetTokenStoreError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PasswordResetTokenStoreError {
+    TokenNotFound,
+    UnexpectedError,
+}
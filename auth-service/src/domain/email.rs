@@ -0,0 +1,347 @@
+use std::{collections::HashSet, fmt};
+
+use serde::{Deserialize, Serialize};
+
+// RFC 5321 limits: 64 octets for the local part, 255 for the domain and a path
+// of 256 octets including the angle brackets, which leaves 254 for the address.
+const MAX_EMAIL_LENGTH: usize = 254;
+const MAX_LOCAL_PART_LENGTH: usize = 64;
+
+// An email address that has passed validation. The only way to obtain one is
+// through `Email::parse`, including when deserializing a stored user.
+//
+// The domain is normalized to its lower-case ASCII (punycode) form, so
+// `User@Bücher.DE` and `User@xn--bcher-kva.de` are the same address. The local
+// part is kept as-is, since RFC 5321 leaves its case significance to the host.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Email(String);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmailError {
+    Empty,
+    InvalidFormat,
+    DomainNotPermitted,
+}
+
+impl fmt::Display for EmailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmailError::Empty => write!(f, "Email cannot be empty"),
+            EmailError::InvalidFormat => write!(f, "Invalid email format"),
+            EmailError::DomainNotPermitted => write!(f, "Email domain is not permitted"),
+        }
+    }
+}
+
+impl Email {
+    pub fn parse(email: String) -> Result<Self, EmailError> {
+        if email.is_empty() {
+            return Err(EmailError::Empty);
+        }
+
+        // The local part may itself contain '@' inside a quoted string,
+        // so the domain starts after the last one.
+        let (local_part, domain) = email.rsplit_once('@').ok_or(EmailError::InvalidFormat)?;
+
+        if !is_valid_local_part(local_part) {
+            return Err(EmailError::InvalidFormat);
+        }
+
+        let domain = normalize_domain(domain).ok_or(EmailError::InvalidFormat)?;
+
+        let email = format!("{}@{}", local_part, domain);
+        if email.len() > MAX_EMAIL_LENGTH {
+            return Err(EmailError::InvalidFormat);
+        }
+
+        Ok(Self(email))
+    }
+
+    // The normalized (lower-case, punycode) domain part.
+    pub fn domain(&self) -> &str {
+        self.0
+            .rsplit_once('@')
+            .map(|(_, domain)| domain)
+            .unwrap_or_default()
+    }
+}
+
+// Accepts an RFC 5322 dot-atom (with the RFC 6532 extension allowing non-ASCII
+// characters) or a quoted string.
+fn is_valid_local_part(local_part: &str) -> bool {
+    if local_part.is_empty() || local_part.len() > MAX_LOCAL_PART_LENGTH {
+        return false;
+    }
+
+    if let Some(quoted) = local_part
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        return is_valid_quoted_string(quoted);
+    }
+
+    local_part
+        .split('.')
+        .all(|atom| !atom.is_empty() && atom.chars().all(is_atext))
+}
+
+fn is_atext(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+        || "!#$%&'*+-/=?^_`{|}~".contains(c)
+        || !c.is_ascii() && !c.is_control()
+}
+
+fn is_valid_quoted_string(quoted: &str) -> bool {
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // quoted-pair: a backslash escapes any printable character or space
+            '\\' => match chars.next() {
+                Some(escaped) if escaped == ' ' || escaped.is_ascii_graphic() => {}
+                _ => return false,
+            },
+            '"' => return false,
+            c if c == ' ' || c.is_ascii_graphic() || !c.is_ascii() && !c.is_control() => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+// Converts the domain to its ASCII form (IDNA/UTS #46) and checks it is a valid
+// host name with at least two labels. Address literals such as `[127.0.0.1]`
+// are not accepted for account emails.
+fn normalize_domain(domain: &str) -> Option<String> {
+    let ascii = idna::domain_to_ascii_strict(domain).ok()?;
+
+    let labels: Vec<&str> = ascii.split('.').collect();
+    if labels.len() < 2 {
+        return None;
+    }
+
+    let valid_labels = labels.iter().all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+
+    // A purely numeric top-level label would make the domain look like an IP address.
+    let tld = labels.last()?;
+    if !valid_labels || tld.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(ascii)
+}
+
+impl AsRef<str> for Email {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl TryFrom<String> for Email {
+    type Error = EmailError;
+
+    fn try_from(email: String) -> Result<Self, Self::Error> {
+        Self::parse(email)
+    }
+}
+
+impl From<Email> for String {
+    fn from(email: Email) -> Self {
+        email.0
+    }
+}
+
+// Decides which email domains may be used to sign up. A domain entry also covers
+// its subdomains. An empty allow list permits every domain that isn't denied.
+#[derive(Debug, Clone, Default)]
+pub struct EmailDomainPolicy {
+    allowed_domains: HashSet<String>,
+    denied_domains: HashSet<String>,
+}
+
+fn normalize_domains(
+    domains: impl IntoIterator<Item = String>,
+) -> Result<HashSet<String>, EmailError> {
+    domains
+        .into_iter()
+        .map(|domain| normalize_domain(&domain).ok_or(EmailError::InvalidFormat))
+        .collect()
+}
+
+impl EmailDomainPolicy {
+    pub fn new<I, J>(allowed_domains: I, denied_domains: J) -> Result<Self, EmailError>
+    where
+        I: IntoIterator<Item = String>,
+        J: IntoIterator<Item = String>,
+    {
+        Ok(Self {
+            allowed_domains: normalize_domains(allowed_domains)?,
+            denied_domains: normalize_domains(denied_domains)?,
+        })
+    }
+
+    pub fn check(&self, email: &Email) -> Result<(), EmailError> {
+        let domain = email.domain();
+
+        let matches = |domains: &HashSet<String>| {
+            domains.iter().any(|entry| {
+                domain == entry
+                    || domain
+                        .strip_suffix(entry.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            })
+        };
+
+        if matches(&self.denied_domains) {
+            return Err(EmailError::DomainNotPermitted);
+        }
+
+        if !self.allowed_domains.is_empty() && !matches(&self.allowed_domains) {
+            return Err(EmailError::DomainNotPermitted);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(email: &str) -> Result<Email, EmailError> {
+        Email::parse(email.to_owned())
+    }
+
+    #[test]
+    fn test_valid_emails_are_parsed() {
+        for email in [
+            "test@example.com",
+            "test.user+tag@example.com.co",
+            "user@domain.org",
+            "first_last@sub.example.io",
+            "x@example.de",
+            "student@university.edu",
+            "qSdy6asQjL8siKA@m.fegl",
+            "o'brien@example.ie",
+            "\"john doe\"@example.com",
+            "\"a@b\"@example.com",
+            "jörg@example.de",
+        ] {
+            assert_eq!(parse(email).unwrap().as_ref(), email);
+        }
+    }
+
+    #[test]
+    fn test_invalid_emails_are_rejected() {
+        assert_eq!(parse(""), Err(EmailError::Empty));
+
+        let long_local_part = format!("{}@example.com", "a".repeat(65));
+        let long_email = format!("user@{}.com", vec!["a".repeat(63); 4].join("."));
+
+        for email in [
+            "invalidemail",
+            "test@",
+            "@example.com",
+            "test@@example.com",
+            "test@example",
+            "test@example.com.",
+            "test@.example.com",
+            ".test@example.com",
+            "test.@example.com",
+            "te..st@example.com",
+            "te st@example.com",
+            "test@exa mple.com",
+            "test@-example.com",
+            "test@example-.com",
+            "test@exam_ple.com",
+            "test@127.0.0.1",
+            "test@[127.0.0.1]",
+            "\"unterminated@example.com",
+            long_local_part.as_str(),
+            long_email.as_str(),
+        ] {
+            assert_eq!(
+                parse(email),
+                Err(EmailError::InvalidFormat),
+                "accepted {}",
+                email
+            );
+        }
+    }
+
+    #[test]
+    fn test_domain_is_normalized() {
+        assert_eq!(
+            parse("User@Example.COM").unwrap().as_ref(),
+            "User@example.com"
+        );
+        assert_eq!(
+            parse("user@Bücher.de").unwrap().as_ref(),
+            "user@xn--bcher-kva.de"
+        );
+        assert_eq!(
+            parse("user@bücher.de").unwrap(),
+            parse("user@xn--bcher-kva.de").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_deserialize_validates() {
+        assert!(serde_json::from_str::<Email>("\"test@example.com\"").is_ok());
+        assert!(serde_json::from_str::<Email>("\"invalidemail\"").is_err());
+    }
+
+    #[test]
+    fn test_domain_policy_deny_list() {
+        let policy = EmailDomainPolicy::new(vec![], vec!["Mailinator.com".to_owned()]).unwrap();
+
+        assert_eq!(policy.check(&parse("a@example.com").unwrap()), Ok(()));
+        assert_eq!(
+            policy.check(&parse("a@mailinator.com").unwrap()),
+            Err(EmailError::DomainNotPermitted)
+        );
+        assert_eq!(
+            policy.check(&parse("a@eu.mailinator.com").unwrap()),
+            Err(EmailError::DomainNotPermitted)
+        );
+        assert_eq!(policy.check(&parse("a@notmailinator.com").unwrap()), Ok(()));
+    }
+
+    #[test]
+    fn test_domain_policy_allow_list() {
+        let policy = EmailDomainPolicy::new(
+            vec!["example.org".to_owned(), "bücher.de".to_owned()],
+            vec!["old.example.org".to_owned()],
+        )
+        .unwrap();
+
+        assert_eq!(policy.check(&parse("a@example.org").unwrap()), Ok(()));
+        assert_eq!(policy.check(&parse("a@dev.example.org").unwrap()), Ok(()));
+        assert_eq!(policy.check(&parse("a@xn--bcher-kva.de").unwrap()), Ok(()));
+        assert_eq!(
+            policy.check(&parse("a@old.example.org").unwrap()),
+            Err(EmailError::DomainNotPermitted)
+        );
+        assert_eq!(
+            policy.check(&parse("a@example.com").unwrap()),
+            Err(EmailError::DomainNotPermitted)
+        );
+    }
+
+    #[test]
+    fn test_domain_policy_rejects_invalid_entries() {
+        assert!(EmailDomainPolicy::new(vec!["not a domain".to_owned()], vec![]).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+use super::Email;
+
+#[async_trait::async_trait]
+pub trait EmailClient {
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), EmailClientError>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EmailClientError {
+    InvalidAddress(String),
+    DeliveryFailed(String),
+}
@@ -0,0 +1,39 @@
+use super::{EmailError, PasswordError, PasswordPolicyViolation, UserStoreError};
+
+// Every way a request to the API can fail. Handlers return this type and the
+// conversion into an HTTP response (status code and JSON body) lives in one place.
+#[derive(Debug)]
+pub enum AuthAPIError {
+    InvalidInput(String),
+    PasswordPolicyViolation(Vec<PasswordPolicyViolation>),
+    MalformedBody(String),
+    UserAlreadyExists,
+    IncorrectCredentials,
+    // Correct credentials, but the link from the verification email wasn't followed yet.
+    EmailNotVerified,
+    MissingToken,
+    InvalidToken,
+    UnexpectedError,
+}
+
+impl From<EmailError> for AuthAPIError {
+    fn from(e: EmailError) -> Self {
+        AuthAPIError::InvalidInput(e.to_string())
+    }
+}
+
+impl From<PasswordError> for AuthAPIError {
+    fn from(e: PasswordError) -> Self {
+        AuthAPIError::InvalidInput(e.to_string())
+    }
+}
+
+impl From<UserStoreError> for AuthAPIError {
+    fn from(e: UserStoreError) -> Self {
+        match e {
+            UserStoreError::UserAlreadyExists => AuthAPIError::UserAlreadyExists,
+            UserStoreError::UserNotFound => AuthAPIError::IncorrectCredentials,
+            UserStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+        }
+    }
+}
@@ -0,0 +1,16 @@
+mod data_stores;
+mod email;
+mod email_client;
+mod error;
+mod password;
+mod password_policy;
+mod user;
+
+// re-export items from sub-modules
+pub use data_stores::*;
+pub use email::*;
+pub use email_client::*;
+pub use error::*;
+pub use password::*;
+pub use password_policy::*;
+pub use user::*;
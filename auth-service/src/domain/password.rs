@@ -0,0 +1,58 @@
+use std::fmt;
+
+// A plaintext password as submitted by a user. It never leaves the process:
+// it only exists long enough to be hashed or verified, and is redacted from `Debug`.
+#[derive(Clone, PartialEq)]
+pub struct Password(String);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PasswordError {
+    Empty,
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordError::Empty => write!(f, "Password cannot be empty"),
+        }
+    }
+}
+
+impl Password {
+    pub fn parse(password: String) -> Result<Self, PasswordError> {
+        if password.is_empty() {
+            return Err(PasswordError::Empty);
+        }
+
+        Ok(Self(password))
+    }
+}
+
+impl AsRef<str> for Password {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Password(\"********\")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert!(Password::parse("password123".to_owned()).is_ok());
+        assert_eq!(Password::parse(String::new()), Err(PasswordError::Empty));
+    }
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let password = Password::parse("password123".to_owned()).unwrap();
+        assert!(!format!("{:?}", password).contains("password123"));
+    }
+}
@@ -0,0 +1,301 @@
+use std::{collections::HashSet, fmt, path::Path, sync::Arc};
+
+use super::{Email, Password};
+
+// Rules a new password must satisfy at signup or when it is changed.
+// Existing passwords are never re-checked at login.
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub forbid_email_local_part: bool,
+    blocklist: Arc<HashSet<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordPolicyViolation {
+    TooShort,
+    TooLong,
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSymbol,
+    CommonPassword,
+    ContainsEmail,
+}
+
+impl PasswordPolicyViolation {
+    // Stable identifier returned to API clients.
+    pub fn code(&self) -> &'static str {
+        match self {
+            PasswordPolicyViolation::TooShort => "password_too_short",
+            PasswordPolicyViolation::TooLong => "password_too_long",
+            PasswordPolicyViolation::MissingLowercase => "password_missing_lowercase",
+            PasswordPolicyViolation::MissingUppercase => "password_missing_uppercase",
+            PasswordPolicyViolation::MissingDigit => "password_missing_digit",
+            PasswordPolicyViolation::MissingSymbol => "password_missing_symbol",
+            PasswordPolicyViolation::CommonPassword => "password_too_common",
+            PasswordPolicyViolation::ContainsEmail => "password_contains_email",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PasswordPolicyConfigError {
+    InvalidLengthRange {
+        min_length: usize,
+        max_length: usize,
+    },
+    UnreadableBlocklist(String),
+}
+
+impl fmt::Display for PasswordPolicyConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordPolicyConfigError::InvalidLengthRange {
+                min_length,
+                max_length,
+            } => write!(
+                f,
+                "minimum password length {} exceeds maximum {}",
+                min_length, max_length
+            ),
+            PasswordPolicyConfigError::UnreadableBlocklist(path) => {
+                write!(f, "cannot read password blocklist {}", path)
+            }
+        }
+    }
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            forbid_email_local_part: true,
+            blocklist: Arc::new(HashSet::new()),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn validated(self) -> Result<Self, PasswordPolicyConfigError> {
+        if self.min_length > self.max_length {
+            return Err(PasswordPolicyConfigError::InvalidLengthRange {
+                min_length: self.min_length,
+                max_length: self.max_length,
+            });
+        }
+        Ok(self)
+    }
+
+    pub fn with_blocklist<I: IntoIterator<Item = String>>(mut self, passwords: I) -> Self {
+        self.blocklist = Arc::new(
+            passwords
+                .into_iter()
+                .map(|password| password.trim().to_lowercase())
+                .filter(|password| !password.is_empty())
+                .collect(),
+        );
+        self
+    }
+
+    // Blank lines and lines starting with '#' are ignored.
+    pub fn with_blocklist_file(
+        self,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, PasswordPolicyConfigError> {
+        let contents = std::fs::read_to_string(path.as_ref()).map_err(|_| {
+            PasswordPolicyConfigError::UnreadableBlocklist(path.as_ref().display().to_string())
+        })?;
+
+        let passwords = contents
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+
+        Ok(self.with_blocklist(passwords))
+    }
+
+    // Returns every rule the password breaks, not just the first one,
+    // so the user can fix them all at once.
+    pub fn check(
+        &self,
+        password: &Password,
+        email: &Email,
+    ) -> Result<(), Vec<PasswordPolicyViolation>> {
+        let password = password.as_ref();
+        let length = password.chars().count();
+        let mut violations = Vec::new();
+
+        if length < self.min_length {
+            violations.push(PasswordPolicyViolation::TooShort);
+        }
+        if length > self.max_length {
+            violations.push(PasswordPolicyViolation::TooLong);
+        }
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            violations.push(PasswordPolicyViolation::MissingLowercase);
+        }
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            violations.push(PasswordPolicyViolation::MissingUppercase);
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push(PasswordPolicyViolation::MissingDigit);
+        }
+        if self.require_symbol && password.chars().all(char::is_alphanumeric) {
+            violations.push(PasswordPolicyViolation::MissingSymbol);
+        }
+        if self.blocklist.contains(&password.to_lowercase()) {
+            violations.push(PasswordPolicyViolation::CommonPassword);
+        }
+        if self.forbid_email_local_part && contains_local_part(password, email) {
+            violations.push(PasswordPolicyViolation::ContainsEmail);
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+// Very short local parts (e.g. `a@example.com`) would match almost any password,
+// so they are ignored.
+fn contains_local_part(password: &str, email: &Email) -> bool {
+    let local_part = email
+        .as_ref()
+        .rsplit_once('@')
+        .map(|(local_part, _)| local_part.trim_matches('"').to_lowercase())
+        .unwrap_or_default();
+
+    local_part.chars().count() >= 3 && password.to_lowercase().contains(&local_part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(policy: &PasswordPolicy, password: &str) -> Result<(), Vec<PasswordPolicyViolation>> {
+        policy.check(
+            &Password::parse(password.to_owned()).unwrap(),
+            &Email::parse("jane.doe@example.com".to_owned()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_default_policy() {
+        let policy = PasswordPolicy::default();
+
+        assert_eq!(check(&policy, "password123"), Ok(()));
+        assert_eq!(
+            check(&policy, "short"),
+            Err(vec![PasswordPolicyViolation::TooShort])
+        );
+        assert_eq!(
+            check(&policy, &"a".repeat(129)),
+            Err(vec![PasswordPolicyViolation::TooLong])
+        );
+    }
+
+    #[test]
+    fn test_character_classes() {
+        let policy = PasswordPolicy {
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            ..PasswordPolicy::default()
+        };
+
+        assert_eq!(check(&policy, "Str0ng!pass"), Ok(()));
+        assert_eq!(
+            check(&policy, "weakpass"),
+            Err(vec![
+                PasswordPolicyViolation::MissingUppercase,
+                PasswordPolicyViolation::MissingDigit,
+                PasswordPolicyViolation::MissingSymbol,
+            ])
+        );
+        assert_eq!(
+            check(&policy, "WEAK"),
+            Err(vec![
+                PasswordPolicyViolation::TooShort,
+                PasswordPolicyViolation::MissingLowercase,
+                PasswordPolicyViolation::MissingDigit,
+                PasswordPolicyViolation::MissingSymbol,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_blocklist_is_case_insensitive() {
+        let policy = PasswordPolicy::default().with_blocklist(vec!["Qwerty123".to_owned()]);
+
+        assert_eq!(
+            check(&policy, "QWERTY123"),
+            Err(vec![PasswordPolicyViolation::CommonPassword])
+        );
+        assert_eq!(check(&policy, "qwerty1234"), Ok(()));
+    }
+
+    #[test]
+    fn test_blocklist_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blocklist.txt");
+        std::fs::write(&path, "# common passwords\nletmein123\n\niloveyou\n").unwrap();
+
+        let policy = PasswordPolicy::default()
+            .with_blocklist_file(&path)
+            .unwrap();
+
+        assert_eq!(
+            check(&policy, "letmein123"),
+            Err(vec![PasswordPolicyViolation::CommonPassword])
+        );
+        assert_eq!(
+            check(&policy, "iloveyou"),
+            Err(vec![PasswordPolicyViolation::CommonPassword])
+        );
+        assert!(PasswordPolicy::default()
+            .with_blocklist_file(dir.path().join("missing.txt"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_password_containing_email_local_part() {
+        let policy = PasswordPolicy::default();
+
+        assert_eq!(
+            check(&policy, "Jane.Doe2024"),
+            Err(vec![PasswordPolicyViolation::ContainsEmail])
+        );
+
+        let relaxed = PasswordPolicy {
+            forbid_email_local_part: false,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(check(&relaxed, "Jane.Doe2024"), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_length_range() {
+        let policy = PasswordPolicy {
+            min_length: 20,
+            max_length: 10,
+            ..PasswordPolicy::default()
+        };
+
+        assert!(policy.validated().is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+use super::Email;
+
+// The password is only ever kept as an Argon2id PHC string, never in plaintext.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    pub email: Email,
+    pub password_hash: String,
+    pub requires_2fa: bool,
+    // How the second factor is checked when `requires_2fa` is set.
+    #[serde(default)]
+    pub two_fa_method: TwoFAMethod,
+    // Base32 secret of the user's confirmed authenticator app.
+    #[serde(default)]
+    pub totp_secret: Option<String>,
+    // Secret handed out by the latest enrollment, until a valid code confirms it.
+    #[serde(default)]
+    pub pending_totp_secret: Option<String>,
+    // Time step of the last accepted TOTP code. Codes of this or earlier steps are
+    // rejected, so an observed code can't be replayed (RFC 6238 section 5.2).
+    #[serde(default)]
+    pub totp_last_step: Option<u64>,
+    // New accounts can't log in until the link from the verification email was
+    // followed. Accounts stored before verification existed count as verified.
+    #[serde(default = "verified_by_default")]
+    pub email_verified: bool,
+    // When the last verification email went out, to throttle resending it.
+    #[serde(default)]
+    pub verification_email_sent_at: Option<u64>,
+    // Auth tokens carry the generation they were issued in. Bumping it, e.g. by a
+    // password reset, rejects every token issued before.
+    #[serde(default)]
+    pub session_generation: u64,
+}
+
+fn verified_by_default() -> bool {
+    true
+}
+
+impl User {
+    pub fn new(email: Email, password_hash: String, requires_2fa: bool) -> Self {
+        Self {
+            email,
+            password_hash,
+            requires_2fa,
+            two_fa_method: TwoFAMethod::default(),
+            totp_secret: None,
+            pending_totp_secret: None,
+            totp_last_step: None,
+            email_verified: false,
+            verification_email_sent_at: None,
+            session_generation: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TwoFAMethod {
+    // A one-time code sent by email at login.
+    #[default]
+    Email,
+    // A code from an authenticator app (RFC 6238).
+    Totp,
+    // An assertion from a registered WebAuthn credential (passkey or security key).
+    Webauthn,
+}
+
+impl TwoFAMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TwoFAMethod::Email => "email",
+            TwoFAMethod::Totp => "totp",
+            TwoFAMethod::Webauthn => "webauthn",
+        }
+    }
+
+    pub fn parse(method: &str) -> Result<Self, String> {
+        match method {
+            "email" => Ok(TwoFAMethod::Email),
+            "totp" => Ok(TwoFAMethod::Totp),
+            "webauthn" => Ok(TwoFAMethod::Webauthn),
+            other => Err(format!("Unknown 2FA method {:?}", other)),
+        }
+    }
+}
@@ -1,9 +1,35 @@
-use axum::{routing::post, serve::Serve, Router};
-use std::error::Error;
+use app_state::AppState;
+use axum::{
+    http::{header, Method, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    serve::Serve,
+    Json, Router,
+};
+use domain::AuthAPIError;
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use settings::{DatabaseSettings, SqliteSettings};
+use sqlx::{
+    migrate::Migrator,
+    postgres::PgPoolOptions,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
+    PgPool, SqlitePool,
+};
+use std::{error::Error, path::Path, time::Duration};
 use tokio::net::TcpListener;
-use tower_http::services::ServeDir;
+use tower_http::{cors::CorsLayer, services::ServeDir};
 
+pub mod app_state;
+pub mod domain;
 pub mod routes;
+pub mod services;
+pub mod settings;
+pub mod utils;
+
+// Versioned schema migrations, embedded into the binary at compile time.
+pub static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
 
 // This struct encapsulates our application-related logic.
 pub struct Application {
@@ -14,16 +40,62 @@ pub struct Application {
 }
 
 impl Application {
-    pub async fn build(address: &str) -> Result<Self, Box<dyn Error>> {
+    pub async fn build(app_state: AppState, address: &str) -> Result<Self, Box<dyn Error>> {
         // Create the router with the fallback service for static assets
         let assets_dir = ServeDir::new("assets");
+
+        // Browsers only send the auth cookie cross-origin to explicitly allowed origins.
+        let cors = CorsLayer::new()
+            .allow_origin(app_state.settings.cors.header_values()?)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([header::CONTENT_TYPE])
+            .allow_credentials(true);
+
         let router = Router::new()
             .route("/signup", post(routes::signup))
             .route("/login", post(routes::login))
             .route("/logout", post(routes::logout))
+            .route("/refresh", post(routes::refresh))
             .route("/verify-2fa", post(routes::verify_2fa))
+            .route("/verify-email", get(routes::verify_email))
+            .route(
+                "/verify-email/resend",
+                post(routes::resend_verification_email),
+            )
+            .route(
+                "/password-reset/request",
+                post(routes::request_password_reset),
+            )
+            .route(
+                "/password-reset/confirm",
+                post(routes::confirm_password_reset),
+            )
+            .route("/2fa/method", post(routes::set_two_fa_method))
+            .route("/2fa/totp/enroll", post(routes::enroll_totp))
+            .route("/2fa/totp/confirm", post(routes::confirm_totp))
+            .route("/2fa/recovery-codes", get(routes::count_recovery_codes))
+            .route(
+                "/2fa/recovery-codes/regenerate",
+                post(routes::regenerate_recovery_codes),
+            )
+            .route(
+                "/webauthn/register/start",
+                post(routes::start_webauthn_registration),
+            )
+            .route(
+                "/webauthn/register/finish",
+                post(routes::finish_webauthn_registration),
+            )
+            .route("/webauthn/login/start", post(routes::start_webauthn_login))
+            .route(
+                "/webauthn/login/finish",
+                post(routes::finish_webauthn_login),
+            )
             .route("/verify-token", post(routes::verify_token))
-            .fallback_service(assets_dir);
+            .route("/.well-known/jwks.json", get(routes::jwks))
+            .fallback_service(assets_dir)
+            .with_state(app_state)
+            .layer(cors);
 
         let listener = tokio::net::TcpListener::bind(address).await?;
         let address = listener.local_addr()?.to_string();
@@ -38,3 +110,114 @@ impl Application {
         self.server.await
     }
 }
+
+// Both pool constructors bring the database schema up to date, so stores can be
+// used (e.g. to load the signing keys) before the server starts.
+pub async fn get_postgres_pool(
+    url: &str,
+    settings: &DatabaseSettings,
+) -> Result<PgPool, sqlx::Error> {
+    let pool = PgPoolOptions::new()
+        .max_connections(settings.max_connections)
+        .min_connections(settings.min_connections)
+        .acquire_timeout(Duration::from_secs(settings.acquire_timeout_seconds))
+        .connect(url)
+        .await?;
+
+    POSTGRES_MIGRATOR.run(&pool).await?;
+    Ok(pool)
+}
+
+// WAL mode lets readers proceed while a write is in progress, which matters because
+// every request may touch the database. `NORMAL` sync is durable across application
+// crashes and only risks the last transactions on power loss.
+pub async fn get_sqlite_pool(
+    path: &Path,
+    settings: &SqliteSettings,
+) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(Duration::from_secs(settings.busy_timeout_seconds))
+        .foreign_keys(true);
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(settings.max_connections)
+        .connect_with(options)
+        .await?;
+
+    SQLITE_MIGRATOR.run(&pool).await?;
+    Ok(pool)
+}
+
+// The connection manager reconnects on its own and is cheap to clone,
+// so every Redis-backed store shares one.
+pub async fn get_redis_connection(url: &str) -> redis::RedisResult<ConnectionManager> {
+    redis::Client::open(url)?.get_connection_manager().await
+}
+
+// JSON body of every error response, as described in `api_schema.yml`.
+// `codes` is only present for password policy violations.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub codes: Vec<String>,
+}
+
+impl IntoResponse for AuthAPIError {
+    fn into_response(self) -> Response {
+        let (status, error_message, codes) = match self {
+            AuthAPIError::InvalidInput(message) => (StatusCode::BAD_REQUEST, message, vec![]),
+            AuthAPIError::PasswordPolicyViolation(violations) => (
+                StatusCode::BAD_REQUEST,
+                "Password does not meet the password policy".to_owned(),
+                violations
+                    .iter()
+                    .map(|violation| violation.code().to_owned())
+                    .collect(),
+            ),
+            AuthAPIError::MalformedBody(message) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, message, vec![])
+            }
+            AuthAPIError::UserAlreadyExists => (
+                StatusCode::CONFLICT,
+                "User already exists".to_owned(),
+                vec![],
+            ),
+            AuthAPIError::IncorrectCredentials => (
+                StatusCode::UNAUTHORIZED,
+                "Incorrect credentials".to_owned(),
+                vec![],
+            ),
+            AuthAPIError::EmailNotVerified => (
+                StatusCode::FORBIDDEN,
+                "Email address not verified".to_owned(),
+                vec![],
+            ),
+            AuthAPIError::MissingToken => (
+                StatusCode::BAD_REQUEST,
+                "Missing auth token".to_owned(),
+                vec![],
+            ),
+            AuthAPIError::InvalidToken => (
+                StatusCode::UNAUTHORIZED,
+                "Invalid auth token".to_owned(),
+                vec![],
+            ),
+            AuthAPIError::UnexpectedError => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Unexpected error".to_owned(),
+                vec![],
+            ),
+        };
+
+        let body = Json(ErrorResponse {
+            error: error_message,
+            codes,
+        });
+        (status, body).into_response()
+    }
+}
@@ -1,10 +1,341 @@
-use auth_service::Application;
+use std::{env, process, sync::Arc, time::Duration};
+
+use auth_service::{
+    app_state::{
+        AppState, BannedTokenStoreType, EmailClientType, PasswordResetTokenStoreType,
+        RecoveryCodeStoreType, RefreshTokenStoreType, SigningKeyStoreType, TwoFACodeStoreType,
+        UserStoreType, WebauthnChallengeStoreType, WebauthnCredentialStoreType,
+    },
+    domain::SigningKeyStoreError,
+    get_postgres_pool, get_redis_connection, get_sqlite_pool,
+    services::{
+        FileBannedTokenStore, FileEmailClient, FileUserStore, HashmapBannedTokenStore,
+        HashmapPasswordResetTokenStore, HashmapRecoveryCodeStore, HashmapRefreshTokenStore,
+        HashmapSigningKeyStore, HashmapTwoFACodeStore, HashmapUserStore,
+        HashmapWebauthnChallengeStore, HashmapWebauthnCredentialStore, LogEmailClient,
+        PostgresRecoveryCodeStore, PostgresRefreshTokenStore, PostgresSigningKeyStore,
+        PostgresUserStore, PostgresWebauthnCredentialStore, RedisBannedTokenStore,
+        RedisPasswordResetTokenStore, RedisTwoFACodeStore, RedisWebauthnChallengeStore,
+        SmtpEmailClient, SqliteBannedTokenStore, SqlitePasswordResetTokenStore,
+        SqliteRecoveryCodeStore, SqliteRefreshTokenStore, SqliteSigningKeyStore,
+        SqliteTwoFACodeStore, SqliteUserStore, SqliteWebauthnChallengeStore,
+        SqliteWebauthnCredentialStore,
+    },
+    settings::{EmailBackend, EmailSettings, Settings, SettingsError, StoreBackend},
+    utils::auth::{generate_signing_key, load_jwt_keys, reload_jwt_keys},
+    Application,
+};
+use tokio::sync::RwLock;
 
 #[tokio::main]
 async fn main() {
-    let app = Application::build("0.0.0.0:3000")
+    let settings = Settings::load().unwrap_or_else(|e| exit_with(e));
+    let app_state = build_app_state(settings)
+        .await
+        .unwrap_or_else(|e| exit_with(e));
+
+    // `auth-service keys ...` manages the signing keys instead of serving.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some((command, rest)) = args.split_first() {
+        if command == "keys" {
+            run_keys_command(&app_state, rest).await;
+            return;
+        }
+    }
+
+    if app_state.settings.stores.signing_keys != StoreBackend::Memory {
+        spawn_key_reloader(&app_state);
+    }
+    let address = app_state.settings.address();
+
+    let app = Application::build(app_state, &address)
         .await
         .expect("Failed to build app");
 
     app.run().await.expect("Failed to run app");
 }
+
+fn exit_with(error: SettingsError) -> ! {
+    eprintln!("{}", error);
+    process::exit(1);
+}
+
+async fn build_app_state(settings: Settings) -> Result<AppState, SettingsError> {
+    let pg_pool = match &settings.database.url {
+        Some(url) if settings.uses_backend(StoreBackend::Postgres) => Some(
+            get_postgres_pool(url.expose(), &settings.database)
+                .await
+                .expect("Failed to create Postgres connection pool"),
+        ),
+        _ => None,
+    };
+
+    let sqlite_pool = match &settings.sqlite.path {
+        Some(path) if settings.uses_backend(StoreBackend::Sqlite) => Some(
+            get_sqlite_pool(path, &settings.sqlite)
+                .await
+                .expect("Failed to open SQLite database"),
+        ),
+        _ => None,
+    };
+
+    let redis_conn = match &settings.redis.url {
+        Some(url) if settings.uses_backend(StoreBackend::Redis) => Some(
+            get_redis_connection(url.expose())
+                .await
+                .expect("Failed to connect to Redis"),
+        ),
+        _ => None,
+    };
+
+    // Persist state in Postgres, SQLite, Redis or on disk when configured,
+    // otherwise keep it in memory for the lifetime of the process.
+    let stores = &settings.stores;
+    let user_store: UserStoreType = match (
+        stores.users,
+        &pg_pool,
+        &sqlite_pool,
+        &stores.user_store_file,
+    ) {
+        (StoreBackend::Postgres, Some(pool), _, _) => {
+            Arc::new(RwLock::new(PostgresUserStore::new(pool.clone())))
+        }
+        (StoreBackend::Sqlite, _, Some(pool), _) => {
+            Arc::new(RwLock::new(SqliteUserStore::new(pool.clone())))
+        }
+        (StoreBackend::File, _, _, Some(path)) => Arc::new(RwLock::new(
+            FileUserStore::open(path)
+                .await
+                .expect("Failed to open user store file"),
+        )),
+        _ => Arc::new(RwLock::new(HashmapUserStore::default())),
+    };
+    let banned_token_store: BannedTokenStoreType = match (
+        stores.banned_tokens,
+        &sqlite_pool,
+        &redis_conn,
+        &stores.banned_token_store_file,
+    ) {
+        (StoreBackend::Sqlite, Some(pool), _, _) => {
+            Arc::new(RwLock::new(SqliteBannedTokenStore::new(pool.clone())))
+        }
+        (StoreBackend::Redis, _, Some(conn), _) => {
+            Arc::new(RwLock::new(RedisBannedTokenStore::new(conn.clone())))
+        }
+        (StoreBackend::File, _, _, Some(path)) => Arc::new(RwLock::new(
+            FileBannedTokenStore::open(path)
+                .await
+                .expect("Failed to open banned token store file"),
+        )),
+        _ => Arc::new(RwLock::new(HashmapBannedTokenStore::default())),
+    };
+    let two_fa_code_store: TwoFACodeStoreType =
+        match (stores.two_fa_codes, &sqlite_pool, &redis_conn) {
+            (StoreBackend::Sqlite, Some(pool), _) => {
+                Arc::new(RwLock::new(SqliteTwoFACodeStore::new(pool.clone())))
+            }
+            (StoreBackend::Redis, _, Some(conn)) => {
+                Arc::new(RwLock::new(RedisTwoFACodeStore::new(conn.clone())))
+            }
+            _ => Arc::new(RwLock::new(HashmapTwoFACodeStore::default())),
+        };
+    let refresh_token_store: RefreshTokenStoreType =
+        match (stores.refresh_tokens, &pg_pool, &sqlite_pool) {
+            (StoreBackend::Postgres, Some(pool), _) => {
+                Arc::new(RwLock::new(PostgresRefreshTokenStore::new(pool.clone())))
+            }
+            (StoreBackend::Sqlite, _, Some(pool)) => {
+                Arc::new(RwLock::new(SqliteRefreshTokenStore::new(pool.clone())))
+            }
+            _ => Arc::new(RwLock::new(HashmapRefreshTokenStore::default())),
+        };
+    let signing_key_store: SigningKeyStoreType = match (stores.signing_keys, &pg_pool, &sqlite_pool)
+    {
+        (StoreBackend::Postgres, Some(pool), _) => {
+            Arc::new(RwLock::new(PostgresSigningKeyStore::new(pool.clone())))
+        }
+        (StoreBackend::Sqlite, _, Some(pool)) => {
+            Arc::new(RwLock::new(SqliteSigningKeyStore::new(pool.clone())))
+        }
+        _ => Arc::new(RwLock::new(HashmapSigningKeyStore::default())),
+    };
+    let webauthn_credential_store: WebauthnCredentialStoreType =
+        match (stores.webauthn_credentials, &pg_pool, &sqlite_pool) {
+            (StoreBackend::Postgres, Some(pool), _) => Arc::new(RwLock::new(
+                PostgresWebauthnCredentialStore::new(pool.clone()),
+            )),
+            (StoreBackend::Sqlite, _, Some(pool)) => Arc::new(RwLock::new(
+                SqliteWebauthnCredentialStore::new(pool.clone()),
+            )),
+            _ => Arc::new(RwLock::new(HashmapWebauthnCredentialStore::default())),
+        };
+    let webauthn_challenge_store: WebauthnChallengeStoreType =
+        match (stores.webauthn_challenges, &sqlite_pool, &redis_conn) {
+            (StoreBackend::Sqlite, Some(pool), _) => {
+                Arc::new(RwLock::new(SqliteWebauthnChallengeStore::new(pool.clone())))
+            }
+            (StoreBackend::Redis, _, Some(conn)) => {
+                Arc::new(RwLock::new(RedisWebauthnChallengeStore::new(conn.clone())))
+            }
+            _ => Arc::new(RwLock::new(HashmapWebauthnChallengeStore::default())),
+        };
+    let recovery_code_store: RecoveryCodeStoreType =
+        match (stores.recovery_codes, &pg_pool, &sqlite_pool) {
+            (StoreBackend::Postgres, Some(pool), _) => {
+                Arc::new(RwLock::new(PostgresRecoveryCodeStore::new(pool.clone())))
+            }
+            (StoreBackend::Sqlite, _, Some(pool)) => {
+                Arc::new(RwLock::new(SqliteRecoveryCodeStore::new(pool.clone())))
+            }
+            _ => Arc::new(RwLock::new(HashmapRecoveryCodeStore::default())),
+        };
+    let password_reset_token_store: PasswordResetTokenStoreType =
+        match (stores.password_reset_tokens, &sqlite_pool, &redis_conn) {
+            (StoreBackend::Sqlite, Some(pool), _) => Arc::new(RwLock::new(
+                SqlitePasswordResetTokenStore::new(pool.clone()),
+            )),
+            (StoreBackend::Redis, _, Some(conn)) => {
+                Arc::new(RwLock::new(RedisPasswordResetTokenStore::new(conn.clone())))
+            }
+            _ => Arc::new(RwLock::new(HashmapPasswordResetTokenStore::default())),
+        };
+    let jwt_keys = match settings.jwt.static_keys()? {
+        Some(keys) => keys,
+        None => load_jwt_keys(&signing_key_store, &settings.jwt.algorithm)
+            .await
+            .expect("Failed to load signing keys"),
+    };
+
+    Ok(AppState {
+        pg_pool,
+        sqlite_pool,
+        user_store,
+        banned_token_store,
+        two_fa_code_store,
+        refresh_token_store,
+        signing_key_store,
+        webauthn_credential_store,
+        webauthn_challenge_store,
+        recovery_code_store,
+        password_reset_token_store,
+        email_client: build_email_client(&settings.email),
+        password_hasher: settings.argon2.hasher()?,
+        email_verifier: settings.email_verification.verifier(),
+        jwt_keys: Arc::new(RwLock::new(jwt_keys)),
+        email_policy: settings.email.domain_policy()?,
+        password_policy: settings.password_policy.policy()?,
+        settings: Arc::new(settings),
+    })
+}
+
+// Keys are generated, promoted and retired by `auth-service keys ...`, possibly on
+// another replica; poll the store so this process signs and verifies with them.
+fn spawn_key_reloader(app_state: &AppState) {
+    let signing_key_store = app_state.signing_key_store.clone();
+    let jwt_keys = app_state.jwt_keys.clone();
+    let period = Duration::from_secs(app_state.settings.jwt.key_reload_seconds);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        // The first tick completes immediately, and the keys were just loaded.
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Err(e) = reload_jwt_keys(&signing_key_store, &jwt_keys).await {
+                eprintln!("failed to reload signing keys: {:?}", e);
+            }
+        }
+    });
+}
+
+// Rotate without downtime by generating a key, waiting until every verifier has
+// fetched the new JWKS, promoting it, and retiring the old key once the last token
+// it signed has expired.
+async fn run_keys_command(app_state: &AppState, args: &[String]) {
+    let settings = &app_state.settings;
+    if !settings.jwt.manages_keys() || settings.stores.signing_keys == StoreBackend::Memory {
+        eprintln!("keys can only be managed with stores.signing_keys = \"postgres\" or \"sqlite\"");
+        process::exit(1);
+    }
+
+    let store = &app_state.signing_key_store;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["list"] => store.read().await.get_keys().await.map(|keys| {
+            for key in keys {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    key.kid,
+                    key.algorithm,
+                    key.status.as_str(),
+                    key.created_at
+                );
+            }
+        }),
+        ["generate", algorithm @ ..] if algorithm.len() <= 1 => {
+            let algorithm = algorithm
+                .first()
+                .copied()
+                .unwrap_or(&settings.jwt.algorithm);
+            let key = generate_signing_key(algorithm).unwrap_or_else(|e| {
+                eprintln!("cannot generate key: {:?}", e);
+                process::exit(1);
+            });
+            let kid = key.kid.clone();
+            store
+                .write()
+                .await
+                .add_key(key)
+                .await
+                .map(|_| println!("{}", kid))
+        }
+        ["promote", kid] => store.write().await.promote_key(kid).await,
+        ["retire", kid] => store.write().await.retire_key(kid).await,
+        _ => {
+            eprintln!(
+                "usage: auth-service keys list | generate [RS256|EdDSA] | promote <kid> | retire <kid>"
+            );
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = result {
+        let message = match e {
+            SigningKeyStoreError::KeyNotFound => "no such key",
+            SigningKeyStoreError::KeyInUse => {
+                "the signing key can't be retired, promote another key first"
+            }
+            SigningKeyStoreError::UnexpectedError => "unexpected error",
+        };
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
+
+// Deliver email over SMTP, drop `.eml` files into a directory,
+// or only log recipients and subjects to stdout, depending on `email.backend`.
+fn build_email_client(settings: &EmailSettings) -> EmailClientType {
+    let sender = settings.sender.clone();
+
+    match (settings.backend, &settings.smtp.host, &settings.drop_dir) {
+        (EmailBackend::Smtp, Some(host), _) => {
+            let credentials = settings.smtp.username.clone().zip(
+                settings
+                    .smtp
+                    .password
+                    .as_ref()
+                    .map(|password| password.expose().to_owned()),
+            );
+            Arc::new(SmtpEmailClient::new(
+                sender,
+                host,
+                settings.smtp.port,
+                credentials,
+            ))
+        }
+        (EmailBackend::File, _, Some(directory)) => Arc::new(
+            FileEmailClient::new(sender, directory).expect("Failed to create email drop directory"),
+        ),
+        _ => Arc::new(LogEmailClient),
+    }
+}
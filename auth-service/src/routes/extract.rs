@@ -0,0 +1,74 @@
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Request},
+    http::request::Parts,
+    Json,
+};
+use axum_extra::extract::CookieJar;
+
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, UserStoreError},
+    utils::{auth::validate_token, constants::JWT_COOKIE_NAME},
+};
+
+// Drop-in replacement for `axum::Json` as a request extractor. A body that can't be
+// deserialized is rejected with the API's JSON error shape instead of axum's
+// plain-text rejection.
+pub struct AppJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for AppJson<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = AuthAPIError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        Ok(Self(value))
+    }
+}
+
+impl From<JsonRejection> for AuthAPIError {
+    fn from(rejection: JsonRejection) -> Self {
+        AuthAPIError::MalformedBody(rejection.body_text())
+    }
+}
+
+// The user whose valid, unbanned JWT cookie came with the request. Handlers for a
+// signed-in user's own account take this instead of an email in the body.
+pub struct AuthenticatedUser {
+    pub email: Email,
+}
+
+// Errors of the record of the user a token was issued to: a valid token for a user
+// that no longer exists is treated like an invalid token.
+pub(crate) fn token_user_error(e: UserStoreError) -> AuthAPIError {
+    match e {
+        UserStoreError::UserNotFound => AuthAPIError::InvalidToken,
+        e => e.into(),
+    }
+}
+
+impl FromRequestParts<AppState> for AuthenticatedUser {
+    type Rejection = AuthAPIError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let jar = CookieJar::from_headers(&parts.headers);
+        let token = jar.get(JWT_COOKIE_NAME).ok_or(AuthAPIError::MissingToken)?;
+
+        let claims = validate_token(
+            token.value(),
+            &*state.jwt_keys.read().await,
+            &state.banned_token_store,
+            &state.user_store,
+        )
+        .await?;
+
+        let email = Email::parse(claims.sub).map_err(|_| AuthAPIError::InvalidToken)?;
+        Ok(Self { email })
+    }
+}
@@ -0,0 +1,16 @@
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Json},
+};
+
+use crate::app_state::AppState;
+
+// Public keys that verify the JWTs issued by this service, for local verification.
+pub async fn jwks(State(state): State<AppState>) -> impl IntoResponse {
+    let jwks = state.jwt_keys.read().await.jwks().clone();
+
+    // Clients may cache the set for as long as it takes us to notice a new key.
+    let cache_control = format!("public, max-age={}", state.settings.jwt.key_reload_seconds);
+    ([(header::CACHE_CONTROL, cache_control)], Json(jwks))
+}
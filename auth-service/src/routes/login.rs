@@ -1,5 +1,163 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
 
-pub async fn login() -> impl IntoResponse {
-    StatusCode::OK.into_response()
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, Email, LoginAttemptId, Password, TwoFACode, TwoFAMethod, User,
+        UserStoreError, WebauthnCeremony,
+    },
+    routes::{request_options, start_session, AppJson, CredentialRequestOptions},
+    utils::{constants::TWO_FA_CODE_TTL_SECONDS, time::unix_now},
+};
+
+pub async fn login(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    AppJson(request): AppJson<LoginRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = Email::parse(request.email)?;
+    let password = Password::parse(request.password)?;
+
+    let user = match state.user_store.read().await.get_user(&email).await {
+        Ok(user) => Some(user),
+        Err(UserStoreError::UserNotFound) => None,
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+
+    // Unknown users and wrong passwords produce the same response (and take the same
+    // time), so the endpoint can't be used to find out which emails are registered.
+    let Some(user) = user else {
+        state
+            .password_hasher
+            .verify_dummy(password)
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?;
+        return Err(AuthAPIError::IncorrectCredentials);
+    };
+
+    let password_matches = state
+        .password_hasher
+        .verify(password.clone(), user.password_hash.clone())
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    if !password_matches {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    // Only checked once the password is known to be right, so it doesn't reveal
+    // anything about accounts of other people.
+    if !user.email_verified {
+        return Err(AuthAPIError::EmailNotVerified);
+    }
+
+    // Transparently upgrade hashes created with outdated cost parameters.
+    if state.password_hasher.needs_rehash(&user.password_hash) {
+        rehash_password(&state, &user.email, password).await;
+    }
+
+    if user.requires_2fa {
+        let response = handle_2fa(&state, &user).await?;
+        return Ok((jar, response).into_response());
+    }
+
+    let updated_jar = start_session(&state, jar, &user.email).await?;
+    Ok((updated_jar, StatusCode::OK).into_response())
+}
+
+async fn handle_2fa(
+    state: &AppState,
+    user: &User,
+) -> Result<(StatusCode, Json<TwoFactorAuthResponse>), AuthAPIError> {
+    let email = &user.email;
+    let login_attempt_id = LoginAttemptId::default();
+    // With TOTP and WebAuthn this code is never sent; the stored entry only ties the
+    // login attempt to the user, and `/verify-2fa` checks the authenticator instead.
+    let two_fa_code = TwoFACode::default();
+
+    state
+        .two_fa_code_store
+        .write()
+        .await
+        .add_code(
+            email.clone(),
+            login_attempt_id.clone(),
+            two_fa_code.clone(),
+            unix_now() + TWO_FA_CODE_TTL_SECONDS,
+        )
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    if user.two_fa_method == TwoFAMethod::Email {
+        let content = format!(
+            "Your login code is {}. It expires in {} minutes.",
+            two_fa_code.as_ref(),
+            TWO_FA_CODE_TTL_SECONDS / 60
+        );
+        state
+            .email_client
+            .send_email(email, "2FA Code", &content)
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?;
+    }
+
+    let webauthn_options = match user.two_fa_method {
+        TwoFAMethod::Webauthn => {
+            Some(request_options(state, WebauthnCeremony::SecondFactor, Some(email)).await?)
+        }
+        _ => None,
+    };
+
+    let response = TwoFactorAuthResponse {
+        message: "2FA required".to_owned(),
+        login_attempt_id: login_attempt_id.as_ref().to_owned(),
+        two_fa_method: user.two_fa_method,
+        webauthn_options,
+    };
+    Ok((StatusCode::PARTIAL_CONTENT, Json(response)))
+}
+
+async fn rehash_password(state: &AppState, email: &Email, password: Password) {
+    let result = match state.password_hasher.hash(password).await {
+        Ok(password_hash) => state
+            .user_store
+            .write()
+            .await
+            .update_password(email, password_hash)
+            .await
+            .map_err(|e| format!("{:?}", e)),
+        Err(e) => Err(format!("{:?}", e)),
+    };
+
+    // A failed upgrade must not fail the login; the old hash is still valid.
+    if let Err(e) = result {
+        eprintln!("failed to rehash password for {}: {}", email, e);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+// If a user requires 2FA, this JSON body should be returned!
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct TwoFactorAuthResponse {
+    pub message: String,
+    #[serde(rename = "loginAttemptId")]
+    pub login_attempt_id: String,
+    // Where the user finds the code: their inbox or their authenticator app, or
+    // whether a WebAuthn credential has to sign the challenge below.
+    #[serde(rename = "twoFAMethod")]
+    pub two_fa_method: TwoFAMethod,
+    // Options for `navigator.credentials.get()`, only with the `webauthn` method.
+    #[serde(
+        rename = "webauthnOptions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub webauthn_options: Option<CredentialRequestOptions>,
 }
@@ -1,5 +1,68 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::CookieJar;
 
-pub async fn logout() -> impl IntoResponse {
-    StatusCode::OK.into_response()
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, RefreshTokenStoreError},
+    utils::{
+        auth::{removal_auth_cookie, removal_refresh_cookie, validate_token, TokenError},
+        constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+        opaque_token::hash_opaque_token,
+    },
+};
+
+pub async fn logout(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let token = jar
+        .get(JWT_COOKIE_NAME)
+        .ok_or(AuthAPIError::MissingToken)?
+        .value()
+        .to_owned();
+
+    let claims = validate_token(
+        &token,
+        &*state.jwt_keys.read().await,
+        &state.banned_token_store,
+        &state.user_store,
+    )
+    .await?;
+
+    // Ban the token until it would have expired on its own.
+    state
+        .banned_token_store
+        .write()
+        .await
+        .add_token(claims.jti, claims.exp as u64)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    // End the refresh token chain of this session as well, so the JWT can't be renewed.
+    if let Some(cookie) = jar.get(REFRESH_TOKEN_COOKIE_NAME) {
+        let mut refresh_token_store = state.refresh_token_store.write().await;
+        match refresh_token_store
+            .get_token(&hash_opaque_token(cookie.value()))
+            .await
+        {
+            Ok(token) => refresh_token_store.revoke_family(&token.family_id).await?,
+            Err(RefreshTokenStoreError::TokenNotFound) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let secure = state.settings.cookies.secure;
+    let updated_jar = jar
+        .add(removal_auth_cookie(secure))
+        .add(removal_refresh_cookie(secure));
+    Ok((updated_jar, StatusCode::OK))
+}
+
+impl From<TokenError> for AuthAPIError {
+    fn from(e: TokenError) -> Self {
+        match e {
+            TokenError::InvalidToken(_) | TokenError::BannedToken => AuthAPIError::InvalidToken,
+            TokenError::UnexpectedError => AuthAPIError::UnexpectedError,
+        }
+    }
 }
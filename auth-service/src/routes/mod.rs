@@ -1,12 +1,30 @@
+mod extract;
+mod jwks;
 mod login;
 mod logout;
+mod password_reset;
+mod recovery_codes;
+mod refresh;
 mod signup;
+mod totp;
+mod two_fa_method;
 mod verify_2fa;
+mod verify_email;
 mod verify_token;
+mod webauthn;
 
 // re-export items from sub-modules
+pub use extract::*;
+pub use jwks::*;
 pub use login::*;
 pub use logout::*;
+pub use password_reset::*;
+pub use recovery_codes::*;
+pub use refresh::*;
 pub use signup::*;
+pub use totp::*;
+pub use two_fa_method::*;
 pub use verify_2fa::*;
+pub use verify_email::*;
 pub use verify_token::*;
+pub use webauthn::*;
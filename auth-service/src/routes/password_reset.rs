@@ -0,0 +1,195 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, Email, Password, PasswordResetToken, PasswordResetTokenStoreError,
+        UserStoreError,
+    },
+    routes::AppJson,
+    utils::{
+        opaque_token::{generate_opaque_token, hash_opaque_token},
+        time::unix_now,
+    },
+};
+
+// Email a single-use reset link, valid for `password_reset.token_ttl_seconds`.
+// Unknown addresses get the same response as known ones, so the endpoint doesn't
+// reveal which addresses have accounts.
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    AppJson(request): AppJson<PasswordResetRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = Email::parse(request.email)?;
+
+    let user_exists = match state.user_store.read().await.get_user(&email).await {
+        Ok(_) => true,
+        Err(UserStoreError::UserNotFound) => false,
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+
+    if user_exists {
+        let token = generate_opaque_token();
+        let ttl_seconds = state.settings.password_reset.token_ttl_seconds;
+        state
+            .password_reset_token_store
+            .write()
+            .await
+            .add_token(PasswordResetToken {
+                token_hash: hash_opaque_token(&token),
+                email: email.clone(),
+                expires_at: unix_now().saturating_add(ttl_seconds),
+            })
+            .await?;
+
+        let content = format!(
+            "Someone asked to reset the password of your account. If it was you, \
+             choose a new password by opening this link:\n\n{}?token={}\n\n\
+             It expires in {} minutes and works once. If it wasn't you, ignore this email.",
+            state.settings.password_reset.link_base_url,
+            token,
+            ttl_seconds.div_ceil(60)
+        );
+        // A failed delivery must not tell the caller that the account exists.
+        if let Err(e) = state
+            .email_client
+            .send_email(&email, "Reset your password", &content)
+            .await
+        {
+            eprintln!("failed to send password reset email to {}: {:?}", email, e);
+        }
+    }
+
+    let response = PasswordResetResponse {
+        message: "If an account exists for this address, a reset link was sent".to_owned(),
+    };
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Set a new password with the token from the reset link. Every session of the user
+// ends: outstanding JWTs are rejected from now on and refresh tokens are revoked.
+pub async fn confirm_password_reset(
+    State(state): State<AppState>,
+    AppJson(request): AppJson<ConfirmPasswordResetRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let password = Password::parse(request.new_password)?;
+    let token_hash = hash_opaque_token(&request.token);
+
+    // The token is only used up once the new password was accepted, so a policy
+    // violation can be fixed without requesting another link.
+    let email = state
+        .password_reset_token_store
+        .read()
+        .await
+        .get_token(&token_hash)
+        .await?
+        .email;
+    state
+        .password_policy
+        .check(&password, &email)
+        .map_err(AuthAPIError::PasswordPolicyViolation)?;
+
+    let password_hash = state
+        .password_hasher
+        .hash(password)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    // Of two concurrent requests with the same token only one gets past this.
+    state
+        .password_reset_token_store
+        .write()
+        .await
+        .take_token(&token_hash)
+        .await?;
+
+    let mut user_store = state.user_store.write().await;
+    let mut user = match user_store.get_user(&email).await {
+        Ok(user) => user,
+        // The account was deleted after the link was sent.
+        Err(UserStoreError::UserNotFound) => {
+            return Err(PasswordResetTokenStoreError::TokenNotFound.into())
+        }
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+    user.password_hash = password_hash;
+    user.session_generation += 1;
+    // Following the link proved control of the address.
+    user.email_verified = true;
+    user_store
+        .update_user(user)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    drop(user_store);
+
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_user(&email)
+        .await?;
+    // Other links sent before this one must not change the new password again, and a
+    // login started with the old password must not be finished.
+    state
+        .password_reset_token_store
+        .write()
+        .await
+        .revoke_user(&email)
+        .await?;
+    state
+        .two_fa_code_store
+        .write()
+        .await
+        .remove_code(&email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    let content = "The password of your account was just changed and every device \
+                   signed in to it was signed out. If you didn't do this, reset your \
+                   password right away and contact support.";
+    // The password is already changed; a failed notification must not report otherwise.
+    if let Err(e) = state
+        .email_client
+        .send_email(&email, "Your password was changed", content)
+        .await
+    {
+        eprintln!(
+            "failed to send password change notification to {}: {:?}",
+            email, e
+        );
+    }
+
+    let response = PasswordResetResponse {
+        message: "Password changed, please log in again".to_owned(),
+    };
+    Ok((StatusCode::OK, Json(response)))
+}
+
+impl From<PasswordResetTokenStoreError> for AuthAPIError {
+    fn from(e: PasswordResetTokenStoreError) -> Self {
+        match e {
+            PasswordResetTokenStoreError::TokenNotFound => {
+                AuthAPIError::InvalidInput("Invalid or expired reset token".to_owned())
+            }
+            PasswordResetTokenStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PasswordResetRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmPasswordResetRequest {
+    pub token: String,
+    #[serde(rename = "newPassword")]
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasswordResetResponse {
+    pub message: String,
+}
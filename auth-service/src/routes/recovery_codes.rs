@@ -0,0 +1,101 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, RecoveryCodeStoreError},
+    routes::{token_user_error, AuthenticatedUser},
+    utils::{
+        constants::RECOVERY_CODE_COUNT,
+        recovery_code::{generate_recovery_code, hash_recovery_code},
+    },
+};
+
+// How many unused recovery codes the signed-in user has left.
+pub async fn count_recovery_codes(
+    State(state): State<AppState>,
+    AuthenticatedUser { email }: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let remaining = state
+        .recovery_code_store
+        .read()
+        .await
+        .count_codes(&email)
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(RecoveryCodeCountResponse { remaining }),
+    ))
+}
+
+// Replace the signed-in user's recovery codes with a fresh set, e.g. after using
+// some of them or if the old ones may have been seen by someone else.
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    AuthenticatedUser { email }: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let user = state
+        .user_store
+        .read()
+        .await
+        .get_user(&email)
+        .await
+        .map_err(token_user_error)?;
+
+    if !user.requires_2fa {
+        return Err(AuthAPIError::InvalidInput(
+            "Two-factor authentication is not enabled".to_owned(),
+        ));
+    }
+
+    let recovery_codes = issue_recovery_codes(&state, &email).await?;
+    Ok((
+        StatusCode::OK,
+        Json(RecoveryCodesResponse { recovery_codes }),
+    ))
+}
+
+// Generate a new set of recovery codes for `email`, invalidating any previous set.
+// The plain codes are returned to be shown to the user once; only hashes are kept.
+pub(crate) async fn issue_recovery_codes(
+    state: &AppState,
+    email: &Email,
+) -> Result<Vec<String>, AuthAPIError> {
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect();
+    let code_hashes = recovery_codes
+        .iter()
+        .map(|code| hash_recovery_code(code).ok_or(AuthAPIError::UnexpectedError))
+        .collect::<Result<_, _>>()?;
+
+    state
+        .recovery_code_store
+        .write()
+        .await
+        .replace_codes(email, code_hashes)
+        .await?;
+
+    Ok(recovery_codes)
+}
+
+impl From<RecoveryCodeStoreError> for AuthAPIError {
+    fn from(e: RecoveryCodeStoreError) -> Self {
+        match e {
+            RecoveryCodeStoreError::CodeNotFound => AuthAPIError::IncorrectCredentials,
+            RecoveryCodeStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodeCountResponse {
+    pub remaining: usize,
+}
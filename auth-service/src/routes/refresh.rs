@@ -0,0 +1,111 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, RefreshToken, RefreshTokenStoreError},
+    routes::token_user_error,
+    utils::{
+        auth::{create_refresh_cookie, generate_auth_cookie},
+        constants::REFRESH_TOKEN_COOKIE_NAME,
+        opaque_token::{generate_opaque_token, hash_opaque_token},
+        time::unix_now,
+    },
+};
+
+pub async fn refresh(
+    State(state): State<AppState>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let token = jar
+        .get(REFRESH_TOKEN_COOKIE_NAME)
+        .ok_or(AuthAPIError::MissingToken)?
+        .value()
+        .to_owned();
+
+    let mut refresh_token_store = state.refresh_token_store.write().await;
+    let previous = refresh_token_store
+        .use_token(&hash_opaque_token(&token))
+        .await?;
+
+    // Every refresh token is good for exactly one rotation. Seeing one again means
+    // it was copied, and we can't tell the legitimate client from the thief, so the
+    // whole session is ended.
+    if previous.used {
+        refresh_token_store
+            .revoke_family(&previous.family_id)
+            .await?;
+        return Err(AuthAPIError::InvalidToken);
+    }
+    if previous.expires_at <= unix_now() {
+        return Err(AuthAPIError::InvalidToken);
+    }
+    drop(refresh_token_store);
+
+    let updated_jar = issue_tokens(&state, jar, &previous.email, previous.family_id).await?;
+    Ok((updated_jar, StatusCode::OK))
+}
+
+// Set the auth cookie and a refresh token cookie in a new token family.
+// Called whenever a user completes a login.
+pub(crate) async fn start_session(
+    state: &AppState,
+    jar: CookieJar,
+    email: &Email,
+) -> Result<CookieJar, AuthAPIError> {
+    issue_tokens(state, jar, email, uuid::Uuid::new_v4().to_string()).await
+}
+
+async fn issue_tokens(
+    state: &AppState,
+    jar: CookieJar,
+    email: &Email,
+    family_id: String,
+) -> Result<CookieJar, AuthAPIError> {
+    let session_generation = state
+        .user_store
+        .read()
+        .await
+        .get_user(email)
+        .await
+        .map_err(token_user_error)?
+        .session_generation;
+    let auth_cookie = generate_auth_cookie(
+        email,
+        session_generation,
+        &*state.jwt_keys.read().await,
+        state.settings.jwt.token_ttl_seconds,
+        state.settings.cookies.secure,
+    )
+    .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    let refresh_token = generate_opaque_token();
+    let ttl_seconds = state.settings.jwt.refresh_token_ttl_seconds;
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .add_token(RefreshToken {
+            token_hash: hash_opaque_token(&refresh_token),
+            family_id,
+            email: email.clone(),
+            expires_at: unix_now() + ttl_seconds,
+            used: false,
+        })
+        .await?;
+
+    Ok(jar.add(auth_cookie).add(create_refresh_cookie(
+        refresh_token,
+        ttl_seconds,
+        state.settings.cookies.secure,
+    )))
+}
+
+impl From<RefreshTokenStoreError> for AuthAPIError {
+    fn from(e: RefreshTokenStoreError) -> Self {
+        match e {
+            RefreshTokenStoreError::TokenNotFound => AuthAPIError::InvalidToken,
+            RefreshTokenStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+        }
+    }
+}
@@ -1,56 +1,83 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
-use regex::Regex;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, Password, User, UserStoreError},
+    routes::{
+        clear_verification_email_sent_at, issue_recovery_codes, send_verification_email, AppJson,
+    },
+    utils::time::unix_now,
+};
+
 #[derive(Serialize)]
 pub struct SignupResponse {
     pub message: String,
+    // Only for accounts that require 2FA, shown to the user this one time.
+    #[serde(rename = "recoveryCodes", skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<Vec<String>>,
 }
 
-#[derive(Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-}
+pub async fn signup(
+    State(state): State<AppState>,
+    AppJson(request): AppJson<SignupRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = Email::parse(request.email)?;
+    state.email_policy.check(&email)?;
 
-pub async fn signup(Json(request): Json<SignupRequest>) -> impl IntoResponse {
-    // Validate email format
-    if request.email.is_empty() {
-        let error_response = ErrorResponse {
-            error: "Email cannot be empty".to_string(),
-        };
-        return (StatusCode::BAD_REQUEST, Json(error_response)).into_response();
-    }
-
-    if !is_valid_email(&request.email) {
-        let error_response = ErrorResponse {
-            error: "Invalid email format".to_string(),
-        };
-        return (StatusCode::BAD_REQUEST, Json(error_response)).into_response();
-    }
-
-    // Validate password
-    if request.password.is_empty() {
-        let error_response = ErrorResponse {
-            error: "Password cannot be empty".to_string(),
-        };
-        return (StatusCode::BAD_REQUEST, Json(error_response)).into_response();
-    }
-
-    // If validation passes, return 201 Created
-    let response = SignupResponse {
-        message: "User created successfully!".to_string(),
+    let password = Password::parse(request.password)?;
+    state
+        .password_policy
+        .check(&password, &email)
+        .map_err(AuthAPIError::PasswordPolicyViolation)?;
+
+    let password_hash = state
+        .password_hasher
+        .hash(password)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+
+    // The account stays unverified until the link from the email below is followed.
+    let mut user = User::new(email.clone(), password_hash, request.requires_2fa);
+    user.verification_email_sent_at = Some(unix_now());
+
+    let mut user_store = state.user_store.write().await;
+
+    user_store.add_user(user).await.map_err(|e| match e {
+        UserStoreError::UserAlreadyExists => AuthAPIError::UserAlreadyExists,
+        _ => AuthAPIError::UnexpectedError,
+    })?;
+    drop(user_store);
+
+    // The account exists now, so a failed delivery must not fail the signup: a retry
+    // would only get 409. The user can ask for the link again instead.
+    let verification_email_sent = match send_verification_email(&state, &email).await {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("failed to send verification email to {}: {:?}", email, e);
+            clear_verification_email_sent_at(&state, &email).await?;
+            false
+        }
     };
-    (StatusCode::CREATED, Json(response)).into_response()
-}
 
-fn is_valid_email(email: &str) -> bool {
-    // Email validation using regex pattern
-    // Pattern: ^[a-zA-Z0-9._%+-]+@(?:[a-zA-Z0-9-]+\.)+(com|com\.[a-zA-Z]{2,4})$
-    // This validates emails with .com or .com.XX domains
-    let email_regex = Regex::new(r"^[a-zA-Z0-9._%+-]+@(?:[a-zA-Z0-9-]+\.)+(com|com\.[a-zA-Z]{2,4})$")
-        .expect("Invalid regex pattern");
+    let recovery_codes = if request.requires_2fa {
+        Some(issue_recovery_codes(&state, &email).await?)
+    } else {
+        None
+    };
 
-    email_regex.is_match(email)
+    // If the user was stored, return 201 Created
+    let message = if verification_email_sent {
+        "User created successfully!"
+    } else {
+        "User created, but the verification email could not be sent. \
+         Request a new one with /verify-email/resend."
+    };
+    let response = SignupResponse {
+        message: message.to_string(),
+        recovery_codes,
+    };
+    Ok((StatusCode::CREATED, Json(response)))
 }
 
 #[derive(Deserialize)]
@@ -0,0 +1,138 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, TwoFACode, TwoFAMethod},
+    routes::{issue_recovery_codes, token_user_error, AppJson, AuthenticatedUser},
+    utils::{
+        time::unix_now,
+        totp::{
+            generate_totp_secret, qr_code_png, qr_code_svg, totp_uri, verify_totp_code, TotpError,
+        },
+    },
+};
+
+// Start setting up an authenticator app. The new secret only takes effect once
+// `/2fa/totp/confirm` has seen a valid code for it, so a half-finished enrollment
+// can't lock the user out.
+pub async fn enroll_totp(
+    State(state): State<AppState>,
+    AuthenticatedUser { email }: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let secret = generate_totp_secret();
+    let otpauth_uri = totp_uri(&secret, &state.settings.totp.issuer, &email)?;
+
+    let response = TotpEnrollmentResponse {
+        qr_code_png: STANDARD.encode(qr_code_png(&otpauth_uri)?),
+        qr_code_svg: qr_code_svg(&otpauth_uri)?,
+        otpauth_uri,
+        secret: secret.clone(),
+    };
+
+    let mut user_store = state.user_store.write().await;
+    let mut user = user_store
+        .get_user(&email)
+        .await
+        .map_err(token_user_error)?;
+    user.pending_totp_secret = Some(secret);
+    user_store
+        .update_user(user)
+        .await
+        .map_err(token_user_error)?;
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Activate the pending secret and make TOTP the user's 2FA method. Users without
+// recovery codes left get a fresh set, so enabling 2FA this way doesn't leave them
+// without a fallback.
+pub async fn confirm_totp(
+    State(state): State<AppState>,
+    AuthenticatedUser { email }: AuthenticatedUser,
+    AppJson(request): AppJson<ConfirmTotpRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let code = TwoFACode::parse(request.two_fa_code).map_err(AuthAPIError::InvalidInput)?;
+
+    let mut user_store = state.user_store.write().await;
+    let mut user = user_store
+        .get_user(&email)
+        .await
+        .map_err(token_user_error)?;
+
+    let Some(secret) = user.pending_totp_secret.take() else {
+        return Err(AuthAPIError::InvalidInput(
+            "No pending TOTP enrollment".to_owned(),
+        ));
+    };
+
+    let Some(step) = verify_totp_code(
+        &secret,
+        code.as_ref(),
+        state.settings.totp.drift_steps,
+        unix_now(),
+    ) else {
+        return Err(AuthAPIError::IncorrectCredentials);
+    };
+
+    user.totp_secret = Some(secret);
+    // The code that confirmed the app can't be used to log in as well.
+    user.totp_last_step = Some(step);
+    user.two_fa_method = TwoFAMethod::Totp;
+    user.requires_2fa = true;
+    user_store
+        .update_user(user)
+        .await
+        .map_err(token_user_error)?;
+    drop(user_store);
+
+    let remaining = state
+        .recovery_code_store
+        .read()
+        .await
+        .count_codes(&email)
+        .await?;
+    let recovery_codes = if remaining == 0 {
+        Some(issue_recovery_codes(&state, &email).await?)
+    } else {
+        None
+    };
+
+    Ok((StatusCode::OK, Json(ConfirmTotpResponse { recovery_codes })))
+}
+
+impl From<TotpError> for AuthAPIError {
+    fn from(_: TotpError) -> Self {
+        AuthAPIError::UnexpectedError
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TotpEnrollmentResponse {
+    // Base32 secret, for typing into an app that can't scan the QR code.
+    pub secret: String,
+    #[serde(rename = "otpauthUri")]
+    pub otpauth_uri: String,
+    // Base64 encoded PNG image of the URI as a QR code.
+    #[serde(rename = "qrCodePng")]
+    pub qr_code_png: String,
+    #[serde(rename = "qrCodeSvg")]
+    pub qr_code_svg: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmTotpResponse {
+    #[serde(
+        rename = "recoveryCodes",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub recovery_codes: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmTotpRequest {
+    #[serde(rename = "2FACode")]
+    pub two_fa_code: String,
+}
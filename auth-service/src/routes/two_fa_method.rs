@@ -0,0 +1,63 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, TwoFAMethod},
+    routes::{token_user_error, AppJson, AuthenticatedUser},
+};
+
+// Switch the signed-in user's second factor between emailed codes, their
+// authenticator app and WebAuthn. TOTP can only be chosen once an app has been
+// confirmed, WebAuthn once a credential has been registered. Choosing either turns
+// 2FA on for users who signed up without it.
+pub async fn set_two_fa_method(
+    State(state): State<AppState>,
+    AuthenticatedUser { email }: AuthenticatedUser,
+    AppJson(request): AppJson<SetTwoFAMethodRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let method = TwoFAMethod::parse(&request.two_fa_method).map_err(AuthAPIError::InvalidInput)?;
+
+    if method == TwoFAMethod::Webauthn
+        && state
+            .webauthn_credential_store
+            .read()
+            .await
+            .get_credentials(&email)
+            .await?
+            .is_empty()
+    {
+        return Err(AuthAPIError::InvalidInput(
+            "No WebAuthn credential registered".to_owned(),
+        ));
+    }
+
+    let mut user_store = state.user_store.write().await;
+    let mut user = user_store
+        .get_user(&email)
+        .await
+        .map_err(token_user_error)?;
+
+    if method == TwoFAMethod::Totp && user.totp_secret.is_none() {
+        return Err(AuthAPIError::InvalidInput(
+            "No authenticator app enrolled".to_owned(),
+        ));
+    }
+
+    if method != TwoFAMethod::Email {
+        user.requires_2fa = true;
+    }
+    user.two_fa_method = method;
+    user_store
+        .update_user(user)
+        .await
+        .map_err(token_user_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+pub struct SetTwoFAMethodRequest {
+    #[serde(rename = "twoFAMethod")]
+    pub two_fa_method: String,
+}
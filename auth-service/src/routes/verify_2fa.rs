@@ -1,5 +1,194 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::CookieJar;
+use serde::Deserialize;
+use subtle::ConstantTimeEq;
 
-pub async fn verify_2fa() -> impl IntoResponse {
-    StatusCode::OK.into_response()
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, Email, LoginAttemptId, TwoFACode, TwoFACodeStoreError, TwoFAMethod,
+        UserStoreError, WebauthnCeremony,
+    },
+    routes::{check_assertion, start_session, AppJson, AssertionCredential},
+    utils::{
+        constants::MAX_TWO_FA_ATTEMPTS, recovery_code::hash_recovery_code, time::unix_now,
+        totp::verify_totp_code,
+    },
+};
+
+pub async fn verify_2fa(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    AppJson(request): AppJson<Verify2FARequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = Email::parse(request.email)?;
+    let login_attempt_id =
+        LoginAttemptId::parse(request.login_attempt_id).map_err(AuthAPIError::InvalidInput)?;
+    let proof = match request.second_factor {
+        SecondFactor::Code { two_fa_code } => {
+            Proof::Code(TwoFACode::parse(two_fa_code).map_err(AuthAPIError::InvalidInput)?)
+        }
+        SecondFactor::Webauthn { assertion } => Proof::Webauthn(assertion),
+        SecondFactor::RecoveryCode { recovery_code } => Proof::RecoveryCode(
+            hash_recovery_code(&recovery_code)
+                .ok_or_else(|| AuthAPIError::InvalidInput("Invalid recovery code".to_owned()))?,
+        ),
+    };
+
+    let user = match state.user_store.read().await.get_user(&email).await {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::IncorrectCredentials),
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+
+    let (expected_login_attempt_id, expected_code) = state
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&email)
+        .await
+        .map_err(two_fa_code_store_error)?;
+
+    if login_attempt_id != expected_login_attempt_id {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    // Any error other than an unexpected one is a failed check.
+    let checked = match proof {
+        Proof::Code(two_fa_code) => match (user.two_fa_method, &user.totp_secret) {
+            (TwoFAMethod::Totp, Some(secret)) => match verify_totp_code(
+                secret,
+                two_fa_code.as_ref(),
+                state.settings.totp.drift_steps,
+                unix_now(),
+            ) {
+                // Like the emailed codes, each TOTP code can only be used once.
+                Some(step) if user.totp_last_step.is_none_or(|last| step > last) => {
+                    let mut user = user.clone();
+                    user.totp_last_step = Some(step);
+                    state
+                        .user_store
+                        .write()
+                        .await
+                        .update_user(user)
+                        .await
+                        .map_err(|_| AuthAPIError::UnexpectedError)?;
+                    Ok(())
+                }
+                _ => Err(AuthAPIError::IncorrectCredentials),
+            },
+            // The stored code was never sent to the user.
+            (TwoFAMethod::Webauthn, _) => Err(AuthAPIError::IncorrectCredentials),
+            _ if bool::from(
+                two_fa_code
+                    .as_ref()
+                    .as_bytes()
+                    .ct_eq(expected_code.as_ref().as_bytes()),
+            ) =>
+            {
+                Ok(())
+            }
+            _ => Err(AuthAPIError::IncorrectCredentials),
+        },
+        Proof::Webauthn(assertion) if user.two_fa_method == TwoFAMethod::Webauthn => {
+            check_assertion(
+                &state,
+                &assertion,
+                WebauthnCeremony::SecondFactor,
+                Some(&email),
+            )
+            .await
+            .map(|_| ())
+        }
+        Proof::Webauthn(_) => Err(AuthAPIError::IncorrectCredentials),
+        // Works whatever the user's method, that's what recovery codes are for.
+        Proof::RecoveryCode(code_hash) => state
+            .recovery_code_store
+            .write()
+            .await
+            .use_code(&email, &code_hash)
+            .await
+            .map_err(AuthAPIError::from),
+    };
+
+    if let Err(e) = checked {
+        if matches!(e, AuthAPIError::UnexpectedError) {
+            return Err(e);
+        }
+        // Too many failed checks use up the login attempt; the user has to log in again.
+        let mut two_fa_code_store = state.two_fa_code_store.write().await;
+        let failures = two_fa_code_store
+            .record_failure(&email)
+            .await
+            .map_err(two_fa_code_store_error)?;
+        if failures >= MAX_TWO_FA_ATTEMPTS {
+            two_fa_code_store
+                .remove_code(&email)
+                .await
+                .map_err(|_| AuthAPIError::UnexpectedError)?;
+        }
+        return Err(e);
+    }
+
+    // Each code can only be used once. Taking it is atomic in every store, so of several
+    // concurrent requests with the same code, even on different replicas, only one gets
+    // it back. That also covers a TOTP code accepted twice before either request saved
+    // its step.
+    let (taken_login_attempt_id, _) = state
+        .two_fa_code_store
+        .write()
+        .await
+        .take_code(&email)
+        .await
+        .map_err(two_fa_code_store_error)?;
+    if taken_login_attempt_id != login_attempt_id {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    let updated_jar = start_session(&state, jar, &email).await?;
+    Ok((updated_jar, StatusCode::OK))
+}
+
+fn two_fa_code_store_error(e: TwoFACodeStoreError) -> AuthAPIError {
+    match e {
+        TwoFACodeStoreError::LoginAttemptIdNotFound => AuthAPIError::IncorrectCredentials,
+        TwoFACodeStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Verify2FARequest {
+    pub email: String,
+    #[serde(rename = "loginAttemptId")]
+    pub login_attempt_id: String,
+    #[serde(flatten)]
+    pub second_factor: SecondFactor,
+}
+
+// What proves the second factor: a 6-digit code, for users with the `webauthn`
+// method an assertion over the challenge from the login response, or one of the
+// user's recovery codes.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum SecondFactor {
+    Code {
+        #[serde(rename = "2FACode")]
+        two_fa_code: String,
+    },
+    Webauthn {
+        #[serde(rename = "webauthnAssertion")]
+        assertion: AssertionCredential,
+    },
+    RecoveryCode {
+        #[serde(rename = "recoveryCode")]
+        recovery_code: String,
+    },
+}
+
+// The second factor once its format has been checked.
+enum Proof {
+    Code(TwoFACode),
+    Webauthn(AssertionCredential),
+    // Hash of the recovery code, as stored.
+    RecoveryCode(String),
 }
@@ -0,0 +1,160 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, EmailClientError, UserStoreError},
+    routes::AppJson,
+    utils::{email_verification::EmailVerificationError, time::unix_now},
+};
+
+// Target of the link in the verification email. Following it again after the
+// account was activated is harmless.
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Query(query): Query<VerifyEmailQuery>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let token = query
+        .token
+        .ok_or_else(|| AuthAPIError::InvalidInput("Missing verification token".to_owned()))?;
+    let email = state.email_verifier.verify(&token, unix_now())?;
+
+    let mut user_store = state.user_store.write().await;
+    let mut user = match user_store.get_user(&email).await {
+        Ok(user) => user,
+        // The account was deleted after the link was sent.
+        Err(UserStoreError::UserNotFound) => {
+            return Err(EmailVerificationError::InvalidSignature.into())
+        }
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+
+    if !user.email_verified {
+        user.email_verified = true;
+        user_store
+            .update_user(user)
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?;
+    }
+
+    let response = VerifyEmailResponse {
+        message: "Email address verified".to_owned(),
+    };
+    Ok((StatusCode::OK, Json(response)))
+}
+
+// Send a new verification link, at most once per `email_verification.resend_interval_seconds`.
+// Unknown and already verified addresses, and resends that come too soon, get the
+// same response as a successful resend, so the endpoint doesn't reveal which
+// addresses have accounts.
+pub async fn resend_verification_email(
+    State(state): State<AppState>,
+    AppJson(request): AppJson<ResendVerificationEmailRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = Email::parse(request.email)?;
+    let now = unix_now();
+
+    let mut user_store = state.user_store.write().await;
+    let user = match user_store.get_user(&email).await {
+        Ok(user) if !user.email_verified => Some(user),
+        Ok(_) | Err(UserStoreError::UserNotFound) => None,
+        Err(_) => return Err(AuthAPIError::UnexpectedError),
+    };
+
+    let resend_interval = state.settings.email_verification.resend_interval_seconds;
+    let user = user.filter(|user| {
+        user.verification_email_sent_at
+            .is_none_or(|sent_at| now >= sent_at.saturating_add(resend_interval))
+    });
+
+    if let Some(mut user) = user {
+        user.verification_email_sent_at = Some(now);
+        user_store
+            .update_user(user)
+            .await
+            .map_err(|_| AuthAPIError::UnexpectedError)?;
+        drop(user_store);
+
+        // Failing here would tell the caller that the account exists.
+        if let Err(e) = send_verification_email(&state, &email).await {
+            eprintln!("failed to send verification email to {}: {:?}", email, e);
+            clear_verification_email_sent_at(&state, &email).await?;
+        }
+    }
+
+    let response = VerifyEmailResponse {
+        message: "If the account exists and is not verified yet, a new link was sent".to_owned(),
+    };
+    Ok((StatusCode::OK, Json(response)))
+}
+
+pub(crate) async fn send_verification_email(
+    state: &AppState,
+    email: &Email,
+) -> Result<(), EmailClientError> {
+    let link = format!(
+        "{}?token={}",
+        state.settings.email_verification.link_base_url,
+        state.email_verifier.token(email, unix_now())
+    );
+    let content = format!(
+        "Confirm your email address by opening this link:\n\n{}\n\nIt expires in {} hours.",
+        link,
+        state.email_verifier.ttl_seconds().div_ceil(60 * 60)
+    );
+
+    state
+        .email_client
+        .send_email(email, "Verify your email address", &content)
+        .await
+}
+
+// After a failed delivery, so the resend throttle doesn't hold back the next attempt.
+pub(crate) async fn clear_verification_email_sent_at(
+    state: &AppState,
+    email: &Email,
+) -> Result<(), AuthAPIError> {
+    let mut user_store = state.user_store.write().await;
+    let mut user = user_store
+        .get_user(email)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    user.verification_email_sent_at = None;
+    user_store
+        .update_user(user)
+        .await
+        .map_err(|_| AuthAPIError::UnexpectedError)
+}
+
+impl From<EmailVerificationError> for AuthAPIError {
+    fn from(e: EmailVerificationError) -> Self {
+        match e {
+            EmailVerificationError::Expired => {
+                AuthAPIError::InvalidInput("Verification link has expired".to_owned())
+            }
+            EmailVerificationError::Malformed | EmailVerificationError::InvalidSignature => {
+                AuthAPIError::InvalidInput("Invalid verification link".to_owned())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmailQuery {
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ResendVerificationEmailRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyEmailResponse {
+    pub message: String,
+}
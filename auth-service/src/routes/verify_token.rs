@@ -1,5 +1,26 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
 
-pub async fn verify_token() -> impl IntoResponse {
-    StatusCode::OK.into_response()
+use crate::{
+    app_state::AppState, domain::AuthAPIError, routes::AppJson, utils::auth::validate_token,
+};
+
+pub async fn verify_token(
+    State(state): State<AppState>,
+    AppJson(request): AppJson<VerifyTokenRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    validate_token(
+        &request.token,
+        &*state.jwt_keys.read().await,
+        &state.banned_token_store,
+        &state.user_store,
+    )
+    .await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+pub struct VerifyTokenRequest {
+    pub token: String,
 }
@@ -0,0 +1,440 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, Email, WebauthnCeremony, WebauthnChallenge, WebauthnChallengeStoreError,
+        WebauthnCredential, WebauthnCredentialStoreError,
+    },
+    routes::{start_session, AppJson, AuthenticatedUser},
+    utils::{
+        time::unix_now,
+        webauthn::{
+            base64url_decode, base64url_encode, generate_challenge, user_handle, verify_assertion,
+            verify_registration, ClientData, RelyingParty, WebauthnError, COSE_ALG_EDDSA,
+            COSE_ALG_ES256,
+        },
+    },
+};
+
+const PUBLIC_KEY_CREDENTIAL_TYPE: &str = "public-key";
+
+// Options for `navigator.credentials.create()` that register a new credential for the
+// signed-in user. Credentials they already have are excluded, so the same
+// authenticator isn't registered twice.
+pub async fn start_webauthn_registration(
+    State(state): State<AppState>,
+    AuthenticatedUser { email }: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let settings = &state.settings.webauthn;
+    let existing_credentials = state
+        .webauthn_credential_store
+        .read()
+        .await
+        .get_credentials(&email)
+        .await?;
+    let challenge = add_challenge(&state, WebauthnCeremony::Registration, Some(&email)).await?;
+
+    let options = CredentialCreationOptions {
+        rp: RelyingPartyEntity {
+            id: settings.rp_id.clone(),
+            name: settings.rp_name.clone(),
+        },
+        user: UserEntity {
+            id: user_handle(&email),
+            name: email.as_ref().to_owned(),
+            display_name: email.as_ref().to_owned(),
+        },
+        challenge,
+        pub_key_cred_params: [COSE_ALG_ES256, COSE_ALG_EDDSA]
+            .into_iter()
+            .map(|alg| CredentialParameters {
+                credential_type: PUBLIC_KEY_CREDENTIAL_TYPE.to_owned(),
+                alg,
+            })
+            .collect(),
+        timeout: settings.challenge_ttl_seconds * 1000,
+        exclude_credentials: existing_credentials.iter().map(descriptor).collect(),
+        authenticator_selection: AuthenticatorSelection {
+            resident_key: "preferred".to_owned(),
+            user_verification: "preferred".to_owned(),
+        },
+        attestation: "none".to_owned(),
+    };
+
+    Ok((StatusCode::OK, Json(options)))
+}
+
+pub async fn finish_webauthn_registration(
+    State(state): State<AppState>,
+    AuthenticatedUser { email }: AuthenticatedUser,
+    AppJson(credential): AppJson<RegistrationCredential>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let credential_id = base64url_decode(&credential.id, "credential id")?;
+    let client_data_json = base64url_decode(&credential.response.client_data_json, "client data")?;
+    let attestation_object = base64url_decode(
+        &credential.response.attestation_object,
+        "attestation object",
+    )?;
+
+    let client_data = ClientData::parse(&client_data_json)?;
+    take_challenge(
+        &state,
+        &client_data.challenge,
+        WebauthnCeremony::Registration,
+        Some(&email),
+    )
+    .await?;
+
+    let new_credential =
+        verify_registration(&relying_party(&state), &client_data, &attestation_object)?;
+    // Otherwise the browser could report one id while another one gets stored.
+    if new_credential.credential_id != credential_id {
+        return Err(WebauthnError::CredentialIdMismatch.into());
+    }
+
+    state
+        .webauthn_credential_store
+        .write()
+        .await
+        .add_credential(WebauthnCredential {
+            credential_id: base64url_encode(&new_credential.credential_id),
+            email,
+            public_key: base64url_encode(&new_credential.public_key),
+            sign_count: new_credential.sign_count,
+            created_at: unix_now(),
+        })
+        .await?;
+
+    Ok(StatusCode::CREATED)
+}
+
+// Options for a passwordless login. Without an email the authenticator offers its
+// discoverable credentials for this site. Unknown emails get the same response as
+// users without credentials, so the endpoint doesn't reveal who is registered.
+pub async fn start_webauthn_login(
+    State(state): State<AppState>,
+    AppJson(request): AppJson<StartWebauthnLoginRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email = request.email.map(Email::parse).transpose()?;
+    let options = request_options(&state, WebauthnCeremony::Login, email.as_ref()).await?;
+
+    Ok((StatusCode::OK, Json(options)))
+}
+
+// Sign in with a credential alone. The authenticator must have verified the user
+// (PIN or biometrics), which makes the credential a second factor in itself.
+pub async fn finish_webauthn_login(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    AppJson(credential): AppJson<AssertionCredential>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let stored = check_assertion(&state, &credential, WebauthnCeremony::Login, None).await?;
+
+    let updated_jar = start_session(&state, jar, &stored.email).await?;
+    Ok((updated_jar, StatusCode::OK))
+}
+
+// Options for `navigator.credentials.get()` with a fresh challenge for `ceremony`.
+pub(crate) async fn request_options(
+    state: &AppState,
+    ceremony: WebauthnCeremony,
+    email: Option<&Email>,
+) -> Result<CredentialRequestOptions, AuthAPIError> {
+    let allow_credentials = match email {
+        Some(email) => state
+            .webauthn_credential_store
+            .read()
+            .await
+            .get_credentials(email)
+            .await?
+            .iter()
+            .map(descriptor)
+            .collect(),
+        None => vec![],
+    };
+    let challenge = add_challenge(state, ceremony, email).await?;
+
+    let user_verification = match ceremony {
+        WebauthnCeremony::Login => "required",
+        _ => "preferred",
+    };
+
+    Ok(CredentialRequestOptions {
+        challenge,
+        timeout: state.settings.webauthn.challenge_ttl_seconds * 1000,
+        rp_id: state.settings.webauthn.rp_id.clone(),
+        allow_credentials,
+        user_verification: user_verification.to_owned(),
+    })
+}
+
+// Check an assertion made for `ceremony`, optionally by a credential of `email`, and
+// record the authenticator's new signature counter. Returns the stored credential.
+// The challenge is used up even if the check fails, so every attempt needs new options.
+pub(crate) async fn check_assertion(
+    state: &AppState,
+    credential: &AssertionCredential,
+    ceremony: WebauthnCeremony,
+    email: Option<&Email>,
+) -> Result<WebauthnCredential, AuthAPIError> {
+    let response = &credential.response;
+    let credential_id = base64url_decode(&credential.id, "credential id")?;
+    let client_data_json = base64url_decode(&response.client_data_json, "client data")?;
+    let authenticator_data = base64url_decode(&response.authenticator_data, "authenticator data")?;
+    let signature = base64url_decode(&response.signature, "signature")?;
+
+    let client_data = ClientData::parse(&client_data_json)?;
+    let challenge = take_challenge(state, &client_data.challenge, ceremony, email).await?;
+
+    let stored = state
+        .webauthn_credential_store
+        .read()
+        .await
+        .get_credential(&base64url_encode(&credential_id))
+        .await?;
+
+    // A challenge issued for a user can only be answered by one of their credentials.
+    let expected_emails = [challenge.email.as_ref(), email];
+    if expected_emails
+        .into_iter()
+        .flatten()
+        .any(|email| *email != stored.email)
+    {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+    // Discoverable credentials also report the user they were created for.
+    if let Some(handle) = response.user_handle.as_deref().filter(|h| !h.is_empty()) {
+        if handle != user_handle(&stored.email) {
+            return Err(AuthAPIError::IncorrectCredentials);
+        }
+    }
+
+    let public_key = base64url_decode(&stored.public_key, "stored public key")
+        .map_err(|_| AuthAPIError::UnexpectedError)?;
+    let sign_count = verify_assertion(
+        &relying_party(state),
+        &client_data,
+        &authenticator_data,
+        &signature,
+        &public_key,
+        stored.sign_count,
+        ceremony == WebauthnCeremony::Login,
+    )?;
+
+    state
+        .webauthn_credential_store
+        .write()
+        .await
+        .update_sign_count(&stored.credential_id, sign_count)
+        .await?;
+
+    Ok(WebauthnCredential {
+        sign_count,
+        ..stored
+    })
+}
+
+async fn add_challenge(
+    state: &AppState,
+    ceremony: WebauthnCeremony,
+    email: Option<&Email>,
+) -> Result<String, AuthAPIError> {
+    let challenge = generate_challenge();
+
+    state
+        .webauthn_challenge_store
+        .write()
+        .await
+        .add_challenge(WebauthnChallenge {
+            challenge: challenge.clone(),
+            ceremony,
+            email: email.cloned(),
+            expires_at: unix_now() + state.settings.webauthn.challenge_ttl_seconds,
+        })
+        .await?;
+
+    Ok(challenge)
+}
+
+// Use up the challenge echoed in the client data, which must have been issued for
+// `ceremony` and, if it names a user, for `email`.
+async fn take_challenge(
+    state: &AppState,
+    challenge: &str,
+    ceremony: WebauthnCeremony,
+    email: Option<&Email>,
+) -> Result<WebauthnChallenge, AuthAPIError> {
+    let challenge = state
+        .webauthn_challenge_store
+        .write()
+        .await
+        .take_challenge(challenge)
+        .await?;
+
+    let email_matches = match (email, &challenge.email) {
+        (Some(email), Some(expected)) => email == expected,
+        (Some(_), None) => false,
+        (None, _) => true,
+    };
+    if challenge.ceremony != ceremony || !email_matches {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+    Ok(challenge)
+}
+
+fn relying_party(state: &AppState) -> RelyingParty<'_> {
+    RelyingParty {
+        id: &state.settings.webauthn.rp_id,
+        origins: &state.settings.webauthn.origins,
+    }
+}
+
+fn descriptor(credential: &WebauthnCredential) -> CredentialDescriptor {
+    CredentialDescriptor {
+        credential_type: PUBLIC_KEY_CREDENTIAL_TYPE.to_owned(),
+        id: credential.credential_id.clone(),
+    }
+}
+
+// Responses the authenticator could have produced for a different request are
+// rejected as invalid input; those that fail a check are treated like a wrong password.
+impl From<WebauthnError> for AuthAPIError {
+    fn from(e: WebauthnError) -> Self {
+        match e {
+            WebauthnError::Malformed(_)
+            | WebauthnError::UnsupportedAttestation(_)
+            | WebauthnError::UnsupportedAlgorithm
+            | WebauthnError::CredentialIdMismatch => AuthAPIError::InvalidInput(e.to_string()),
+            _ => AuthAPIError::IncorrectCredentials,
+        }
+    }
+}
+
+impl From<WebauthnCredentialStoreError> for AuthAPIError {
+    fn from(e: WebauthnCredentialStoreError) -> Self {
+        match e {
+            WebauthnCredentialStoreError::CredentialAlreadyExists => {
+                AuthAPIError::InvalidInput("Credential already registered".to_owned())
+            }
+            WebauthnCredentialStoreError::CredentialNotFound => AuthAPIError::IncorrectCredentials,
+            WebauthnCredentialStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+        }
+    }
+}
+
+impl From<WebauthnChallengeStoreError> for AuthAPIError {
+    fn from(e: WebauthnChallengeStoreError) -> Self {
+        match e {
+            WebauthnChallengeStoreError::ChallengeNotFound => AuthAPIError::IncorrectCredentials,
+            WebauthnChallengeStoreError::UnexpectedError => AuthAPIError::UnexpectedError,
+        }
+    }
+}
+
+// The JSON forms of the WebAuthn options and responses, as produced and accepted by
+// `PublicKeyCredential.parseCreationOptionsFromJSON()` and `toJSON()` in browsers.
+// Binary values are base64url encoded.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CredentialCreationOptions {
+    pub rp: RelyingPartyEntity,
+    pub user: UserEntity,
+    pub challenge: String,
+    #[serde(rename = "pubKeyCredParams")]
+    pub pub_key_cred_params: Vec<CredentialParameters>,
+    // Milliseconds.
+    pub timeout: u64,
+    #[serde(rename = "excludeCredentials")]
+    pub exclude_credentials: Vec<CredentialDescriptor>,
+    #[serde(rename = "authenticatorSelection")]
+    pub authenticator_selection: AuthenticatorSelection,
+    pub attestation: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RelyingPartyEntity {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct UserEntity {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CredentialParameters {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub alg: i64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CredentialDescriptor {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub id: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuthenticatorSelection {
+    #[serde(rename = "residentKey")]
+    pub resident_key: String,
+    #[serde(rename = "userVerification")]
+    pub user_verification: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CredentialRequestOptions {
+    pub challenge: String,
+    // Milliseconds.
+    pub timeout: u64,
+    #[serde(rename = "rpId")]
+    pub rp_id: String,
+    #[serde(rename = "allowCredentials")]
+    pub allow_credentials: Vec<CredentialDescriptor>,
+    #[serde(rename = "userVerification")]
+    pub user_verification: String,
+}
+
+#[derive(Deserialize)]
+pub struct RegistrationCredential {
+    pub id: String,
+    pub response: AttestationResponse,
+}
+
+#[derive(Deserialize)]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "attestationObject")]
+    pub attestation_object: String,
+}
+
+#[derive(Deserialize)]
+pub struct AssertionCredential {
+    pub id: String,
+    pub response: AssertionResponse,
+}
+
+#[derive(Deserialize)]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "authenticatorData")]
+    pub authenticator_data: String,
+    pub signature: String,
+    #[serde(rename = "userHandle", default)]
+    pub user_handle: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct StartWebauthnLoginRequest {
+    #[serde(default)]
+    pub email: Option<String>,
+}
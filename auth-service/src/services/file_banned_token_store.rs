@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    domain::{BannedTokenStore, BannedTokenStoreError},
+    utils::time::unix_now,
+};
+
+// Keeps banned tokens in memory and mirrors every change to a JSON file,
+// so a logout stays effective across a restart of the service.
+pub struct FileBannedTokenStore {
+    path: PathBuf,
+    tokens: HashMap<String, u64>,
+}
+
+impl FileBannedTokenStore {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, BannedTokenStoreError> {
+        let path = path.as_ref().to_path_buf();
+
+        let tokens = match tokio::fs::read(&path).await {
+            Ok(contents) => serde_json::from_slice(&contents)
+                .map_err(|_| BannedTokenStoreError::UnexpectedError)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(_) => return Err(BannedTokenStoreError::UnexpectedError),
+        };
+
+        Ok(Self { path, tokens })
+    }
+
+    async fn persist(&self) -> Result<(), BannedTokenStoreError> {
+        let contents = serde_json::to_vec_pretty(&self.tokens)
+            .map_err(|_| BannedTokenStoreError::UnexpectedError)?;
+
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|_| BannedTokenStoreError::UnexpectedError)?;
+        }
+
+        // Write to a temporary file first and rename it over the real one,
+        // so a crash mid-write never leaves a truncated store behind.
+        let tmp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, contents)
+            .await
+            .map_err(|_| BannedTokenStoreError::UnexpectedError)?;
+        tokio::fs::rename(&tmp_path, &self.path)
+            .await
+            .map_err(|_| BannedTokenStoreError::UnexpectedError)
+    }
+}
+
+#[async_trait::async_trait]
+impl BannedTokenStore for FileBannedTokenStore {
+    async fn add_token(
+        &mut self,
+        jti: String,
+        expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        // Expired tokens are rejected anyway, so there's no need to remember them.
+        let now = unix_now();
+        self.tokens.retain(|_, exp| *exp > now);
+
+        self.tokens.insert(jti, expires_at);
+        self.persist().await
+    }
+
+    async fn contains_token(&self, jti: &str) -> Result<bool, BannedTokenStoreError> {
+        Ok(self
+            .tokens
+            .get(jti)
+            .is_some_and(|expires_at| *expires_at > unix_now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tokens_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("banned_tokens.json");
+
+        let mut store = FileBannedTokenStore::open(&path).await.unwrap();
+        store
+            .add_token("jti".to_owned(), unix_now() + 600)
+            .await
+            .unwrap();
+
+        let reopened = FileBannedTokenStore::open(&path).await.unwrap();
+        assert_eq!(reopened.contains_token("jti").await, Ok(true));
+        assert_eq!(reopened.contains_token("other").await, Ok(false));
+    }
+}
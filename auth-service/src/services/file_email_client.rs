@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use lettre::{AsyncFileTransport, AsyncTransport, Tokio1Executor};
+
+use super::build_message;
+use crate::domain::{Email, EmailClient, EmailClientError};
+
+// Writes every message as an `.eml` file into a directory instead of sending it,
+// which is handy for local development without a mail server.
+pub struct FileEmailClient {
+    sender: String,
+    transport: AsyncFileTransport<Tokio1Executor>,
+}
+
+impl FileEmailClient {
+    pub fn new(sender: String, directory: impl AsRef<Path>) -> std::io::Result<Self> {
+        std::fs::create_dir_all(directory.as_ref())?;
+
+        Ok(Self {
+            sender,
+            transport: AsyncFileTransport::new(directory),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailClient for FileEmailClient {
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), EmailClientError> {
+        let message = build_message(&self.sender, recipient.as_ref(), subject, content)?;
+
+        self.transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| EmailClientError::DeliveryFailed(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_email() -> Email {
+        Email::parse("test@example.com".to_owned()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_writes_eml_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = FileEmailClient::new("no-reply@example.com".to_owned(), dir.path()).unwrap();
+
+        client
+            .send_email(&test_email(), "Subject", "Hello there")
+            .await
+            .unwrap();
+
+        let entries: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].extension().unwrap(), "eml");
+
+        let contents = std::fs::read_to_string(&entries[0]).unwrap();
+        assert!(contents.contains("To: test@example.com"));
+        assert!(contents.contains("Subject: Subject"));
+        assert!(contents.contains("Hello there"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_sender() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = FileEmailClient::new("not-an-address".to_owned(), dir.path()).unwrap();
+
+        assert!(matches!(
+            client.send_email(&test_email(), "Subject", "Hi").await,
+            Err(EmailClientError::InvalidAddress(_))
+        ));
+    }
+}
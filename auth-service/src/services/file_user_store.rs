@@ -0,0 +1,131 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::domain::{Email, User, UserStore, UserStoreError};
+
+// Keeps users in memory and mirrors every change to a JSON file,
+// so accounts survive a restart of the service.
+pub struct FileUserStore {
+    path: PathBuf,
+    users: HashMap<Email, User>,
+}
+
+impl FileUserStore {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, UserStoreError> {
+        let path = path.as_ref().to_path_buf();
+
+        let users = match tokio::fs::read(&path).await {
+            Ok(contents) => serde_json::from_slice::<Vec<User>>(&contents)
+                .map_err(|_| UserStoreError::UnexpectedError)?
+                .into_iter()
+                .map(|user| (user.email.clone(), user))
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(_) => return Err(UserStoreError::UnexpectedError),
+        };
+
+        Ok(Self { path, users })
+    }
+
+    async fn persist(&self) -> Result<(), UserStoreError> {
+        let users: Vec<&User> = self.users.values().collect();
+        let contents =
+            serde_json::to_vec_pretty(&users).map_err(|_| UserStoreError::UnexpectedError)?;
+
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|_| UserStoreError::UnexpectedError)?;
+        }
+
+        // Write to a temporary file first and rename it over the real one,
+        // so a crash mid-write never leaves a truncated store behind.
+        let tmp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, contents)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+        tokio::fs::rename(&tmp_path, &self.path)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)
+    }
+}
+
+#[async_trait::async_trait]
+impl UserStore for FileUserStore {
+    async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
+        if self.users.contains_key(&user.email) {
+            return Err(UserStoreError::UserAlreadyExists);
+        }
+        self.users.insert(user.email.clone(), user);
+        self.persist().await
+    }
+
+    async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
+        self.users
+            .get(email)
+            .cloned()
+            .ok_or(UserStoreError::UserNotFound)
+    }
+
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password_hash: String,
+    ) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.password_hash = password_hash;
+        self.persist().await
+    }
+
+    async fn update_user(&mut self, user: User) -> Result<(), UserStoreError> {
+        let stored = self
+            .users
+            .get_mut(&user.email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        *stored = user;
+        self.persist().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_users_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.json");
+        let user = User::new(
+            Email::parse("test@example.com".to_owned()).unwrap(),
+            "$argon2id$v=19$m=19456,t=2,p=1$c2FsdA$aGFzaA".to_owned(),
+            true,
+        );
+
+        let mut store = FileUserStore::open(&path).await.unwrap();
+        store.add_user(user.clone()).await.unwrap();
+
+        let mut reopened = FileUserStore::open(&path).await.unwrap();
+        assert_eq!(reopened.get_user(&user.email).await, Ok(user.clone()));
+        assert_eq!(
+            reopened.add_user(user).await,
+            Err(UserStoreError::UserAlreadyExists)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_rejects_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.json");
+        tokio::fs::write(&path, b"not json").await.unwrap();
+
+        assert!(matches!(
+            FileUserStore::open(&path).await,
+            Err(UserStoreError::UnexpectedError)
+        ));
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use crate::{
+    domain::{BannedTokenStore, BannedTokenStoreError},
+    utils::time::unix_now,
+};
+
+// Maps each banned `jti` to the unix timestamp at which its token expires.
+#[derive(Default)]
+pub struct HashmapBannedTokenStore {
+    tokens: HashMap<String, u64>,
+}
+
+#[async_trait::async_trait]
+impl BannedTokenStore for HashmapBannedTokenStore {
+    async fn add_token(
+        &mut self,
+        jti: String,
+        expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        // Expired tokens are rejected anyway, so there's no need to remember them.
+        let now = unix_now();
+        self.tokens.retain(|_, exp| *exp > now);
+
+        self.tokens.insert(jti, expires_at);
+        Ok(())
+    }
+
+    async fn contains_token(&self, jti: &str) -> Result<bool, BannedTokenStoreError> {
+        Ok(self
+            .tokens
+            .get(jti)
+            .is_some_and(|expires_at| *expires_at > unix_now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_and_contains_token() {
+        let mut store = HashmapBannedTokenStore::default();
+
+        assert_eq!(store.contains_token("jti").await, Ok(false));
+        store
+            .add_token("jti".to_owned(), unix_now() + 600)
+            .await
+            .unwrap();
+        assert_eq!(store.contains_token("jti").await, Ok(true));
+    }
+
+    #[tokio::test]
+    async fn test_expired_tokens_are_dropped() {
+        let mut store = HashmapBannedTokenStore::default();
+
+        store
+            .add_token("expired".to_owned(), unix_now() - 1)
+            .await
+            .unwrap();
+        assert_eq!(store.contains_token("expired").await, Ok(false));
+
+        store
+            .add_token("jti".to_owned(), unix_now() + 600)
+            .await
+            .unwrap();
+        assert!(!store.tokens.contains_key("expired"));
+    }
+}
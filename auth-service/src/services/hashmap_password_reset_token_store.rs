@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use crate::{
+    domain::{Email, PasswordResetToken, PasswordResetTokenStore, PasswordResetTokenStoreError},
+    utils::time::unix_now,
+};
+
+#[derive(Default)]
+pub struct HashmapPasswordResetTokenStore {
+    tokens: HashMap<String, PasswordResetToken>,
+}
+
+#[async_trait::async_trait]
+impl PasswordResetTokenStore for HashmapPasswordResetTokenStore {
+    async fn add_token(
+        &mut self,
+        token: PasswordResetToken,
+    ) -> Result<(), PasswordResetTokenStoreError> {
+        let now = unix_now();
+        self.tokens.retain(|_, token| token.expires_at > now);
+
+        self.tokens.insert(token.token_hash.clone(), token);
+        Ok(())
+    }
+
+    async fn get_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<PasswordResetToken, PasswordResetTokenStoreError> {
+        match self.tokens.get(token_hash) {
+            Some(token) if token.expires_at > unix_now() => Ok(token.clone()),
+            _ => Err(PasswordResetTokenStoreError::TokenNotFound),
+        }
+    }
+
+    async fn take_token(
+        &mut self,
+        token_hash: &str,
+    ) -> Result<PasswordResetToken, PasswordResetTokenStoreError> {
+        match self.tokens.remove(token_hash) {
+            Some(token) if token.expires_at > unix_now() => Ok(token),
+            _ => Err(PasswordResetTokenStoreError::TokenNotFound),
+        }
+    }
+
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), PasswordResetTokenStoreError> {
+        self.tokens.retain(|_, token| token.email != *email);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_token(token_hash: &str, expires_at: u64) -> PasswordResetToken {
+        PasswordResetToken {
+            token_hash: token_hash.to_owned(),
+            email: Email::parse("test@example.com".to_owned()).unwrap(),
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_can_only_be_taken_once() {
+        let mut store = HashmapPasswordResetTokenStore::default();
+        let token = test_token("hash", unix_now() + 900);
+        store.add_token(token.clone()).await.unwrap();
+
+        assert_eq!(store.get_token("hash").await, Ok(token.clone()));
+        assert_eq!(store.take_token("hash").await, Ok(token));
+        assert_eq!(
+            store.get_token("hash").await,
+            Err(PasswordResetTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.take_token("hash").await,
+            Err(PasswordResetTokenStoreError::TokenNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user_removes_only_their_tokens() {
+        let mut store = HashmapPasswordResetTokenStore::default();
+        let other = PasswordResetToken {
+            email: Email::parse("other@example.com".to_owned()).unwrap(),
+            ..test_token("other", unix_now() + 900)
+        };
+        for token in [
+            test_token("first", unix_now() + 900),
+            test_token("second", unix_now() + 900),
+            other.clone(),
+        ] {
+            store.add_token(token).await.unwrap();
+        }
+
+        store
+            .revoke_user(&Email::parse("test@example.com".to_owned()).unwrap())
+            .await
+            .unwrap();
+
+        for token_hash in ["first", "second"] {
+            assert_eq!(
+                store.get_token(token_hash).await,
+                Err(PasswordResetTokenStoreError::TokenNotFound)
+            );
+        }
+        assert_eq!(store.get_token("other").await, Ok(other));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_not_returned() {
+        let mut store = HashmapPasswordResetTokenStore::default();
+        store
+            .add_token(test_token("hash", unix_now() - 1))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_token("hash").await,
+            Err(PasswordResetTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.take_token("hash").await,
+            Err(PasswordResetTokenStoreError::TokenNotFound)
+        );
+    }
+}
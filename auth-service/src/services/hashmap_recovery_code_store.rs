@@ -0,0 +1,95 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::domain::{Email, RecoveryCodeStore, RecoveryCodeStoreError};
+
+#[derive(Default)]
+pub struct HashmapRecoveryCodeStore {
+    codes: HashMap<Email, HashSet<String>>,
+}
+
+#[async_trait::async_trait]
+impl RecoveryCodeStore for HashmapRecoveryCodeStore {
+    async fn replace_codes(
+        &mut self,
+        email: &Email,
+        code_hashes: Vec<String>,
+    ) -> Result<(), RecoveryCodeStoreError> {
+        self.codes
+            .insert(email.clone(), code_hashes.into_iter().collect());
+        Ok(())
+    }
+
+    async fn use_code(
+        &mut self,
+        email: &Email,
+        code_hash: &str,
+    ) -> Result<(), RecoveryCodeStoreError> {
+        let removed = self
+            .codes
+            .get_mut(email)
+            .is_some_and(|codes| codes.remove(code_hash));
+
+        if !removed {
+            return Err(RecoveryCodeStoreError::CodeNotFound);
+        }
+        Ok(())
+    }
+
+    async fn count_codes(&self, email: &Email) -> Result<usize, RecoveryCodeStoreError> {
+        Ok(self.codes.get(email).map_or(0, HashSet::len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_hashes(hashes: &[&str]) -> Vec<String> {
+        hashes.iter().map(|hash| hash.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_use_code() {
+        let mut store = HashmapRecoveryCodeStore::default();
+        let email = Email::parse("test@example.com".to_owned()).unwrap();
+        let other = Email::parse("other@example.com".to_owned()).unwrap();
+        store
+            .replace_codes(&email, test_hashes(&["a", "b"]))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.use_code(&other, "a").await,
+            Err(RecoveryCodeStoreError::CodeNotFound)
+        );
+        assert_eq!(store.use_code(&email, "a").await, Ok(()));
+        assert_eq!(
+            store.use_code(&email, "a").await,
+            Err(RecoveryCodeStoreError::CodeNotFound)
+        );
+        assert_eq!(store.count_codes(&email).await, Ok(1));
+        assert_eq!(store.count_codes(&other).await, Ok(0));
+    }
+
+    #[tokio::test]
+    async fn test_replace_codes() {
+        let mut store = HashmapRecoveryCodeStore::default();
+        let email = Email::parse("test@example.com".to_owned()).unwrap();
+        store
+            .replace_codes(&email, test_hashes(&["a", "b"]))
+            .await
+            .unwrap();
+
+        store
+            .replace_codes(&email, test_hashes(&["c", "d", "e"]))
+            .await
+            .unwrap();
+
+        assert_eq!(store.count_codes(&email).await, Ok(3));
+        assert_eq!(
+            store.use_code(&email, "a").await,
+            Err(RecoveryCodeStoreError::CodeNotFound)
+        );
+        assert_eq!(store.use_code(&email, "c").await, Ok(()));
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use crate::{
+    domain::{Email, RefreshToken, RefreshTokenStore, RefreshTokenStoreError},
+    utils::time::unix_now,
+};
+
+#[derive(Default)]
+pub struct HashmapRefreshTokenStore {
+    tokens: HashMap<String, RefreshToken>,
+}
+
+#[async_trait::async_trait]
+impl RefreshTokenStore for HashmapRefreshTokenStore {
+    async fn add_token(&mut self, token: RefreshToken) -> Result<(), RefreshTokenStoreError> {
+        let now = unix_now();
+        self.tokens.retain(|_, token| token.expires_at > now);
+
+        self.tokens.insert(token.token_hash.clone(), token);
+        Ok(())
+    }
+
+    async fn get_token(&self, token_hash: &str) -> Result<RefreshToken, RefreshTokenStoreError> {
+        self.tokens
+            .get(token_hash)
+            .cloned()
+            .ok_or(RefreshTokenStoreError::TokenNotFound)
+    }
+
+    async fn use_token(
+        &mut self,
+        token_hash: &str,
+    ) -> Result<RefreshToken, RefreshTokenStoreError> {
+        let token = self
+            .tokens
+            .get_mut(token_hash)
+            .ok_or(RefreshTokenStoreError::TokenNotFound)?;
+
+        let previous = token.clone();
+        token.used = true;
+        Ok(previous)
+    }
+
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        self.tokens.retain(|_, token| token.family_id != family_id);
+        Ok(())
+    }
+
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError> {
+        self.tokens.retain(|_, token| token.email != *email);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_token(token_hash: &str, family_id: &str) -> RefreshToken {
+        RefreshToken {
+            token_hash: token_hash.to_owned(),
+            family_id: family_id.to_owned(),
+            email: Email::parse("test@example.com".to_owned()).unwrap(),
+            expires_at: unix_now() + 600,
+            used: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_use_token_reports_previous_use() {
+        let mut store = HashmapRefreshTokenStore::default();
+        store.add_token(test_token("hash", "family")).await.unwrap();
+
+        assert!(!store.use_token("hash").await.unwrap().used);
+        assert!(store.use_token("hash").await.unwrap().used);
+        assert_eq!(
+            store.use_token("other").await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoke_family() {
+        let mut store = HashmapRefreshTokenStore::default();
+        store
+            .add_token(test_token("first", "family"))
+            .await
+            .unwrap();
+        store
+            .add_token(test_token("second", "family"))
+            .await
+            .unwrap();
+        store.add_token(test_token("third", "other")).await.unwrap();
+
+        store.revoke_family("family").await.unwrap();
+
+        assert_eq!(
+            store.get_token("first").await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.get_token("second").await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.get_token("third").await,
+            Ok(test_token("third", "other"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user() {
+        let mut store = HashmapRefreshTokenStore::default();
+        let other_user = RefreshToken {
+            email: Email::parse("other@example.com".to_owned()).unwrap(),
+            ..test_token("third", "other")
+        };
+        store
+            .add_token(test_token("first", "family"))
+            .await
+            .unwrap();
+        store
+            .add_token(test_token("second", "second-login"))
+            .await
+            .unwrap();
+        store.add_token(other_user.clone()).await.unwrap();
+
+        store
+            .revoke_user(&Email::parse("test@example.com".to_owned()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_token("first").await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.get_token("second").await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(store.get_token("third").await, Ok(other_user));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::domain::{SigningKey, SigningKeyStatus, SigningKeyStore, SigningKeyStoreError};
+
+// Keys only live as long as the process, so every restart starts a new key set.
+#[derive(Default)]
+pub struct HashmapSigningKeyStore {
+    keys: Vec<SigningKey>,
+}
+
+#[async_trait::async_trait]
+impl SigningKeyStore for HashmapSigningKeyStore {
+    async fn add_key(&mut self, key: SigningKey) -> Result<(), SigningKeyStoreError> {
+        if self.keys.iter().any(|existing| existing.kid == key.kid) {
+            return Err(SigningKeyStoreError::UnexpectedError);
+        }
+
+        self.keys.push(key);
+        Ok(())
+    }
+
+    async fn get_keys(&self) -> Result<Vec<SigningKey>, SigningKeyStoreError> {
+        Ok(self.keys.clone())
+    }
+
+    async fn promote_key(&mut self, kid: &str) -> Result<(), SigningKeyStoreError> {
+        if !self.keys.iter().any(|key| key.kid == kid) {
+            return Err(SigningKeyStoreError::KeyNotFound);
+        }
+
+        for key in &mut self.keys {
+            key.status = if key.kid == kid {
+                SigningKeyStatus::Signing
+            } else {
+                SigningKeyStatus::Standby
+            };
+        }
+        Ok(())
+    }
+
+    async fn retire_key(&mut self, kid: &str) -> Result<(), SigningKeyStoreError> {
+        let index = self
+            .keys
+            .iter()
+            .position(|key| key.kid == kid)
+            .ok_or(SigningKeyStoreError::KeyNotFound)?;
+
+        if self.keys[index].status == SigningKeyStatus::Signing {
+            return Err(SigningKeyStoreError::KeyInUse);
+        }
+
+        self.keys.remove(index);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key(kid: &str) -> SigningKey {
+        SigningKey {
+            kid: kid.to_owned(),
+            algorithm: "EdDSA".to_owned(),
+            private_key: "private key".to_owned(),
+            status: SigningKeyStatus::Standby,
+            created_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_promote_key_demotes_previous_signing_key() {
+        let mut store = HashmapSigningKeyStore::default();
+        store.add_key(test_key("first")).await.unwrap();
+        store.add_key(test_key("second")).await.unwrap();
+
+        store.promote_key("first").await.unwrap();
+        store.promote_key("second").await.unwrap();
+
+        let statuses: Vec<_> = store
+            .get_keys()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|key| (key.kid, key.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("first".to_owned(), SigningKeyStatus::Standby),
+                ("second".to_owned(), SigningKeyStatus::Signing),
+            ]
+        );
+        assert_eq!(
+            store.promote_key("unknown").await,
+            Err(SigningKeyStoreError::KeyNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retire_key() {
+        let mut store = HashmapSigningKeyStore::default();
+        store.add_key(test_key("first")).await.unwrap();
+        store.add_key(test_key("second")).await.unwrap();
+        store.promote_key("second").await.unwrap();
+
+        assert_eq!(
+            store.retire_key("second").await,
+            Err(SigningKeyStoreError::KeyInUse)
+        );
+        store.retire_key("first").await.unwrap();
+        assert_eq!(
+            store.retire_key("first").await,
+            Err(SigningKeyStoreError::KeyNotFound)
+        );
+        assert_eq!(store.get_keys().await.unwrap().len(), 1);
+    }
+}
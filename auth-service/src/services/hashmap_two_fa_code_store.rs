@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use crate::{
+    domain::{Email, LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
+    utils::time::unix_now,
+};
+
+#[derive(Default)]
+pub struct HashmapTwoFACodeStore {
+    // Login attempt, code, expiry and number of wrong codes so far.
+    codes: HashMap<Email, (LoginAttemptId, TwoFACode, u64, u32)>,
+}
+
+#[async_trait::async_trait]
+impl TwoFACodeStore for HashmapTwoFACodeStore {
+    async fn add_code(
+        &mut self,
+        email: Email,
+        login_attempt_id: LoginAttemptId,
+        code: TwoFACode,
+        expires_at: u64,
+    ) -> Result<(), TwoFACodeStoreError> {
+        let now = unix_now();
+        self.codes.retain(|_, (_, _, exp, _)| *exp > now);
+
+        self.codes
+            .insert(email, (login_attempt_id, code, expires_at, 0));
+        Ok(())
+    }
+
+    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
+        self.codes.remove(email);
+        Ok(())
+    }
+
+    async fn get_code(
+        &self,
+        email: &Email,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+        match self.codes.get(email) {
+            Some((login_attempt_id, code, expires_at, _)) if *expires_at > unix_now() => {
+                Ok((login_attempt_id.clone(), code.clone()))
+            }
+            _ => Err(TwoFACodeStoreError::LoginAttemptIdNotFound),
+        }
+    }
+
+    async fn take_code(
+        &mut self,
+        email: &Email,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+        match self.codes.remove(email) {
+            Some((login_attempt_id, code, expires_at, _)) if expires_at > unix_now() => {
+                Ok((login_attempt_id, code))
+            }
+            _ => Err(TwoFACodeStoreError::LoginAttemptIdNotFound),
+        }
+    }
+
+    async fn record_failure(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+        match self.codes.get_mut(email) {
+            Some((_, _, expires_at, failures)) if *expires_at > unix_now() => {
+                *failures += 1;
+                Ok(*failures)
+            }
+            _ => Err(TwoFACodeStoreError::LoginAttemptIdNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_email() -> Email {
+        Email::parse("test@example.com".to_owned()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_add_get_and_remove_code() {
+        let mut store = HashmapTwoFACodeStore::default();
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+
+        store
+            .add_code(
+                test_email(),
+                login_attempt_id.clone(),
+                code.clone(),
+                unix_now() + 600,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_code(&test_email()).await,
+            Ok((login_attempt_id, code))
+        );
+
+        store.remove_code(&test_email()).await.unwrap();
+        assert_eq!(
+            store.get_code(&test_email()).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_code_can_only_be_taken_once() {
+        let mut store = HashmapTwoFACodeStore::default();
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+
+        store
+            .add_code(
+                test_email(),
+                login_attempt_id.clone(),
+                code.clone(),
+                unix_now() + 600,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            store.take_code(&test_email()).await,
+            Ok((login_attempt_id, code))
+        );
+        assert_eq!(
+            store.take_code(&test_email()).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_counted_per_code() {
+        let mut store = HashmapTwoFACodeStore::default();
+        for _ in 0..2 {
+            store
+                .add_code(
+                    test_email(),
+                    LoginAttemptId::default(),
+                    TwoFACode::default(),
+                    unix_now() + 600,
+                )
+                .await
+                .unwrap();
+            assert_eq!(store.record_failure(&test_email()).await, Ok(1));
+            assert_eq!(store.record_failure(&test_email()).await, Ok(2));
+        }
+
+        store.remove_code(&test_email()).await.unwrap();
+        assert_eq!(
+            store.record_failure(&test_email()).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_code_is_not_returned() {
+        let mut store = HashmapTwoFACodeStore::default();
+
+        store
+            .add_code(
+                test_email(),
+                LoginAttemptId::default(),
+                TwoFACode::default(),
+                unix_now() - 1,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_code(&test_email()).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[test]
+    fn test_parse_two_fa_code() {
+        assert!(TwoFACode::parse("123456".to_owned()).is_ok());
+        assert!(TwoFACode::parse("12345".to_owned()).is_err());
+        assert!(TwoFACode::parse("12345a".to_owned()).is_err());
+        assert!(TwoFACode::parse(TwoFACode::default().as_ref().to_owned()).is_ok());
+    }
+
+    #[test]
+    fn test_parse_login_attempt_id() {
+        assert!(LoginAttemptId::parse(uuid::Uuid::new_v4().to_string()).is_ok());
+        assert!(LoginAttemptId::parse("some-id".to_owned()).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use crate::domain::{Email, User, UserStore, UserStoreError};
+
+#[derive(Default)]
+pub struct HashmapUserStore {
+    users: HashMap<Email, User>,
+}
+
+#[async_trait::async_trait]
+impl UserStore for HashmapUserStore {
+    async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
+        if self.users.contains_key(&user.email) {
+            return Err(UserStoreError::UserAlreadyExists);
+        }
+        self.users.insert(user.email.clone(), user);
+        Ok(())
+    }
+
+    async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
+        self.users
+            .get(email)
+            .cloned()
+            .ok_or(UserStoreError::UserNotFound)
+    }
+
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password_hash: String,
+    ) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.password_hash = password_hash;
+        Ok(())
+    }
+
+    async fn update_user(&mut self, user: User) -> Result<(), UserStoreError> {
+        let stored = self
+            .users
+            .get_mut(&user.email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        *stored = user;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_user() -> User {
+        User::new(
+            Email::parse("test@example.com".to_owned()).unwrap(),
+            "$argon2id$v=19$m=19456,t=2,p=1$c2FsdA$aGFzaA".to_owned(),
+            false,
+        )
+    }
+
+    fn missing_email() -> Email {
+        Email::parse("missing@example.com".to_owned()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_add_user() {
+        let mut store = HashmapUserStore::default();
+        let user = test_user();
+
+        assert_eq!(store.add_user(user.clone()).await, Ok(()));
+        assert_eq!(
+            store.add_user(user).await,
+            Err(UserStoreError::UserAlreadyExists)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_user() {
+        let mut store = HashmapUserStore::default();
+        let user = test_user();
+        store.add_user(user.clone()).await.unwrap();
+
+        assert_eq!(store.get_user(&user.email).await, Ok(user));
+        assert_eq!(
+            store.get_user(&missing_email()).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_password() {
+        let mut store = HashmapUserStore::default();
+        let user = test_user();
+        store.add_user(user.clone()).await.unwrap();
+
+        assert_eq!(
+            store
+                .update_password(&user.email, "new-hash".to_owned())
+                .await,
+            Ok(())
+        );
+        assert_eq!(
+            store.get_user(&user.email).await.unwrap().password_hash,
+            "new-hash"
+        );
+        assert_eq!(
+            store
+                .update_password(&missing_email(), "new-hash".to_owned())
+                .await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_user() {
+        let mut store = HashmapUserStore::default();
+        let mut user = test_user();
+        store.add_user(user.clone()).await.unwrap();
+
+        user.requires_2fa = true;
+        user.pending_totp_secret = Some("JBSWY3DPEHPK3PXP".to_owned());
+
+        assert_eq!(store.update_user(user.clone()).await, Ok(()));
+        assert_eq!(store.get_user(&user.email).await, Ok(user));
+
+        let mut missing = test_user();
+        missing.email = missing_email();
+        assert_eq!(
+            store.update_user(missing).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use crate::{
+    domain::{WebauthnChallenge, WebauthnChallengeStore, WebauthnChallengeStoreError},
+    utils::time::unix_now,
+};
+
+#[derive(Default)]
+pub struct HashmapWebauthnChallengeStore {
+    challenges: HashMap<String, WebauthnChallenge>,
+}
+
+#[async_trait::async_trait]
+impl WebauthnChallengeStore for HashmapWebauthnChallengeStore {
+    async fn add_challenge(
+        &mut self,
+        challenge: WebauthnChallenge,
+    ) -> Result<(), WebauthnChallengeStoreError> {
+        let now = unix_now();
+        self.challenges
+            .retain(|_, challenge| challenge.expires_at > now);
+
+        self.challenges
+            .insert(challenge.challenge.clone(), challenge);
+        Ok(())
+    }
+
+    async fn take_challenge(
+        &mut self,
+        challenge: &str,
+    ) -> Result<WebauthnChallenge, WebauthnChallengeStoreError> {
+        match self.challenges.remove(challenge) {
+            Some(challenge) if challenge.expires_at > unix_now() => Ok(challenge),
+            _ => Err(WebauthnChallengeStoreError::ChallengeNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Email, WebauthnCeremony};
+
+    fn test_challenge(challenge: &str, expires_at: u64) -> WebauthnChallenge {
+        WebauthnChallenge {
+            challenge: challenge.to_owned(),
+            ceremony: WebauthnCeremony::SecondFactor,
+            email: Some(Email::parse("test@example.com".to_owned()).unwrap()),
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_challenge_can_only_be_taken_once() {
+        let mut store = HashmapWebauthnChallengeStore::default();
+        let challenge = test_challenge("challenge", unix_now() + 300);
+        store.add_challenge(challenge.clone()).await.unwrap();
+
+        assert_eq!(store.take_challenge("challenge").await, Ok(challenge));
+        assert_eq!(
+            store.take_challenge("challenge").await,
+            Err(WebauthnChallengeStoreError::ChallengeNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_challenge_is_not_returned() {
+        let mut store = HashmapWebauthnChallengeStore::default();
+        store
+            .add_challenge(test_challenge("challenge", unix_now() - 1))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.take_challenge("challenge").await,
+            Err(WebauthnChallengeStoreError::ChallengeNotFound)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use crate::domain::{
+    Email, WebauthnCredential, WebauthnCredentialStore, WebauthnCredentialStoreError,
+};
+
+#[derive(Default)]
+pub struct HashmapWebauthnCredentialStore {
+    credentials: HashMap<String, WebauthnCredential>,
+}
+
+#[async_trait::async_trait]
+impl WebauthnCredentialStore for HashmapWebauthnCredentialStore {
+    async fn add_credential(
+        &mut self,
+        credential: WebauthnCredential,
+    ) -> Result<(), WebauthnCredentialStoreError> {
+        if self.credentials.contains_key(&credential.credential_id) {
+            return Err(WebauthnCredentialStoreError::CredentialAlreadyExists);
+        }
+
+        self.credentials
+            .insert(credential.credential_id.clone(), credential);
+        Ok(())
+    }
+
+    async fn get_credential(
+        &self,
+        credential_id: &str,
+    ) -> Result<WebauthnCredential, WebauthnCredentialStoreError> {
+        self.credentials
+            .get(credential_id)
+            .cloned()
+            .ok_or(WebauthnCredentialStoreError::CredentialNotFound)
+    }
+
+    async fn get_credentials(
+        &self,
+        email: &Email,
+    ) -> Result<Vec<WebauthnCredential>, WebauthnCredentialStoreError> {
+        let mut credentials: Vec<_> = self
+            .credentials
+            .values()
+            .filter(|credential| &credential.email == email)
+            .cloned()
+            .collect();
+        credentials.sort_by(|a, b| {
+            (a.created_at, &a.credential_id).cmp(&(b.created_at, &b.credential_id))
+        });
+
+        Ok(credentials)
+    }
+
+    async fn update_sign_count(
+        &mut self,
+        credential_id: &str,
+        sign_count: u32,
+    ) -> Result<(), WebauthnCredentialStoreError> {
+        let credential = self
+            .credentials
+            .get_mut(credential_id)
+            .ok_or(WebauthnCredentialStoreError::CredentialNotFound)?;
+
+        credential.sign_count = sign_count;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_credential(credential_id: &str, email: &str, created_at: u64) -> WebauthnCredential {
+        WebauthnCredential {
+            credential_id: credential_id.to_owned(),
+            email: Email::parse(email.to_owned()).unwrap(),
+            public_key: "public-key".to_owned(),
+            sign_count: 0,
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_credentials() {
+        let mut store = HashmapWebauthnCredentialStore::default();
+        let second = test_credential("second", "test@example.com", 2);
+        let first = test_credential("first", "test@example.com", 1);
+        let other = test_credential("other", "other@example.com", 1);
+        for credential in [&second, &first, &other] {
+            store.add_credential(credential.clone()).await.unwrap();
+        }
+
+        assert_eq!(
+            store.add_credential(first.clone()).await,
+            Err(WebauthnCredentialStoreError::CredentialAlreadyExists)
+        );
+        assert_eq!(store.get_credential("other").await, Ok(other));
+        assert_eq!(
+            store.get_credentials(&first.email).await,
+            Ok(vec![first, second])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_sign_count() {
+        let mut store = HashmapWebauthnCredentialStore::default();
+        store
+            .add_credential(test_credential("id", "test@example.com", 1))
+            .await
+            .unwrap();
+
+        store.update_sign_count("id", 7).await.unwrap();
+
+        assert_eq!(store.get_credential("id").await.unwrap().sign_count, 7);
+        assert_eq!(
+            store.update_sign_count("unknown", 1).await,
+            Err(WebauthnCredentialStoreError::CredentialNotFound)
+        );
+    }
+}
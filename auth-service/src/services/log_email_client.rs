@@ -0,0 +1,22 @@
+use crate::domain::{Email, EmailClient, EmailClientError};
+
+// Doesn't deliver anything; it only logs who a message was for. Bodies carry 2FA
+// codes and single-use links, so they are neither printed nor kept.
+#[derive(Default)]
+pub struct LogEmailClient;
+
+#[async_trait::async_trait]
+impl EmailClient for LogEmailClient {
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        subject: &str,
+        _content: &str,
+    ) -> Result<(), EmailClientError> {
+        println!(
+            "Not delivering email to {} with subject: {}",
+            recipient, subject
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+use crate::domain::{Email, EmailClient, EmailClientError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentEmail {
+    pub recipient: String,
+    pub subject: String,
+    pub content: String,
+}
+
+// Doesn't deliver anything; it records every message so tests can inspect them.
+// Only for tests: the messages are kept in memory for as long as it lives.
+#[derive(Default)]
+pub struct MockEmailClient {
+    sent_emails: Mutex<Vec<SentEmail>>,
+    failing: AtomicBool,
+}
+
+impl MockEmailClient {
+    // While set, every message fails with `DeliveryFailed` and isn't recorded.
+    pub fn set_failing(&self, failing: bool) {
+        self.failing.store(failing, Ordering::SeqCst);
+    }
+
+    pub fn sent_emails(&self) -> Vec<SentEmail> {
+        self.sent_emails
+            .lock()
+            .expect("Mock email client lock poisoned")
+            .clone()
+    }
+
+    pub fn last_email_to(&self, recipient: &str) -> Option<SentEmail> {
+        self.sent_emails()
+            .into_iter()
+            .rev()
+            .find(|email| email.recipient == recipient)
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailClient for MockEmailClient {
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), EmailClientError> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(EmailClientError::DeliveryFailed(
+                "mock configured to fail".to_owned(),
+            ));
+        }
+
+        self.sent_emails
+            .lock()
+            .map_err(|_| EmailClientError::DeliveryFailed("lock poisoned".to_owned()))?
+            .push(SentEmail {
+                recipient: recipient.to_string(),
+                subject: subject.to_owned(),
+                content: content.to_owned(),
+            });
+        Ok(())
+    }
+}
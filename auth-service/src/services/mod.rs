@@ -0,0 +1,116 @@
+mod file_banned_token_store;
+mod file_email_client;
+mod file_user_store;
+mod hashmap_banned_token_store;
+mod hashmap_password_reset_token_store;
+mod hashmap_recovery_code_store;
+mod hashmap_refresh_token_store;
+mod hashmap_signing_key_store;
+mod hashmap_two_fa_code_store;
+mod hashmap_user_store;
+mod hashmap_webauthn_challenge_store;
+mod hashmap_webauthn_credential_store;
+mod log_email_client;
+mod mock_email_client;
+mod postgres_recovery_code_store;
+mod postgres_refresh_token_store;
+mod postgres_signing_key_store;
+mod postgres_user_store;
+mod postgres_webauthn_credential_store;
+mod redis_banned_token_store;
+mod redis_password_reset_token_store;
+mod redis_two_fa_code_store;
+mod redis_webauthn_challenge_store;
+mod smtp_email_client;
+mod sqlite_banned_token_store;
+mod sqlite_password_reset_token_store;
+mod sqlite_recovery_code_store;
+mod sqlite_refresh_token_store;
+mod sqlite_signing_key_store;
+mod sqlite_two_fa_code_store;
+mod sqlite_user_store;
+mod sqlite_webauthn_challenge_store;
+mod sqlite_webauthn_credential_store;
+
+// re-export items from sub-modules
+pub use file_banned_token_store::*;
+pub use file_email_client::*;
+pub use file_user_store::*;
+pub use hashmap_banned_token_store::*;
+pub use hashmap_password_reset_token_store::*;
+pub use hashmap_recovery_code_store::*;
+pub use hashmap_refresh_token_store::*;
+pub use hashmap_signing_key_store::*;
+pub use hashmap_two_fa_code_store::*;
+pub use hashmap_user_store::*;
+pub use hashmap_webauthn_challenge_store::*;
+pub use hashmap_webauthn_credential_store::*;
+pub use log_email_client::*;
+pub use mock_email_client::*;
+pub use postgres_recovery_code_store::*;
+pub use postgres_refresh_token_store::*;
+pub use postgres_signing_key_store::*;
+pub use postgres_user_store::*;
+pub use postgres_webauthn_credential_store::*;
+pub use redis_banned_token_store::*;
+pub use redis_password_reset_token_store::*;
+pub use redis_two_fa_code_store::*;
+pub use redis_webauthn_challenge_store::*;
+pub use smtp_email_client::*;
+pub use sqlite_banned_token_store::*;
+pub use sqlite_password_reset_token_store::*;
+pub use sqlite_recovery_code_store::*;
+pub use sqlite_refresh_token_store::*;
+pub use sqlite_signing_key_store::*;
+pub use sqlite_two_fa_code_store::*;
+pub use sqlite_user_store::*;
+pub use sqlite_webauthn_challenge_store::*;
+pub use sqlite_webauthn_credential_store::*;
+
+use crate::domain::EmailClientError;
+
+// Build a plain-text message, shared by the email clients backed by lettre.
+fn build_message(
+    sender: &str,
+    recipient: &str,
+    subject: &str,
+    content: &str,
+) -> Result<lettre::Message, EmailClientError> {
+    let parse = |address: &str| {
+        address
+            .parse::<lettre::message::Mailbox>()
+            .map_err(|_| EmailClientError::InvalidAddress(address.to_owned()))
+    };
+
+    lettre::Message::builder()
+        .from(parse(sender)?)
+        .to(parse(recipient)?)
+        .subject(subject)
+        .header(lettre::message::header::ContentType::TEXT_PLAIN)
+        .body(content.to_owned())
+        .map_err(|e| EmailClientError::DeliveryFailed(e.to_string()))
+}
+
+// Fresh, migrated SQLite database in a temporary directory. Keep the directory
+// alive for as long as the pool is used.
+#[cfg(test)]
+async fn test_sqlite_pool() -> (tempfile::TempDir, sqlx::SqlitePool) {
+    let dir = tempfile::tempdir().unwrap();
+    let pool = crate::get_sqlite_pool(
+        &dir.path().join("auth.db"),
+        &crate::settings::SqliteSettings::default(),
+    )
+    .await
+    .unwrap();
+
+    (dir, pool)
+}
+
+// Connection to the Redis server named by `TEST_REDIS_URL`, or a local one.
+#[cfg(test)]
+async fn test_redis_connection() -> redis::aio::ConnectionManager {
+    let url =
+        std::env::var("TEST_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_owned());
+
+    crate::get_redis_connection(&url).await.unwrap()
+}
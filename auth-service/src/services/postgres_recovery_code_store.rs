@@ -0,0 +1,79 @@
+use sqlx::{PgPool, Row};
+
+use crate::domain::{Email, RecoveryCodeStore, RecoveryCodeStoreError};
+
+pub struct PostgresRecoveryCodeStore {
+    pool: PgPool,
+}
+
+impl PostgresRecoveryCodeStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl RecoveryCodeStore for PostgresRecoveryCodeStore {
+    async fn replace_codes(
+        &mut self,
+        email: &Email,
+        code_hashes: Vec<String>,
+    ) -> Result<(), RecoveryCodeStoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        sqlx::query("DELETE FROM recovery_codes WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        for code_hash in code_hashes {
+            sqlx::query("INSERT INTO recovery_codes (email, code_hash) VALUES ($1, $2)")
+                .bind(email.as_ref())
+                .bind(code_hash)
+                .execute(&mut *tx)
+                .await
+                .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)
+    }
+
+    async fn use_code(
+        &mut self,
+        email: &Email,
+        code_hash: &str,
+    ) -> Result<(), RecoveryCodeStoreError> {
+        let rows_affected =
+            sqlx::query("DELETE FROM recovery_codes WHERE email = $1 AND code_hash = $2")
+                .bind(email.as_ref())
+                .bind(code_hash)
+                .execute(&self.pool)
+                .await
+                .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?
+                .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(RecoveryCodeStoreError::CodeNotFound);
+        }
+        Ok(())
+    }
+
+    async fn count_codes(&self, email: &Email) -> Result<usize, RecoveryCodeStoreError> {
+        let row = sqlx::query("SELECT COUNT(*) AS count FROM recovery_codes WHERE email = $1")
+            .bind(email.as_ref())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        row.try_get::<i64, _>("count")
+            .map(|count| count as usize)
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)
+    }
+}
@@ -0,0 +1,113 @@
+use sqlx::{postgres::PgRow, PgPool, Row};
+
+use crate::{
+    domain::{Email, RefreshToken, RefreshTokenStore, RefreshTokenStoreError},
+    utils::time::unix_now,
+};
+
+pub struct PostgresRefreshTokenStore {
+    pool: PgPool,
+}
+
+impl PostgresRefreshTokenStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl RefreshTokenStore for PostgresRefreshTokenStore {
+    async fn add_token(&mut self, token: RefreshToken) -> Result<(), RefreshTokenStoreError> {
+        sqlx::query("DELETE FROM refresh_tokens WHERE expires_at <= $1")
+            .bind(unix_now() as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        sqlx::query(
+            "INSERT INTO refresh_tokens (token_hash, family_id, email, expires_at, used) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(&token.token_hash)
+        .bind(&token.family_id)
+        .bind(token.email.as_ref())
+        .bind(token.expires_at as i64)
+        .bind(token.used)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn get_token(&self, token_hash: &str) -> Result<RefreshToken, RefreshTokenStoreError> {
+        let row = sqlx::query(
+            "SELECT token_hash, family_id, email, expires_at, used \
+             FROM refresh_tokens WHERE token_hash = $1",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| RefreshTokenStoreError::UnexpectedError)?
+        .ok_or(RefreshTokenStoreError::TokenNotFound)?;
+
+        parse_row(&row)
+    }
+
+    async fn use_token(
+        &mut self,
+        token_hash: &str,
+    ) -> Result<RefreshToken, RefreshTokenStoreError> {
+        // The row lock taken by the conditional update means only one of two concurrent
+        // requests with the same token, on any replica, can see it unused.
+        let first_use =
+            sqlx::query("UPDATE refresh_tokens SET used = TRUE WHERE token_hash = $1 AND NOT used")
+                .bind(token_hash)
+                .execute(&self.pool)
+                .await
+                .map_err(|_| RefreshTokenStoreError::UnexpectedError)?
+                .rows_affected()
+                == 1;
+
+        let token = self.get_token(token_hash).await?;
+        Ok(RefreshToken {
+            used: !first_use,
+            ..token
+        })
+    }
+
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        sqlx::query("DELETE FROM refresh_tokens WHERE family_id = $1")
+            .bind(family_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError> {
+        sqlx::query("DELETE FROM refresh_tokens WHERE email = $1")
+            .bind(email.as_ref())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+fn parse_row(row: &PgRow) -> Result<RefreshToken, RefreshTokenStoreError> {
+    let parse = || -> Result<RefreshToken, sqlx::Error> {
+        Ok(RefreshToken {
+            token_hash: row.try_get("token_hash")?,
+            family_id: row.try_get("family_id")?,
+            email: Email::parse(row.try_get("email")?)
+                .map_err(|e| sqlx::Error::Decode(e.to_string().into()))?,
+            expires_at: row.try_get::<i64, _>("expires_at")? as u64,
+            used: row.try_get("used")?,
+        })
+    };
+
+    parse().map_err(|_| RefreshTokenStoreError::UnexpectedError)
+}
@@ -0,0 +1,106 @@
+use sqlx::{postgres::PgRow, PgPool, Row};
+
+use crate::domain::{SigningKey, SigningKeyStatus, SigningKeyStore, SigningKeyStoreError};
+
+pub struct PostgresSigningKeyStore {
+    pool: PgPool,
+}
+
+impl PostgresSigningKeyStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl SigningKeyStore for PostgresSigningKeyStore {
+    async fn add_key(&mut self, key: SigningKey) -> Result<(), SigningKeyStoreError> {
+        sqlx::query(
+            "INSERT INTO signing_keys (kid, algorithm, private_key, status, created_at) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(&key.kid)
+        .bind(&key.algorithm)
+        .bind(&key.private_key)
+        .bind(key.status.as_str())
+        .bind(key.created_at as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| SigningKeyStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn get_keys(&self) -> Result<Vec<SigningKey>, SigningKeyStoreError> {
+        sqlx::query(
+            "SELECT kid, algorithm, private_key, status, created_at \
+             FROM signing_keys ORDER BY created_at, kid",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_| SigningKeyStoreError::UnexpectedError)?
+        .iter()
+        .map(parse_row)
+        .collect()
+    }
+
+    async fn promote_key(&mut self, kid: &str) -> Result<(), SigningKeyStoreError> {
+        // A single statement, so there is never a moment without a signing key.
+        let rows_affected = sqlx::query(
+            "UPDATE signing_keys \
+             SET status = CASE WHEN kid = $1 THEN 'signing' ELSE 'standby' END \
+             WHERE EXISTS (SELECT 1 FROM signing_keys WHERE kid = $1)",
+        )
+        .bind(kid)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| SigningKeyStoreError::UnexpectedError)?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(SigningKeyStoreError::KeyNotFound);
+        }
+        Ok(())
+    }
+
+    async fn retire_key(&mut self, kid: &str) -> Result<(), SigningKeyStoreError> {
+        let rows_affected =
+            sqlx::query("DELETE FROM signing_keys WHERE kid = $1 AND status = 'standby'")
+                .bind(kid)
+                .execute(&self.pool)
+                .await
+                .map_err(|_| SigningKeyStoreError::UnexpectedError)?
+                .rows_affected();
+        if rows_affected == 1 {
+            return Ok(());
+        }
+
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM signing_keys WHERE kid = $1)")
+                .bind(kid)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|_| SigningKeyStoreError::UnexpectedError)?;
+
+        Err(if exists {
+            SigningKeyStoreError::KeyInUse
+        } else {
+            SigningKeyStoreError::KeyNotFound
+        })
+    }
+}
+
+fn parse_row(row: &PgRow) -> Result<SigningKey, SigningKeyStoreError> {
+    let parse = || -> Result<SigningKey, sqlx::Error> {
+        Ok(SigningKey {
+            kid: row.try_get("kid")?,
+            algorithm: row.try_get("algorithm")?,
+            private_key: row.try_get("private_key")?,
+            status: SigningKeyStatus::parse(row.try_get("status")?)
+                .map_err(|e| sqlx::Error::Decode(e.into()))?,
+            created_at: row.try_get::<i64, _>("created_at")? as u64,
+        })
+    };
+
+    parse().map_err(|_| SigningKeyStoreError::UnexpectedError)
+}
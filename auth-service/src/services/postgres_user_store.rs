@@ -0,0 +1,126 @@
+use sqlx::{postgres::PgRow, PgPool, Row};
+
+use crate::domain::{Email, TwoFAMethod, User, UserStore, UserStoreError};
+
+pub struct PostgresUserStore {
+    pool: PgPool,
+}
+
+impl PostgresUserStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl UserStore for PostgresUserStore {
+    async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
+        sqlx::query(
+            "INSERT INTO users \
+             (email, password_hash, requires_2fa, two_fa_method, totp_secret, pending_totp_secret, \
+             totp_last_step, email_verified, verification_email_sent_at, session_generation) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        )
+        .bind(user.email.as_ref())
+        .bind(&user.password_hash)
+        .bind(user.requires_2fa)
+        .bind(user.two_fa_method.as_str())
+        .bind(&user.totp_secret)
+        .bind(&user.pending_totp_secret)
+        .bind(user.totp_last_step.map(|step| step as i64))
+        .bind(user.email_verified)
+        .bind(user.verification_email_sent_at.map(|at| at as i64))
+        .bind(user.session_generation as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(e) if e.is_unique_violation() => UserStoreError::UserAlreadyExists,
+            _ => UserStoreError::UnexpectedError,
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
+        let row = sqlx::query(
+            "SELECT email, password_hash, requires_2fa, two_fa_method, totp_secret, \
+             pending_totp_secret, totp_last_step, email_verified, verification_email_sent_at, \
+             session_generation \
+             FROM users WHERE email = $1",
+        )
+        .bind(email.as_ref())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        user_from_row(&row).ok_or(UserStoreError::UnexpectedError)
+    }
+
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password_hash: String,
+    ) -> Result<(), UserStoreError> {
+        let result = sqlx::query("UPDATE users SET password_hash = $2 WHERE email = $1")
+            .bind(email.as_ref())
+            .bind(password_hash)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn update_user(&mut self, user: User) -> Result<(), UserStoreError> {
+        let result = sqlx::query(
+            "UPDATE users SET password_hash = $1, requires_2fa = $2, two_fa_method = $3, \
+             totp_secret = $4, pending_totp_secret = $5, totp_last_step = $6, email_verified = $7, \
+             verification_email_sent_at = $8, session_generation = $9 WHERE email = $10",
+        )
+        .bind(&user.password_hash)
+        .bind(user.requires_2fa)
+        .bind(user.two_fa_method.as_str())
+        .bind(&user.totp_secret)
+        .bind(&user.pending_totp_secret)
+        .bind(user.totp_last_step.map(|step| step as i64))
+        .bind(user.email_verified)
+        .bind(user.verification_email_sent_at.map(|at| at as i64))
+        .bind(user.session_generation as i64)
+        .bind(user.email.as_ref())
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+}
+
+fn user_from_row(row: &PgRow) -> Option<User> {
+    Some(User {
+        email: Email::parse(row.try_get("email").ok()?).ok()?,
+        password_hash: row.try_get("password_hash").ok()?,
+        requires_2fa: row.try_get("requires_2fa").ok()?,
+        two_fa_method: TwoFAMethod::parse(row.try_get("two_fa_method").ok()?).ok()?,
+        totp_secret: row.try_get("totp_secret").ok()?,
+        pending_totp_secret: row.try_get("pending_totp_secret").ok()?,
+        totp_last_step: row
+            .try_get::<Option<i64>, _>("totp_last_step")
+            .ok()?
+            .map(|step| step as u64),
+        email_verified: row.try_get("email_verified").ok()?,
+        verification_email_sent_at: row
+            .try_get::<Option<i64>, _>("verification_email_sent_at")
+            .ok()?
+            .map(|at| at as u64),
+        session_generation: row.try_get::<i64, _>("session_generation").ok()? as u64,
+    })
+}
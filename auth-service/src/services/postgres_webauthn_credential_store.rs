@@ -0,0 +1,113 @@
+use sqlx::{postgres::PgRow, PgPool, Row};
+
+use crate::domain::{
+    Email, WebauthnCredential, WebauthnCredentialStore, WebauthnCredentialStoreError,
+};
+
+pub struct PostgresWebauthnCredentialStore {
+    pool: PgPool,
+}
+
+impl PostgresWebauthnCredentialStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl WebauthnCredentialStore for PostgresWebauthnCredentialStore {
+    async fn add_credential(
+        &mut self,
+        credential: WebauthnCredential,
+    ) -> Result<(), WebauthnCredentialStoreError> {
+        sqlx::query(
+            "INSERT INTO webauthn_credentials \
+             (credential_id, email, public_key, sign_count, created_at) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(&credential.credential_id)
+        .bind(credential.email.as_ref())
+        .bind(&credential.public_key)
+        .bind(credential.sign_count as i64)
+        .bind(credential.created_at as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(e) if e.is_unique_violation() => {
+                WebauthnCredentialStoreError::CredentialAlreadyExists
+            }
+            _ => WebauthnCredentialStoreError::UnexpectedError,
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_credential(
+        &self,
+        credential_id: &str,
+    ) -> Result<WebauthnCredential, WebauthnCredentialStoreError> {
+        let row = sqlx::query(
+            "SELECT credential_id, email, public_key, sign_count, created_at \
+             FROM webauthn_credentials WHERE credential_id = $1",
+        )
+        .bind(credential_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| WebauthnCredentialStoreError::UnexpectedError)?
+        .ok_or(WebauthnCredentialStoreError::CredentialNotFound)?;
+
+        parse_row(&row)
+    }
+
+    async fn get_credentials(
+        &self,
+        email: &Email,
+    ) -> Result<Vec<WebauthnCredential>, WebauthnCredentialStoreError> {
+        sqlx::query(
+            "SELECT credential_id, email, public_key, sign_count, created_at \
+             FROM webauthn_credentials WHERE email = $1 ORDER BY created_at, credential_id",
+        )
+        .bind(email.as_ref())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_| WebauthnCredentialStoreError::UnexpectedError)?
+        .iter()
+        .map(parse_row)
+        .collect()
+    }
+
+    async fn update_sign_count(
+        &mut self,
+        credential_id: &str,
+        sign_count: u32,
+    ) -> Result<(), WebauthnCredentialStoreError> {
+        let rows_affected =
+            sqlx::query("UPDATE webauthn_credentials SET sign_count = $1 WHERE credential_id = $2")
+                .bind(sign_count as i64)
+                .bind(credential_id)
+                .execute(&self.pool)
+                .await
+                .map_err(|_| WebauthnCredentialStoreError::UnexpectedError)?
+                .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(WebauthnCredentialStoreError::CredentialNotFound);
+        }
+        Ok(())
+    }
+}
+
+fn parse_row(row: &PgRow) -> Result<WebauthnCredential, WebauthnCredentialStoreError> {
+    let parse = || -> Result<WebauthnCredential, sqlx::Error> {
+        Ok(WebauthnCredential {
+            credential_id: row.try_get("credential_id")?,
+            email: Email::parse(row.try_get("email")?)
+                .map_err(|e| sqlx::Error::Decode(e.to_string().into()))?,
+            public_key: row.try_get("public_key")?,
+            sign_count: row.try_get::<i64, _>("sign_count")? as u32,
+            created_at: row.try_get::<i64, _>("created_at")? as u64,
+        })
+    };
+
+    parse().map_err(|_| WebauthnCredentialStoreError::UnexpectedError)
+}
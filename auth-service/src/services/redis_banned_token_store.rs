@@ -0,0 +1,94 @@
+use redis::{aio::ConnectionManager, AsyncCommands};
+
+use crate::{
+    domain::{BannedTokenStore, BannedTokenStoreError},
+    utils::time::unix_now,
+};
+
+const BANNED_TOKEN_KEY_PREFIX: &str = "banned_token:";
+
+// Each banned `jti` is its own key that expires together with the token,
+// so Redis drops entries that no longer matter on its own.
+#[derive(Clone)]
+pub struct RedisBannedTokenStore {
+    conn: ConnectionManager,
+}
+
+impl RedisBannedTokenStore {
+    pub fn new(conn: ConnectionManager) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl BannedTokenStore for RedisBannedTokenStore {
+    async fn add_token(
+        &mut self,
+        jti: String,
+        expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        let ttl = expires_at.saturating_sub(unix_now());
+        if ttl == 0 {
+            // Already expired, so it can't be used anyway.
+            return Ok(());
+        }
+
+        self.conn
+            .set_ex::<_, _, ()>(get_key(&jti), true, ttl)
+            .await
+            .map_err(|_| BannedTokenStoreError::UnexpectedError)
+    }
+
+    async fn contains_token(&self, jti: &str) -> Result<bool, BannedTokenStoreError> {
+        self.conn
+            .clone()
+            .exists(get_key(jti))
+            .await
+            .map_err(|_| BannedTokenStoreError::UnexpectedError)
+    }
+}
+
+fn get_key(jti: &str) -> String {
+    format!("{}{}", BANNED_TOKEN_KEY_PREFIX, jti)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_redis_connection;
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server, see README"]
+    async fn test_add_and_contains_token() {
+        let mut store = RedisBannedTokenStore::new(test_redis_connection().await);
+        let jti = uuid::Uuid::new_v4().to_string();
+
+        store
+            .add_token(jti.clone(), unix_now() + 600)
+            .await
+            .unwrap();
+
+        assert_eq!(store.contains_token(&jti).await, Ok(true));
+        assert_eq!(store.contains_token("other").await, Ok(false));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server, see README"]
+    async fn test_key_expires_with_token() {
+        let mut store = RedisBannedTokenStore::new(test_redis_connection().await);
+        let jti = uuid::Uuid::new_v4().to_string();
+
+        store
+            .add_token(jti.clone(), unix_now() + 600)
+            .await
+            .unwrap();
+        let ttl: i64 = store.conn.ttl(get_key(&jti)).await.unwrap();
+        assert!((599..=600).contains(&ttl));
+
+        store
+            .add_token("expired".to_owned(), unix_now() - 1)
+            .await
+            .unwrap();
+        assert_eq!(store.contains_token("expired").await, Ok(false));
+    }
+}
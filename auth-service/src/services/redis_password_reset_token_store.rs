@@ -0,0 +1,185 @@
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{Email, PasswordResetToken, PasswordResetTokenStore, PasswordResetTokenStoreError},
+    utils::time::unix_now,
+};
+
+const PASSWORD_RESET_TOKEN_KEY_PREFIX: &str = "password_reset:";
+const PASSWORD_RESET_USER_KEY_PREFIX: &str = "password_reset_user:";
+
+// Tokens are stored as JSON under a key per token hash that expires with it. A set
+// per user holds the hashes of their tokens and lives as long as the newest one.
+#[derive(Clone)]
+pub struct RedisPasswordResetTokenStore {
+    conn: ConnectionManager,
+}
+
+impl RedisPasswordResetTokenStore {
+    pub fn new(conn: ConnectionManager) -> Self {
+        Self { conn }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredToken {
+    email: String,
+    expires_at: u64,
+}
+
+#[async_trait::async_trait]
+impl PasswordResetTokenStore for RedisPasswordResetTokenStore {
+    async fn add_token(
+        &mut self,
+        token: PasswordResetToken,
+    ) -> Result<(), PasswordResetTokenStoreError> {
+        let ttl = token.expires_at.saturating_sub(unix_now());
+        if ttl == 0 {
+            return Ok(());
+        }
+
+        let value = serde_json::to_string(&StoredToken {
+            email: token.email.as_ref().to_owned(),
+            expires_at: token.expires_at,
+        })
+        .map_err(|_| PasswordResetTokenStoreError::UnexpectedError)?;
+
+        let user_key = get_user_key(&token.email);
+        redis::pipe()
+            .atomic()
+            .set_ex(get_key(&token.token_hash), value, ttl)
+            .ignore()
+            .sadd(&user_key, &token.token_hash)
+            .ignore()
+            .expire(&user_key, ttl as i64)
+            .ignore()
+            .query_async::<()>(&mut self.conn)
+            .await
+            .map_err(|_| PasswordResetTokenStoreError::UnexpectedError)
+    }
+
+    async fn get_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<PasswordResetToken, PasswordResetTokenStoreError> {
+        let value: Option<String> = self
+            .conn
+            .clone()
+            .get(get_key(token_hash))
+            .await
+            .map_err(|_| PasswordResetTokenStoreError::UnexpectedError)?;
+
+        parse_value(token_hash, value)
+    }
+
+    async fn take_token(
+        &mut self,
+        token_hash: &str,
+    ) -> Result<PasswordResetToken, PasswordResetTokenStoreError> {
+        // GETDEL hands the value to exactly one of several concurrent callers.
+        let value: Option<String> = self
+            .conn
+            .get_del(get_key(token_hash))
+            .await
+            .map_err(|_| PasswordResetTokenStoreError::UnexpectedError)?;
+
+        parse_value(token_hash, value)
+    }
+
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), PasswordResetTokenStoreError> {
+        let user_key = get_user_key(email);
+        let token_hashes: Vec<String> = self
+            .conn
+            .smembers(&user_key)
+            .await
+            .map_err(|_| PasswordResetTokenStoreError::UnexpectedError)?;
+
+        let mut keys: Vec<String> = token_hashes.iter().map(|hash| get_key(hash)).collect();
+        keys.push(user_key);
+        self.conn
+            .del::<_, ()>(keys)
+            .await
+            .map_err(|_| PasswordResetTokenStoreError::UnexpectedError)
+    }
+}
+
+fn parse_value(
+    token_hash: &str,
+    value: Option<String>,
+) -> Result<PasswordResetToken, PasswordResetTokenStoreError> {
+    let value = value.ok_or(PasswordResetTokenStoreError::TokenNotFound)?;
+    let stored: StoredToken =
+        serde_json::from_str(&value).map_err(|_| PasswordResetTokenStoreError::UnexpectedError)?;
+    let email =
+        Email::parse(stored.email).map_err(|_| PasswordResetTokenStoreError::UnexpectedError)?;
+
+    Ok(PasswordResetToken {
+        token_hash: token_hash.to_owned(),
+        email,
+        expires_at: stored.expires_at,
+    })
+}
+
+fn get_key(token_hash: &str) -> String {
+    format!("{}{}", PASSWORD_RESET_TOKEN_KEY_PREFIX, token_hash)
+}
+
+fn get_user_key(email: &Email) -> String {
+    format!("{}{}", PASSWORD_RESET_USER_KEY_PREFIX, email.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_redis_connection;
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server, see README"]
+    async fn test_token_can_only_be_taken_once() {
+        let mut store = RedisPasswordResetTokenStore::new(test_redis_connection().await);
+        let token = PasswordResetToken {
+            token_hash: uuid::Uuid::new_v4().to_string(),
+            email: Email::parse("test@example.com".to_owned()).unwrap(),
+            expires_at: unix_now() + 900,
+        };
+        store.add_token(token.clone()).await.unwrap();
+
+        let ttl: i64 = store.conn.ttl(get_key(&token.token_hash)).await.unwrap();
+        assert!((899..=900).contains(&ttl));
+        assert_eq!(store.get_token(&token.token_hash).await, Ok(token.clone()));
+        assert_eq!(store.take_token(&token.token_hash).await, Ok(token.clone()));
+        assert_eq!(
+            store.get_token(&token.token_hash).await,
+            Err(PasswordResetTokenStoreError::TokenNotFound)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server, see README"]
+    async fn test_revoke_user_removes_every_token() {
+        let mut store = RedisPasswordResetTokenStore::new(test_redis_connection().await);
+        let email = Email::parse(format!("{}@example.com", uuid::Uuid::new_v4())).unwrap();
+        let tokens: Vec<_> = (0..2)
+            .map(|_| PasswordResetToken {
+                token_hash: uuid::Uuid::new_v4().to_string(),
+                email: email.clone(),
+                expires_at: unix_now() + 900,
+            })
+            .collect();
+        for token in &tokens {
+            store.add_token(token.clone()).await.unwrap();
+        }
+
+        store.revoke_user(&email).await.unwrap();
+
+        for token in &tokens {
+            assert_eq!(
+                store.get_token(&token.token_hash).await,
+                Err(PasswordResetTokenStoreError::TokenNotFound)
+            );
+        }
+        let exists: bool = store.conn.exists(get_user_key(&email)).await.unwrap();
+        assert!(!exists);
+    }
+}
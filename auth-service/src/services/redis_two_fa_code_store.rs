@@ -0,0 +1,242 @@
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{Email, LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
+    utils::time::unix_now,
+};
+
+const TWO_FA_CODE_KEY_PREFIX: &str = "two_fa_code:";
+const TWO_FA_FAILURES_KEY_PREFIX: &str = "two_fa_failures:";
+
+// Pending codes are stored as JSON under a key per user that expires with the code.
+// Wrong codes are counted under a second key with the same lifetime.
+#[derive(Clone)]
+pub struct RedisTwoFACodeStore {
+    conn: ConnectionManager,
+}
+
+impl RedisTwoFACodeStore {
+    pub fn new(conn: ConnectionManager) -> Self {
+        Self { conn }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TwoFATuple(String, String);
+
+#[async_trait::async_trait]
+impl TwoFACodeStore for RedisTwoFACodeStore {
+    async fn add_code(
+        &mut self,
+        email: Email,
+        login_attempt_id: LoginAttemptId,
+        code: TwoFACode,
+        expires_at: u64,
+    ) -> Result<(), TwoFACodeStoreError> {
+        let key = get_key(&email);
+        let ttl = expires_at.saturating_sub(unix_now());
+        if ttl == 0 {
+            // Replacing the previous code with one that's already expired.
+            return self.remove_code(&email).await;
+        }
+
+        let value = serde_json::to_string(&TwoFATuple(
+            login_attempt_id.as_ref().to_owned(),
+            code.as_ref().to_owned(),
+        ))
+        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+        redis::pipe()
+            .atomic()
+            .set_ex(key, value, ttl)
+            .ignore()
+            .del(get_failures_key(&email))
+            .ignore()
+            .query_async::<()>(&mut self.conn)
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)
+    }
+
+    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
+        self.conn
+            .del::<_, ()>(&[get_key(email), get_failures_key(email)])
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)
+    }
+
+    async fn get_code(
+        &self,
+        email: &Email,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+        let value: Option<String> = self
+            .conn
+            .clone()
+            .get(get_key(email))
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        parse_value(value)
+    }
+
+    async fn take_code(
+        &mut self,
+        email: &Email,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+        // GETDEL hands the code to exactly one of several concurrent callers.
+        let (value,): (Option<String>,) = redis::pipe()
+            .atomic()
+            .get_del(get_key(email))
+            .del(get_failures_key(email))
+            .ignore()
+            .query_async(&mut self.conn)
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        parse_value(value)
+    }
+
+    async fn record_failure(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+        let ttl: i64 = self
+            .conn
+            .ttl(get_key(email))
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        // -2: no pending code; -1 can't happen, codes are always stored with a TTL.
+        if ttl <= 0 {
+            return Err(TwoFACodeStoreError::LoginAttemptIdNotFound);
+        }
+
+        let (failures,): (u32,) = redis::pipe()
+            .atomic()
+            .incr(get_failures_key(email), 1)
+            .expire(get_failures_key(email), ttl)
+            .ignore()
+            .query_async(&mut self.conn)
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+        Ok(failures)
+    }
+}
+
+fn parse_value(value: Option<String>) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+    let value = value.ok_or(TwoFACodeStoreError::LoginAttemptIdNotFound)?;
+
+    let TwoFATuple(login_attempt_id, code) =
+        serde_json::from_str(&value).map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+    let login_attempt_id = LoginAttemptId::parse(login_attempt_id)
+        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+    let code = TwoFACode::parse(code).map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+    Ok((login_attempt_id, code))
+}
+
+fn get_key(email: &Email) -> String {
+    format!("{}{}", TWO_FA_CODE_KEY_PREFIX, email.as_ref())
+}
+
+fn get_failures_key(email: &Email) -> String {
+    format!("{}{}", TWO_FA_FAILURES_KEY_PREFIX, email.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_redis_connection;
+
+    fn random_email() -> Email {
+        Email::parse(format!("{}@example.com", uuid::Uuid::new_v4())).unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server, see README"]
+    async fn test_add_get_and_remove_code() {
+        let mut store = RedisTwoFACodeStore::new(test_redis_connection().await);
+        let email = random_email();
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+
+        store
+            .add_code(
+                email.clone(),
+                login_attempt_id.clone(),
+                code.clone(),
+                unix_now() + 600,
+            )
+            .await
+            .unwrap();
+        assert_eq!(store.get_code(&email).await, Ok((login_attempt_id, code)));
+
+        assert_eq!(store.record_failure(&email).await, Ok(1));
+        assert_eq!(store.record_failure(&email).await, Ok(2));
+
+        store.remove_code(&email).await.unwrap();
+        assert_eq!(
+            store.get_code(&email).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+        assert_eq!(
+            store.record_failure(&email).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server, see README"]
+    async fn test_code_can_only_be_taken_once() {
+        let mut store = RedisTwoFACodeStore::new(test_redis_connection().await);
+        let email = random_email();
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+
+        store
+            .add_code(
+                email.clone(),
+                login_attempt_id.clone(),
+                code.clone(),
+                unix_now() + 600,
+            )
+            .await
+            .unwrap();
+        store.record_failure(&email).await.unwrap();
+
+        assert_eq!(store.take_code(&email).await, Ok((login_attempt_id, code)));
+        assert_eq!(
+            store.take_code(&email).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+        let failures: Option<u32> = store.conn.get(get_failures_key(&email)).await.unwrap();
+        assert_eq!(failures, None);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server, see README"]
+    async fn test_key_expires_with_code() {
+        let mut store = RedisTwoFACodeStore::new(test_redis_connection().await);
+        let email = random_email();
+
+        store
+            .add_code(
+                email.clone(),
+                LoginAttemptId::default(),
+                TwoFACode::default(),
+                unix_now() + 600,
+            )
+            .await
+            .unwrap();
+        let ttl: i64 = store.conn.ttl(get_key(&email)).await.unwrap();
+        assert!((599..=600).contains(&ttl));
+
+        store
+            .add_code(
+                email.clone(),
+                LoginAttemptId::default(),
+                TwoFACode::default(),
+                unix_now() - 1,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_code(&email).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{
+        Email, WebauthnCeremony, WebauthnChallenge, WebauthnChallengeStore,
+        WebauthnChallengeStoreError,
+    },
+    utils::time::unix_now,
+};
+
+const WEBAUTHN_CHALLENGE_KEY_PREFIX: &str = "webauthn_challenge:";
+
+// Challenges are stored as JSON under a key per challenge that expires with it.
+#[derive(Clone)]
+pub struct RedisWebauthnChallengeStore {
+    conn: ConnectionManager,
+}
+
+impl RedisWebauthnChallengeStore {
+    pub fn new(conn: ConnectionManager) -> Self {
+        Self { conn }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredChallenge {
+    ceremony: String,
+    email: Option<String>,
+    expires_at: u64,
+}
+
+#[async_trait::async_trait]
+impl WebauthnChallengeStore for RedisWebauthnChallengeStore {
+    async fn add_challenge(
+        &mut self,
+        challenge: WebauthnChallenge,
+    ) -> Result<(), WebauthnChallengeStoreError> {
+        let ttl = challenge.expires_at.saturating_sub(unix_now());
+        if ttl == 0 {
+            return Ok(());
+        }
+
+        let value = serde_json::to_string(&StoredChallenge {
+            ceremony: challenge.ceremony.as_str().to_owned(),
+            email: challenge.email.map(|email| email.as_ref().to_owned()),
+            expires_at: challenge.expires_at,
+        })
+        .map_err(|_| WebauthnChallengeStoreError::UnexpectedError)?;
+
+        self.conn
+            .set_ex::<_, _, ()>(get_key(&challenge.challenge), value, ttl)
+            .await
+            .map_err(|_| WebauthnChallengeStoreError::UnexpectedError)
+    }
+
+    async fn take_challenge(
+        &mut self,
+        challenge: &str,
+    ) -> Result<WebauthnChallenge, WebauthnChallengeStoreError> {
+        // GETDEL hands the value to exactly one of several concurrent callers.
+        let value: Option<String> = self
+            .conn
+            .get_del(get_key(challenge))
+            .await
+            .map_err(|_| WebauthnChallengeStoreError::UnexpectedError)?;
+        let value = value.ok_or(WebauthnChallengeStoreError::ChallengeNotFound)?;
+
+        let stored: StoredChallenge = serde_json::from_str(&value)
+            .map_err(|_| WebauthnChallengeStoreError::UnexpectedError)?;
+        let ceremony = WebauthnCeremony::parse(&stored.ceremony)
+            .map_err(|_| WebauthnChallengeStoreError::UnexpectedError)?;
+        let email = stored
+            .email
+            .map(Email::parse)
+            .transpose()
+            .map_err(|_| WebauthnChallengeStoreError::UnexpectedError)?;
+
+        Ok(WebauthnChallenge {
+            challenge: challenge.to_owned(),
+            ceremony,
+            email,
+            expires_at: stored.expires_at,
+        })
+    }
+}
+
+fn get_key(challenge: &str) -> String {
+    format!("{}{}", WEBAUTHN_CHALLENGE_KEY_PREFIX, challenge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_redis_connection;
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server, see README"]
+    async fn test_challenge_can_only_be_taken_once() {
+        let mut store = RedisWebauthnChallengeStore::new(test_redis_connection().await);
+        let challenge = WebauthnChallenge {
+            challenge: uuid::Uuid::new_v4().to_string(),
+            ceremony: WebauthnCeremony::Registration,
+            email: Some(Email::parse("test@example.com".to_owned()).unwrap()),
+            expires_at: unix_now() + 300,
+        };
+        store.add_challenge(challenge.clone()).await.unwrap();
+
+        let ttl: i64 = store.conn.ttl(get_key(&challenge.challenge)).await.unwrap();
+        assert!((299..=300).contains(&ttl));
+        assert_eq!(
+            store.take_challenge(&challenge.challenge).await,
+            Ok(challenge.clone())
+        );
+        assert_eq!(
+            store.take_challenge(&challenge.challenge).await,
+            Err(WebauthnChallengeStoreError::ChallengeNotFound)
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use lettre::{
+    transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport,
+    Tokio1Executor,
+};
+
+use super::build_message;
+use crate::domain::{Email, EmailClient, EmailClientError};
+
+// Delivers messages to an SMTP server. The connection is unencrypted, which suits
+// a local relay or a test server such as MailHog.
+pub struct SmtpEmailClient {
+    sender: String,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpEmailClient {
+    pub fn new(
+        sender: String,
+        host: &str,
+        port: u16,
+        credentials: Option<(String, String)>,
+    ) -> Self {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host).port(port);
+
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Self {
+            sender,
+            transport: builder.build(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailClient for SmtpEmailClient {
+    async fn send_email(
+        &self,
+        recipient: &Email,
+        subject: &str,
+        content: &str,
+    ) -> Result<(), EmailClientError> {
+        let message = build_message(&self.sender, recipient.as_ref(), subject, content)?;
+
+        self.transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| EmailClientError::DeliveryFailed(e.to_string()))
+    }
+}
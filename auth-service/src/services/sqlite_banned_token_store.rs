@@ -0,0 +1,86 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    domain::{BannedTokenStore, BannedTokenStoreError},
+    utils::time::unix_now,
+};
+
+pub struct SqliteBannedTokenStore {
+    pool: SqlitePool,
+}
+
+impl SqliteBannedTokenStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl BannedTokenStore for SqliteBannedTokenStore {
+    async fn add_token(
+        &mut self,
+        jti: String,
+        expires_at: u64,
+    ) -> Result<(), BannedTokenStoreError> {
+        // Tokens that expired on their own no longer need to be remembered.
+        sqlx::query("DELETE FROM banned_tokens WHERE expires_at <= ?")
+            .bind(unix_now() as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| BannedTokenStoreError::UnexpectedError)?;
+
+        sqlx::query("INSERT OR REPLACE INTO banned_tokens (jti, expires_at) VALUES (?, ?)")
+            .bind(jti)
+            .bind(expires_at as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| BannedTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn contains_token(&self, jti: &str) -> Result<bool, BannedTokenStoreError> {
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM banned_tokens WHERE jti = ?)")
+            .bind(jti)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|_| BannedTokenStoreError::UnexpectedError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_sqlite_pool;
+
+    #[tokio::test]
+    async fn test_add_and_contains_token() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteBannedTokenStore::new(pool);
+
+        store
+            .add_token("jti".to_owned(), unix_now() + 600)
+            .await
+            .unwrap();
+
+        assert_eq!(store.contains_token("jti").await, Ok(true));
+        assert_eq!(store.contains_token("other").await, Ok(false));
+    }
+
+    #[tokio::test]
+    async fn test_expired_tokens_are_purged() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteBannedTokenStore::new(pool);
+
+        store
+            .add_token("expired".to_owned(), unix_now() - 1)
+            .await
+            .unwrap();
+        store
+            .add_token("jti".to_owned(), unix_now() + 600)
+            .await
+            .unwrap();
+
+        assert_eq!(store.contains_token("expired").await, Ok(false));
+    }
+}
@@ -0,0 +1,188 @@
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::{
+    domain::{Email, PasswordResetToken, PasswordResetTokenStore, PasswordResetTokenStoreError},
+    utils::time::unix_now,
+};
+
+pub struct SqlitePasswordResetTokenStore {
+    pool: SqlitePool,
+}
+
+impl SqlitePasswordResetTokenStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl PasswordResetTokenStore for SqlitePasswordResetTokenStore {
+    async fn add_token(
+        &mut self,
+        token: PasswordResetToken,
+    ) -> Result<(), PasswordResetTokenStoreError> {
+        sqlx::query("DELETE FROM password_reset_tokens WHERE expires_at <= ?")
+            .bind(unix_now() as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| PasswordResetTokenStoreError::UnexpectedError)?;
+
+        sqlx::query(
+            "INSERT INTO password_reset_tokens (token_hash, email, expires_at) VALUES (?, ?, ?)",
+        )
+        .bind(&token.token_hash)
+        .bind(token.email.as_ref())
+        .bind(token.expires_at as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| PasswordResetTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn get_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<PasswordResetToken, PasswordResetTokenStoreError> {
+        let row = sqlx::query(
+            "SELECT token_hash, email, expires_at FROM password_reset_tokens \
+             WHERE token_hash = ? AND expires_at > ?",
+        )
+        .bind(token_hash)
+        .bind(unix_now() as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| PasswordResetTokenStoreError::UnexpectedError)?
+        .ok_or(PasswordResetTokenStoreError::TokenNotFound)?;
+
+        parse_row(&row)
+    }
+
+    async fn take_token(
+        &mut self,
+        token_hash: &str,
+    ) -> Result<PasswordResetToken, PasswordResetTokenStoreError> {
+        // Deleting and reading in one statement means only one of two concurrent
+        // requests with the same token gets it.
+        let row = sqlx::query(
+            "DELETE FROM password_reset_tokens WHERE token_hash = ? \
+             RETURNING token_hash, email, expires_at",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| PasswordResetTokenStoreError::UnexpectedError)?
+        .ok_or(PasswordResetTokenStoreError::TokenNotFound)?;
+
+        let token = parse_row(&row)?;
+        if token.expires_at <= unix_now() {
+            return Err(PasswordResetTokenStoreError::TokenNotFound);
+        }
+        Ok(token)
+    }
+
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), PasswordResetTokenStoreError> {
+        sqlx::query("DELETE FROM password_reset_tokens WHERE email = ?")
+            .bind(email.as_ref())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| PasswordResetTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+fn parse_row(row: &SqliteRow) -> Result<PasswordResetToken, PasswordResetTokenStoreError> {
+    let parse = || -> Result<PasswordResetToken, sqlx::Error> {
+        Ok(PasswordResetToken {
+            token_hash: row.try_get("token_hash")?,
+            email: Email::parse(row.try_get("email")?)
+                .map_err(|e| sqlx::Error::Decode(e.to_string().into()))?,
+            expires_at: row.try_get::<i64, _>("expires_at")? as u64,
+        })
+    };
+
+    parse().map_err(|_| PasswordResetTokenStoreError::UnexpectedError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_sqlite_pool;
+
+    fn test_token(token_hash: &str, expires_at: u64) -> PasswordResetToken {
+        PasswordResetToken {
+            token_hash: token_hash.to_owned(),
+            email: Email::parse("test@example.com".to_owned()).unwrap(),
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_can_only_be_taken_once() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqlitePasswordResetTokenStore::new(pool);
+        let token = test_token("hash", unix_now() + 900);
+        store.add_token(token.clone()).await.unwrap();
+
+        assert_eq!(store.get_token("hash").await, Ok(token.clone()));
+        assert_eq!(store.take_token("hash").await, Ok(token));
+        assert_eq!(
+            store.get_token("hash").await,
+            Err(PasswordResetTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.take_token("hash").await,
+            Err(PasswordResetTokenStoreError::TokenNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user_removes_only_their_tokens() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqlitePasswordResetTokenStore::new(pool);
+        let other = PasswordResetToken {
+            email: Email::parse("other@example.com".to_owned()).unwrap(),
+            ..test_token("other", unix_now() + 900)
+        };
+        for token in [
+            test_token("first", unix_now() + 900),
+            test_token("second", unix_now() + 900),
+            other.clone(),
+        ] {
+            store.add_token(token).await.unwrap();
+        }
+
+        store
+            .revoke_user(&Email::parse("test@example.com".to_owned()).unwrap())
+            .await
+            .unwrap();
+
+        for token_hash in ["first", "second"] {
+            assert_eq!(
+                store.get_token(token_hash).await,
+                Err(PasswordResetTokenStoreError::TokenNotFound)
+            );
+        }
+        assert_eq!(store.get_token("other").await, Ok(other));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_not_returned() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqlitePasswordResetTokenStore::new(pool);
+        store
+            .add_token(test_token("hash", unix_now() - 1))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_token("hash").await,
+            Err(PasswordResetTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.take_token("hash").await,
+            Err(PasswordResetTokenStoreError::TokenNotFound)
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use sqlx::{Row, SqlitePool};
+
+use crate::domain::{Email, RecoveryCodeStore, RecoveryCodeStoreError};
+
+pub struct SqliteRecoveryCodeStore {
+    pool: SqlitePool,
+}
+
+impl SqliteRecoveryCodeStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl RecoveryCodeStore for SqliteRecoveryCodeStore {
+    async fn replace_codes(
+        &mut self,
+        email: &Email,
+        code_hashes: Vec<String>,
+    ) -> Result<(), RecoveryCodeStoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        sqlx::query("DELETE FROM recovery_codes WHERE email = ?")
+            .bind(email.as_ref())
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        for code_hash in code_hashes {
+            sqlx::query("INSERT INTO recovery_codes (email, code_hash) VALUES (?, ?)")
+                .bind(email.as_ref())
+                .bind(code_hash)
+                .execute(&mut *tx)
+                .await
+                .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)
+    }
+
+    async fn use_code(
+        &mut self,
+        email: &Email,
+        code_hash: &str,
+    ) -> Result<(), RecoveryCodeStoreError> {
+        let rows_affected =
+            sqlx::query("DELETE FROM recovery_codes WHERE email = ? AND code_hash = ?")
+                .bind(email.as_ref())
+                .bind(code_hash)
+                .execute(&self.pool)
+                .await
+                .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?
+                .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(RecoveryCodeStoreError::CodeNotFound);
+        }
+        Ok(())
+    }
+
+    async fn count_codes(&self, email: &Email) -> Result<usize, RecoveryCodeStoreError> {
+        let row = sqlx::query("SELECT COUNT(*) AS count FROM recovery_codes WHERE email = ?")
+            .bind(email.as_ref())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)?;
+
+        row.try_get::<i64, _>("count")
+            .map(|count| count as usize)
+            .map_err(|_| RecoveryCodeStoreError::UnexpectedError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_sqlite_pool;
+
+    fn test_hashes(hashes: &[&str]) -> Vec<String> {
+        hashes.iter().map(|hash| hash.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_use_code() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteRecoveryCodeStore::new(pool);
+        let email = Email::parse("test@example.com".to_owned()).unwrap();
+        let other = Email::parse("other@example.com".to_owned()).unwrap();
+        store
+            .replace_codes(&email, test_hashes(&["a", "b"]))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.use_code(&other, "a").await,
+            Err(RecoveryCodeStoreError::CodeNotFound)
+        );
+        assert_eq!(store.use_code(&email, "a").await, Ok(()));
+        assert_eq!(
+            store.use_code(&email, "a").await,
+            Err(RecoveryCodeStoreError::CodeNotFound)
+        );
+        assert_eq!(store.count_codes(&email).await, Ok(1));
+        assert_eq!(store.count_codes(&other).await, Ok(0));
+    }
+
+    #[tokio::test]
+    async fn test_replace_codes() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteRecoveryCodeStore::new(pool);
+        let email = Email::parse("test@example.com".to_owned()).unwrap();
+        store
+            .replace_codes(&email, test_hashes(&["a", "b"]))
+            .await
+            .unwrap();
+
+        store
+            .replace_codes(&email, test_hashes(&["c", "d", "e"]))
+            .await
+            .unwrap();
+
+        assert_eq!(store.count_codes(&email).await, Ok(3));
+        assert_eq!(
+            store.use_code(&email, "a").await,
+            Err(RecoveryCodeStoreError::CodeNotFound)
+        );
+        assert_eq!(store.use_code(&email, "c").await, Ok(()));
+    }
+}
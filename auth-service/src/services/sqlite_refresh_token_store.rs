@@ -0,0 +1,210 @@
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::{
+    domain::{Email, RefreshToken, RefreshTokenStore, RefreshTokenStoreError},
+    utils::time::unix_now,
+};
+
+pub struct SqliteRefreshTokenStore {
+    pool: SqlitePool,
+}
+
+impl SqliteRefreshTokenStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl RefreshTokenStore for SqliteRefreshTokenStore {
+    async fn add_token(&mut self, token: RefreshToken) -> Result<(), RefreshTokenStoreError> {
+        sqlx::query("DELETE FROM refresh_tokens WHERE expires_at <= ?")
+            .bind(unix_now() as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        sqlx::query(
+            "INSERT INTO refresh_tokens (token_hash, family_id, email, expires_at, used) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&token.token_hash)
+        .bind(&token.family_id)
+        .bind(token.email.as_ref())
+        .bind(token.expires_at as i64)
+        .bind(token.used)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn get_token(&self, token_hash: &str) -> Result<RefreshToken, RefreshTokenStoreError> {
+        let row = sqlx::query(
+            "SELECT token_hash, family_id, email, expires_at, used \
+             FROM refresh_tokens WHERE token_hash = ?",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| RefreshTokenStoreError::UnexpectedError)?
+        .ok_or(RefreshTokenStoreError::TokenNotFound)?;
+
+        parse_row(&row)
+    }
+
+    async fn use_token(
+        &mut self,
+        token_hash: &str,
+    ) -> Result<RefreshToken, RefreshTokenStoreError> {
+        // Flipping the flag in a single conditional statement means only one of two
+        // concurrent requests with the same token can see it unused.
+        let first_use =
+            sqlx::query("UPDATE refresh_tokens SET used = TRUE WHERE token_hash = ? AND NOT used")
+                .bind(token_hash)
+                .execute(&self.pool)
+                .await
+                .map_err(|_| RefreshTokenStoreError::UnexpectedError)?
+                .rows_affected()
+                == 1;
+
+        let token = self.get_token(token_hash).await?;
+        Ok(RefreshToken {
+            used: !first_use,
+            ..token
+        })
+    }
+
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        sqlx::query("DELETE FROM refresh_tokens WHERE family_id = ?")
+            .bind(family_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn revoke_user(&mut self, email: &Email) -> Result<(), RefreshTokenStoreError> {
+        sqlx::query("DELETE FROM refresh_tokens WHERE email = ?")
+            .bind(email.as_ref())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+fn parse_row(row: &SqliteRow) -> Result<RefreshToken, RefreshTokenStoreError> {
+    let parse = || -> Result<RefreshToken, sqlx::Error> {
+        Ok(RefreshToken {
+            token_hash: row.try_get("token_hash")?,
+            family_id: row.try_get("family_id")?,
+            email: Email::parse(row.try_get("email")?)
+                .map_err(|e| sqlx::Error::Decode(e.to_string().into()))?,
+            expires_at: row.try_get::<i64, _>("expires_at")? as u64,
+            used: row.try_get("used")?,
+        })
+    };
+
+    parse().map_err(|_| RefreshTokenStoreError::UnexpectedError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_sqlite_pool;
+
+    fn test_token(token_hash: &str, family_id: &str) -> RefreshToken {
+        RefreshToken {
+            token_hash: token_hash.to_owned(),
+            family_id: family_id.to_owned(),
+            email: Email::parse("test@example.com".to_owned()).unwrap(),
+            expires_at: unix_now() + 600,
+            used: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_use_token_reports_previous_use() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteRefreshTokenStore::new(pool);
+        store.add_token(test_token("hash", "family")).await.unwrap();
+
+        assert_eq!(
+            store.use_token("hash").await,
+            Ok(test_token("hash", "family"))
+        );
+        assert!(store.use_token("hash").await.unwrap().used);
+        assert_eq!(
+            store.use_token("other").await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoke_family() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteRefreshTokenStore::new(pool);
+        store
+            .add_token(test_token("first", "family"))
+            .await
+            .unwrap();
+        store
+            .add_token(test_token("second", "family"))
+            .await
+            .unwrap();
+        store.add_token(test_token("third", "other")).await.unwrap();
+
+        store.revoke_family("family").await.unwrap();
+
+        assert_eq!(
+            store.get_token("first").await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.get_token("second").await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.get_token("third").await,
+            Ok(test_token("third", "other"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteRefreshTokenStore::new(pool);
+        let other_user = RefreshToken {
+            email: Email::parse("other@example.com".to_owned()).unwrap(),
+            ..test_token("third", "other")
+        };
+        store
+            .add_token(test_token("first", "family"))
+            .await
+            .unwrap();
+        store
+            .add_token(test_token("second", "second-login"))
+            .await
+            .unwrap();
+        store.add_token(other_user.clone()).await.unwrap();
+
+        store
+            .revoke_user(&Email::parse("test@example.com".to_owned()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_token("first").await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(
+            store.get_token("second").await,
+            Err(RefreshTokenStoreError::TokenNotFound)
+        );
+        assert_eq!(store.get_token("third").await, Ok(other_user));
+    }
+}
@@ -0,0 +1,178 @@
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::domain::{SigningKey, SigningKeyStatus, SigningKeyStore, SigningKeyStoreError};
+
+pub struct SqliteSigningKeyStore {
+    pool: SqlitePool,
+}
+
+impl SqliteSigningKeyStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl SigningKeyStore for SqliteSigningKeyStore {
+    async fn add_key(&mut self, key: SigningKey) -> Result<(), SigningKeyStoreError> {
+        sqlx::query(
+            "INSERT INTO signing_keys (kid, algorithm, private_key, status, created_at) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&key.kid)
+        .bind(&key.algorithm)
+        .bind(&key.private_key)
+        .bind(key.status.as_str())
+        .bind(key.created_at as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| SigningKeyStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn get_keys(&self) -> Result<Vec<SigningKey>, SigningKeyStoreError> {
+        sqlx::query(
+            "SELECT kid, algorithm, private_key, status, created_at \
+             FROM signing_keys ORDER BY created_at, kid",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_| SigningKeyStoreError::UnexpectedError)?
+        .iter()
+        .map(parse_row)
+        .collect()
+    }
+
+    async fn promote_key(&mut self, kid: &str) -> Result<(), SigningKeyStoreError> {
+        // A single statement, so there is never a moment without a signing key.
+        let rows_affected = sqlx::query(
+            "UPDATE signing_keys \
+             SET status = CASE WHEN kid = ?1 THEN 'signing' ELSE 'standby' END \
+             WHERE EXISTS (SELECT 1 FROM signing_keys WHERE kid = ?1)",
+        )
+        .bind(kid)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| SigningKeyStoreError::UnexpectedError)?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(SigningKeyStoreError::KeyNotFound);
+        }
+        Ok(())
+    }
+
+    async fn retire_key(&mut self, kid: &str) -> Result<(), SigningKeyStoreError> {
+        let rows_affected =
+            sqlx::query("DELETE FROM signing_keys WHERE kid = ? AND status = 'standby'")
+                .bind(kid)
+                .execute(&self.pool)
+                .await
+                .map_err(|_| SigningKeyStoreError::UnexpectedError)?
+                .rows_affected();
+        if rows_affected == 1 {
+            return Ok(());
+        }
+
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM signing_keys WHERE kid = ?)")
+                .bind(kid)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|_| SigningKeyStoreError::UnexpectedError)?;
+
+        Err(if exists {
+            SigningKeyStoreError::KeyInUse
+        } else {
+            SigningKeyStoreError::KeyNotFound
+        })
+    }
+}
+
+fn parse_row(row: &SqliteRow) -> Result<SigningKey, SigningKeyStoreError> {
+    let parse = || -> Result<SigningKey, sqlx::Error> {
+        Ok(SigningKey {
+            kid: row.try_get("kid")?,
+            algorithm: row.try_get("algorithm")?,
+            private_key: row.try_get("private_key")?,
+            status: SigningKeyStatus::parse(row.try_get("status")?)
+                .map_err(|e| sqlx::Error::Decode(e.into()))?,
+            created_at: row.try_get::<i64, _>("created_at")? as u64,
+        })
+    };
+
+    parse().map_err(|_| SigningKeyStoreError::UnexpectedError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_sqlite_pool;
+
+    fn test_key(kid: &str, created_at: u64) -> SigningKey {
+        SigningKey {
+            kid: kid.to_owned(),
+            algorithm: "EdDSA".to_owned(),
+            private_key: "private key".to_owned(),
+            status: SigningKeyStatus::Standby,
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_promote_key_demotes_previous_signing_key() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteSigningKeyStore::new(pool);
+        store.add_key(test_key("first", 1)).await.unwrap();
+        store.add_key(test_key("second", 2)).await.unwrap();
+
+        store.promote_key("first").await.unwrap();
+        store.promote_key("second").await.unwrap();
+
+        let statuses: Vec<_> = store
+            .get_keys()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|key| (key.kid, key.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("first".to_owned(), SigningKeyStatus::Standby),
+                ("second".to_owned(), SigningKeyStatus::Signing),
+            ]
+        );
+        assert_eq!(
+            store.promote_key("unknown").await,
+            Err(SigningKeyStoreError::KeyNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retire_key() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteSigningKeyStore::new(pool);
+        store.add_key(test_key("first", 1)).await.unwrap();
+        store.add_key(test_key("second", 2)).await.unwrap();
+        store.promote_key("second").await.unwrap();
+
+        assert_eq!(
+            store.retire_key("second").await,
+            Err(SigningKeyStoreError::KeyInUse)
+        );
+        store.retire_key("first").await.unwrap();
+        assert_eq!(
+            store.retire_key("first").await,
+            Err(SigningKeyStoreError::KeyNotFound)
+        );
+        assert_eq!(
+            store.get_keys().await.unwrap(),
+            vec![SigningKey {
+                status: SigningKeyStatus::Signing,
+                ..test_key("second", 2)
+            }]
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::{
+    domain::{Email, LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
+    utils::time::unix_now,
+};
+
+pub struct SqliteTwoFACodeStore {
+    pool: SqlitePool,
+}
+
+impl SqliteTwoFACodeStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl TwoFACodeStore for SqliteTwoFACodeStore {
+    async fn add_code(
+        &mut self,
+        email: Email,
+        login_attempt_id: LoginAttemptId,
+        code: TwoFACode,
+        expires_at: u64,
+    ) -> Result<(), TwoFACodeStoreError> {
+        sqlx::query("DELETE FROM two_fa_codes WHERE expires_at <= ?")
+            .bind(unix_now() as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO two_fa_codes (email, login_attempt_id, code, expires_at) \
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(email.as_ref())
+        .bind(login_attempt_id.as_ref())
+        .bind(code.as_ref())
+        .bind(expires_at as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
+        sqlx::query("DELETE FROM two_fa_codes WHERE email = ?")
+            .bind(email.as_ref())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn get_code(
+        &self,
+        email: &Email,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+        let row = sqlx::query(
+            "SELECT login_attempt_id, code FROM two_fa_codes WHERE email = ? AND expires_at > ?",
+        )
+        .bind(email.as_ref())
+        .bind(unix_now() as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?
+        .ok_or(TwoFACodeStoreError::LoginAttemptIdNotFound)?;
+
+        parse_row(&row)
+    }
+
+    async fn take_code(
+        &mut self,
+        email: &Email,
+    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+        // A single statement, so of several concurrent callers only one gets the row back.
+        let row = sqlx::query(
+            "DELETE FROM two_fa_codes WHERE email = ? AND expires_at > ? \
+             RETURNING login_attempt_id, code",
+        )
+        .bind(email.as_ref())
+        .bind(unix_now() as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?
+        .ok_or(TwoFACodeStoreError::LoginAttemptIdNotFound)?;
+
+        parse_row(&row)
+    }
+
+    async fn record_failure(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+        // Incrementing in the statement keeps concurrent guesses from being lost.
+        let row = sqlx::query(
+            "UPDATE two_fa_codes SET failed_attempts = failed_attempts + 1 \
+             WHERE email = ? AND expires_at > ? RETURNING failed_attempts",
+        )
+        .bind(email.as_ref())
+        .bind(unix_now() as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| TwoFACodeStoreError::UnexpectedError)?
+        .ok_or(TwoFACodeStoreError::LoginAttemptIdNotFound)?;
+
+        row.try_get::<i64, _>("failed_attempts")
+            .map(|failures| failures as u32)
+            .map_err(|_| TwoFACodeStoreError::UnexpectedError)
+    }
+}
+
+fn parse_row(row: &SqliteRow) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+    let login_attempt_id = row
+        .try_get("login_attempt_id")
+        .ok()
+        .and_then(|id| LoginAttemptId::parse(id).ok())
+        .ok_or(TwoFACodeStoreError::UnexpectedError)?;
+    let code = row
+        .try_get("code")
+        .ok()
+        .and_then(|code| TwoFACode::parse(code).ok())
+        .ok_or(TwoFACodeStoreError::UnexpectedError)?;
+
+    Ok((login_attempt_id, code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_sqlite_pool;
+
+    fn test_email() -> Email {
+        Email::parse("test@example.com".to_owned()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_add_get_and_remove_code() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteTwoFACodeStore::new(pool);
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+
+        store
+            .add_code(
+                test_email(),
+                login_attempt_id.clone(),
+                code.clone(),
+                unix_now() + 600,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_code(&test_email()).await,
+            Ok((login_attempt_id, code))
+        );
+
+        store.remove_code(&test_email()).await.unwrap();
+        assert_eq!(
+            store.get_code(&test_email()).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_new_code_replaces_previous_one() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteTwoFACodeStore::new(pool);
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+
+        for (login_attempt_id, code) in [
+            (LoginAttemptId::default(), TwoFACode::default()),
+            (login_attempt_id.clone(), code.clone()),
+        ] {
+            store
+                .add_code(test_email(), login_attempt_id, code, unix_now() + 600)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            store.get_code(&test_email()).await,
+            Ok((login_attempt_id, code))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_code_can_only_be_taken_once() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteTwoFACodeStore::new(pool);
+        let login_attempt_id = LoginAttemptId::default();
+        let code = TwoFACode::default();
+
+        store
+            .add_code(
+                test_email(),
+                login_attempt_id.clone(),
+                code.clone(),
+                unix_now() + 600,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            store.take_code(&test_email()).await,
+            Ok((login_attempt_id, code))
+        );
+        assert_eq!(
+            store.take_code(&test_email()).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_counted_per_code() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteTwoFACodeStore::new(pool);
+        for _ in 0..2 {
+            store
+                .add_code(
+                    test_email(),
+                    LoginAttemptId::default(),
+                    TwoFACode::default(),
+                    unix_now() + 600,
+                )
+                .await
+                .unwrap();
+            assert_eq!(store.record_failure(&test_email()).await, Ok(1));
+            assert_eq!(store.record_failure(&test_email()).await, Ok(2));
+        }
+
+        store.remove_code(&test_email()).await.unwrap();
+        assert_eq!(
+            store.record_failure(&test_email()).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_code_is_not_returned() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteTwoFACodeStore::new(pool);
+
+        store
+            .add_code(
+                test_email(),
+                LoginAttemptId::default(),
+                TwoFACode::default(),
+                unix_now() - 1,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_code(&test_email()).await,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
+        );
+    }
+}
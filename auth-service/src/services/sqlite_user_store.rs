@@ -0,0 +1,202 @@
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::domain::{Email, TwoFAMethod, User, UserStore, UserStoreError};
+
+pub struct SqliteUserStore {
+    pool: SqlitePool,
+}
+
+impl SqliteUserStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl UserStore for SqliteUserStore {
+    async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
+        sqlx::query(
+            "INSERT INTO users \
+             (email, password_hash, requires_2fa, two_fa_method, totp_secret, pending_totp_secret, \
+             totp_last_step, email_verified, verification_email_sent_at, session_generation) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(user.email.as_ref())
+        .bind(&user.password_hash)
+        .bind(user.requires_2fa)
+        .bind(user.two_fa_method.as_str())
+        .bind(&user.totp_secret)
+        .bind(&user.pending_totp_secret)
+        .bind(user.totp_last_step.map(|step| step as i64))
+        .bind(user.email_verified)
+        .bind(user.verification_email_sent_at.map(|at| at as i64))
+        .bind(user.session_generation as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(e) if e.is_unique_violation() => UserStoreError::UserAlreadyExists,
+            _ => UserStoreError::UnexpectedError,
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
+        let row = sqlx::query(
+            "SELECT email, password_hash, requires_2fa, two_fa_method, totp_secret, \
+             pending_totp_secret, totp_last_step, email_verified, verification_email_sent_at, \
+             session_generation \
+             FROM users WHERE email = ?",
+        )
+        .bind(email.as_ref())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?
+        .ok_or(UserStoreError::UserNotFound)?;
+
+        user_from_row(&row).ok_or(UserStoreError::UnexpectedError)
+    }
+
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password_hash: String,
+    ) -> Result<(), UserStoreError> {
+        let result = sqlx::query("UPDATE users SET password_hash = ? WHERE email = ?")
+            .bind(password_hash)
+            .bind(email.as_ref())
+            .execute(&self.pool)
+            .await
+            .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn update_user(&mut self, user: User) -> Result<(), UserStoreError> {
+        let result = sqlx::query(
+            "UPDATE users SET password_hash = ?, requires_2fa = ?, two_fa_method = ?, \
+             totp_secret = ?, pending_totp_secret = ?, totp_last_step = ?, email_verified = ?, \
+             verification_email_sent_at = ?, session_generation = ? WHERE email = ?",
+        )
+        .bind(&user.password_hash)
+        .bind(user.requires_2fa)
+        .bind(user.two_fa_method.as_str())
+        .bind(&user.totp_secret)
+        .bind(&user.pending_totp_secret)
+        .bind(user.totp_last_step.map(|step| step as i64))
+        .bind(user.email_verified)
+        .bind(user.verification_email_sent_at.map(|at| at as i64))
+        .bind(user.session_generation as i64)
+        .bind(user.email.as_ref())
+        .execute(&self.pool)
+        .await
+        .map_err(|_| UserStoreError::UnexpectedError)?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+}
+
+fn user_from_row(row: &SqliteRow) -> Option<User> {
+    Some(User {
+        email: Email::parse(row.try_get("email").ok()?).ok()?,
+        password_hash: row.try_get("password_hash").ok()?,
+        requires_2fa: row.try_get("requires_2fa").ok()?,
+        two_fa_method: TwoFAMethod::parse(row.try_get("two_fa_method").ok()?).ok()?,
+        totp_secret: row.try_get("totp_secret").ok()?,
+        pending_totp_secret: row.try_get("pending_totp_secret").ok()?,
+        totp_last_step: row
+            .try_get::<Option<i64>, _>("totp_last_step")
+            .ok()?
+            .map(|step| step as u64),
+        email_verified: row.try_get("email_verified").ok()?,
+        verification_email_sent_at: row
+            .try_get::<Option<i64>, _>("verification_email_sent_at")
+            .ok()?
+            .map(|at| at as u64),
+        session_generation: row.try_get::<i64, _>("session_generation").ok()? as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_sqlite_pool;
+
+    fn test_user(email: &str) -> User {
+        User::new(
+            Email::parse(email.to_owned()).unwrap(),
+            "hash".to_owned(),
+            true,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_user() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteUserStore::new(pool);
+
+        store.add_user(test_user("test@example.com")).await.unwrap();
+
+        let user = store.get_user(&test_user("test@example.com").email).await;
+        assert_eq!(user, Ok(test_user("test@example.com")));
+        assert_eq!(
+            store.add_user(test_user("test@example.com")).await,
+            Err(UserStoreError::UserAlreadyExists)
+        );
+        assert_eq!(
+            store.get_user(&test_user("other@example.com").email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_password() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteUserStore::new(pool);
+        let user = test_user("test@example.com");
+
+        store.add_user(user.clone()).await.unwrap();
+        store
+            .update_password(&user.email, "new-hash".to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_user(&user.email).await.unwrap().password_hash,
+            "new-hash"
+        );
+        assert_eq!(
+            store
+                .update_password(&test_user("other@example.com").email, "hash".to_owned())
+                .await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_user() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteUserStore::new(pool);
+        let mut user = test_user("test@example.com");
+        store.add_user(user.clone()).await.unwrap();
+
+        user.two_fa_method = TwoFAMethod::Totp;
+        user.totp_secret = Some("JBSWY3DPEHPK3PXP".to_owned());
+        user.totp_last_step = Some(56_666_666);
+        store.update_user(user.clone()).await.unwrap();
+
+        assert_eq!(store.get_user(&user.email).await, Ok(user));
+        assert_eq!(
+            store.update_user(test_user("other@example.com")).await,
+            Err(UserStoreError::UserNotFound)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::{
+    domain::{
+        Email, WebauthnCeremony, WebauthnChallenge, WebauthnChallengeStore,
+        WebauthnChallengeStoreError,
+    },
+    utils::time::unix_now,
+};
+
+pub struct SqliteWebauthnChallengeStore {
+    pool: SqlitePool,
+}
+
+impl SqliteWebauthnChallengeStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl WebauthnChallengeStore for SqliteWebauthnChallengeStore {
+    async fn add_challenge(
+        &mut self,
+        challenge: WebauthnChallenge,
+    ) -> Result<(), WebauthnChallengeStoreError> {
+        sqlx::query("DELETE FROM webauthn_challenges WHERE expires_at <= ?")
+            .bind(unix_now() as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|_| WebauthnChallengeStoreError::UnexpectedError)?;
+
+        sqlx::query(
+            "INSERT INTO webauthn_challenges (challenge, ceremony, email, expires_at) \
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(&challenge.challenge)
+        .bind(challenge.ceremony.as_str())
+        .bind(challenge.email.as_ref().map(|email| email.as_ref()))
+        .bind(challenge.expires_at as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|_| WebauthnChallengeStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    async fn take_challenge(
+        &mut self,
+        challenge: &str,
+    ) -> Result<WebauthnChallenge, WebauthnChallengeStoreError> {
+        // Deleting and reading in one statement means only one of two concurrent
+        // requests with the same challenge gets it.
+        let row = sqlx::query(
+            "DELETE FROM webauthn_challenges WHERE challenge = ? \
+             RETURNING challenge, ceremony, email, expires_at",
+        )
+        .bind(challenge)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| WebauthnChallengeStoreError::UnexpectedError)?
+        .ok_or(WebauthnChallengeStoreError::ChallengeNotFound)?;
+
+        let challenge = parse_row(&row)?;
+        if challenge.expires_at <= unix_now() {
+            return Err(WebauthnChallengeStoreError::ChallengeNotFound);
+        }
+        Ok(challenge)
+    }
+}
+
+fn parse_row(row: &SqliteRow) -> Result<WebauthnChallenge, WebauthnChallengeStoreError> {
+    let parse = || -> Result<WebauthnChallenge, sqlx::Error> {
+        Ok(WebauthnChallenge {
+            challenge: row.try_get("challenge")?,
+            ceremony: WebauthnCeremony::parse(row.try_get("ceremony")?)
+                .map_err(|e| sqlx::Error::Decode(e.into()))?,
+            email: row
+                .try_get::<Option<String>, _>("email")?
+                .map(Email::parse)
+                .transpose()
+                .map_err(|e| sqlx::Error::Decode(e.to_string().into()))?,
+            expires_at: row.try_get::<i64, _>("expires_at")? as u64,
+        })
+    };
+
+    parse().map_err(|_| WebauthnChallengeStoreError::UnexpectedError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_sqlite_pool;
+
+    fn test_challenge(challenge: &str, email: Option<&str>, expires_at: u64) -> WebauthnChallenge {
+        WebauthnChallenge {
+            challenge: challenge.to_owned(),
+            ceremony: WebauthnCeremony::Login,
+            email: email.map(|email| Email::parse(email.to_owned()).unwrap()),
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_challenge_can_only_be_taken_once() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteWebauthnChallengeStore::new(pool);
+        let with_email = test_challenge("first", Some("test@example.com"), unix_now() + 300);
+        let without_email = test_challenge("second", None, unix_now() + 300);
+        store.add_challenge(with_email.clone()).await.unwrap();
+        store.add_challenge(without_email.clone()).await.unwrap();
+
+        assert_eq!(store.take_challenge("first").await, Ok(with_email));
+        assert_eq!(store.take_challenge("second").await, Ok(without_email));
+        assert_eq!(
+            store.take_challenge("first").await,
+            Err(WebauthnChallengeStoreError::ChallengeNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_challenge_is_not_returned() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteWebauthnChallengeStore::new(pool);
+        store
+            .add_challenge(test_challenge("challenge", None, unix_now() - 1))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.take_challenge("challenge").await,
+            Err(WebauthnChallengeStoreError::ChallengeNotFound)
+        );
+    }
+}
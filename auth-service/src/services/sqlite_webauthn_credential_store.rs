@@ -0,0 +1,172 @@
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::domain::{
+    Email, WebauthnCredential, WebauthnCredentialStore, WebauthnCredentialStoreError,
+};
+
+pub struct SqliteWebauthnCredentialStore {
+    pool: SqlitePool,
+}
+
+impl SqliteWebauthnCredentialStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl WebauthnCredentialStore for SqliteWebauthnCredentialStore {
+    async fn add_credential(
+        &mut self,
+        credential: WebauthnCredential,
+    ) -> Result<(), WebauthnCredentialStoreError> {
+        sqlx::query(
+            "INSERT INTO webauthn_credentials \
+             (credential_id, email, public_key, sign_count, created_at) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&credential.credential_id)
+        .bind(credential.email.as_ref())
+        .bind(&credential.public_key)
+        .bind(credential.sign_count as i64)
+        .bind(credential.created_at as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(e) if e.is_unique_violation() => {
+                WebauthnCredentialStoreError::CredentialAlreadyExists
+            }
+            _ => WebauthnCredentialStoreError::UnexpectedError,
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_credential(
+        &self,
+        credential_id: &str,
+    ) -> Result<WebauthnCredential, WebauthnCredentialStoreError> {
+        let row = sqlx::query(
+            "SELECT credential_id, email, public_key, sign_count, created_at \
+             FROM webauthn_credentials WHERE credential_id = ?",
+        )
+        .bind(credential_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|_| WebauthnCredentialStoreError::UnexpectedError)?
+        .ok_or(WebauthnCredentialStoreError::CredentialNotFound)?;
+
+        parse_row(&row)
+    }
+
+    async fn get_credentials(
+        &self,
+        email: &Email,
+    ) -> Result<Vec<WebauthnCredential>, WebauthnCredentialStoreError> {
+        sqlx::query(
+            "SELECT credential_id, email, public_key, sign_count, created_at \
+             FROM webauthn_credentials WHERE email = ? ORDER BY created_at, credential_id",
+        )
+        .bind(email.as_ref())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|_| WebauthnCredentialStoreError::UnexpectedError)?
+        .iter()
+        .map(parse_row)
+        .collect()
+    }
+
+    async fn update_sign_count(
+        &mut self,
+        credential_id: &str,
+        sign_count: u32,
+    ) -> Result<(), WebauthnCredentialStoreError> {
+        let rows_affected =
+            sqlx::query("UPDATE webauthn_credentials SET sign_count = ? WHERE credential_id = ?")
+                .bind(sign_count as i64)
+                .bind(credential_id)
+                .execute(&self.pool)
+                .await
+                .map_err(|_| WebauthnCredentialStoreError::UnexpectedError)?
+                .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(WebauthnCredentialStoreError::CredentialNotFound);
+        }
+        Ok(())
+    }
+}
+
+fn parse_row(row: &SqliteRow) -> Result<WebauthnCredential, WebauthnCredentialStoreError> {
+    let parse = || -> Result<WebauthnCredential, sqlx::Error> {
+        Ok(WebauthnCredential {
+            credential_id: row.try_get("credential_id")?,
+            email: Email::parse(row.try_get("email")?)
+                .map_err(|e| sqlx::Error::Decode(e.to_string().into()))?,
+            public_key: row.try_get("public_key")?,
+            sign_count: row.try_get::<i64, _>("sign_count")? as u32,
+            created_at: row.try_get::<i64, _>("created_at")? as u64,
+        })
+    };
+
+    parse().map_err(|_| WebauthnCredentialStoreError::UnexpectedError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_sqlite_pool;
+
+    fn test_credential(credential_id: &str, email: &str, created_at: u64) -> WebauthnCredential {
+        WebauthnCredential {
+            credential_id: credential_id.to_owned(),
+            email: Email::parse(email.to_owned()).unwrap(),
+            public_key: "public-key".to_owned(),
+            sign_count: 0,
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_credentials() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteWebauthnCredentialStore::new(pool);
+        let second = test_credential("second", "test@example.com", 2);
+        let first = test_credential("first", "test@example.com", 1);
+        let other = test_credential("other", "other@example.com", 1);
+        for credential in [&second, &first, &other] {
+            store.add_credential(credential.clone()).await.unwrap();
+        }
+
+        assert_eq!(
+            store.add_credential(first.clone()).await,
+            Err(WebauthnCredentialStoreError::CredentialAlreadyExists)
+        );
+        assert_eq!(store.get_credential("other").await, Ok(other));
+        assert_eq!(
+            store.get_credentials(&first.email).await,
+            Ok(vec![first, second])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_sign_count() {
+        let (_dir, pool) = test_sqlite_pool().await;
+        let mut store = SqliteWebauthnCredentialStore::new(pool);
+        store
+            .add_credential(test_credential("id", "test@example.com", 1))
+            .await
+            .unwrap();
+
+        store.update_sign_count("id", u32::MAX).await.unwrap();
+
+        assert_eq!(
+            store.get_credential("id").await.unwrap().sign_count,
+            u32::MAX
+        );
+        assert_eq!(
+            store.update_sign_count("unknown", 1).await,
+            Err(WebauthnCredentialStoreError::CredentialNotFound)
+        );
+    }
+}
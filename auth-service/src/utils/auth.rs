@@ -0,0 +1,592 @@
+use std::str::FromStr;
+
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    errors::ErrorKind,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use ring::{rand::SystemRandom, signature::Ed25519KeyPair, signature::KeyPair};
+use rsa::{
+    pkcs1::DecodeRsaPrivateKey,
+    pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding},
+    traits::PublicKeyParts,
+    RsaPrivateKey,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{
+    constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME, RSA_KEY_BITS},
+    time::unix_now,
+};
+use crate::{
+    app_state::{BannedTokenStoreType, JwtKeysType, SigningKeyStoreType, UserStoreType},
+    domain::{Email, SigningKey, SigningKeyStatus, SigningKeyStoreError, UserStoreError},
+};
+
+// Keys used to sign and verify auth tokens. HS256 uses a shared secret that is never
+// published. RS256 and EdDSA keys are published as a JWKS and named by the `kid`
+// header of the tokens they sign, so tokens signed by a previous key keep verifying
+// after a rotation.
+#[derive(Clone)]
+pub struct JwtKeys {
+    algorithm: Algorithm,
+    signing_kid: Option<String>,
+    encoding_key: EncodingKey,
+    verification_keys: Vec<VerificationKey>,
+    jwks: JwkSet,
+}
+
+#[derive(Clone)]
+struct VerificationKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    decoding_key: DecodingKey,
+}
+
+#[derive(Debug)]
+pub enum JwtKeysError {
+    InvalidKey(String),
+    UnsupportedAlgorithm(String),
+    NoSigningKey,
+    StoreError(SigningKeyStoreError),
+}
+
+impl From<SigningKeyStoreError> for JwtKeysError {
+    fn from(e: SigningKeyStoreError) -> Self {
+        JwtKeysError::StoreError(e)
+    }
+}
+
+impl JwtKeys {
+    pub fn hs256(secret: &[u8]) -> Self {
+        Self {
+            algorithm: Algorithm::HS256,
+            signing_kid: None,
+            encoding_key: EncodingKey::from_secret(secret),
+            verification_keys: vec![VerificationKey {
+                kid: None,
+                algorithm: Algorithm::HS256,
+                decoding_key: DecodingKey::from_secret(secret),
+            }],
+            jwks: JwkSet { keys: vec![] },
+        }
+    }
+
+    // A single RS256 or EdDSA key, e.g. read from `jwt.private_key_file`.
+    pub fn from_private_key_pem(algorithm: &str, private_key: &str) -> Result<Self, JwtKeysError> {
+        let mut key = signing_key_from_pem(algorithm, private_key.to_owned())?;
+        key.status = SigningKeyStatus::Signing;
+        Self::from_signing_keys(&[key])
+    }
+
+    // Every key verifies tokens; the one with the `Signing` status signs new ones.
+    pub fn from_signing_keys(keys: &[SigningKey]) -> Result<Self, JwtKeysError> {
+        let mut signing = None;
+        let mut verification_keys = Vec::with_capacity(keys.len());
+        let mut jwks = JwkSet {
+            keys: Vec::with_capacity(keys.len()),
+        };
+
+        for key in keys {
+            let (algorithm, encoding_key, jwk) =
+                parse_private_key(&key.algorithm, &key.private_key)?;
+            let jwk = with_key_id(jwk, algorithm, &key.kid);
+            if key.status == SigningKeyStatus::Signing {
+                signing = Some((algorithm, key.kid.clone(), encoding_key));
+            }
+            verification_keys.push(VerificationKey {
+                kid: Some(key.kid.clone()),
+                algorithm,
+                decoding_key: DecodingKey::from_jwk(&jwk)
+                    .map_err(|e| JwtKeysError::InvalidKey(e.to_string()))?,
+            });
+            jwks.keys.push(jwk);
+        }
+
+        let (algorithm, signing_kid, encoding_key) = signing.ok_or(JwtKeysError::NoSigningKey)?;
+        Ok(Self {
+            algorithm,
+            signing_kid: Some(signing_kid),
+            encoding_key,
+            verification_keys,
+            jwks,
+        })
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    // `kid` of the key new tokens are signed with. HS256 tokens have none.
+    pub fn signing_kid(&self) -> Option<&str> {
+        self.signing_kid.as_deref()
+    }
+
+    // Public keys to verify tokens with, served at `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+}
+
+// Generate a new key pair for the signing key store. It starts on standby.
+pub fn generate_signing_key(algorithm: &str) -> Result<SigningKey, JwtKeysError> {
+    let private_key = match parse_algorithm(algorithm)? {
+        Algorithm::RS256 => RsaPrivateKey::new(&mut rand::rngs::OsRng, RSA_KEY_BITS)
+            .and_then(|key| Ok(key.to_pkcs8_pem(LineEnding::LF)?.to_string()))
+            .map_err(|e| JwtKeysError::InvalidKey(e.to_string()))?,
+        _ => {
+            let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .map_err(|e| JwtKeysError::InvalidKey(e.to_string()))?;
+            pem::encode(&pem::Pem::new("PRIVATE KEY", document.as_ref()))
+        }
+    };
+
+    signing_key_from_pem(algorithm, private_key)
+}
+
+fn signing_key_from_pem(algorithm: &str, private_key: String) -> Result<SigningKey, JwtKeysError> {
+    let (_, _, jwk) = parse_private_key(algorithm, &private_key)?;
+
+    Ok(SigningKey {
+        kid: thumbprint(&jwk),
+        algorithm: algorithm.to_owned(),
+        private_key,
+        status: SigningKeyStatus::Standby,
+        created_at: unix_now(),
+    })
+}
+
+fn parse_algorithm(algorithm: &str) -> Result<Algorithm, JwtKeysError> {
+    match Algorithm::from_str(algorithm) {
+        Ok(algorithm @ (Algorithm::RS256 | Algorithm::EdDSA)) => Ok(algorithm),
+        _ => Err(JwtKeysError::UnsupportedAlgorithm(algorithm.to_owned())),
+    }
+}
+
+// Load a PKCS#8 (or, for RSA, PKCS#1) PEM private key, returning the key to sign
+// with and its public half as a JWK without `kid`.
+fn parse_private_key(
+    algorithm: &str,
+    private_key: &str,
+) -> Result<(Algorithm, EncodingKey, Jwk), JwtKeysError> {
+    let invalid_key = |e: &dyn std::fmt::Display| JwtKeysError::InvalidKey(e.to_string());
+
+    let algorithm = parse_algorithm(algorithm)?;
+    let (encoding_key, parameters) = match algorithm {
+        Algorithm::RS256 => {
+            let key = RsaPrivateKey::from_pkcs8_pem(private_key)
+                .or_else(|_| RsaPrivateKey::from_pkcs1_pem(private_key))
+                .map_err(|e| invalid_key(&e))?;
+            let encoding_key =
+                EncodingKey::from_rsa_pem(private_key.as_bytes()).map_err(|e| invalid_key(&e))?;
+            let parameters = AlgorithmParameters::RSA(RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n: URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+                e: URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+            });
+            (encoding_key, parameters)
+        }
+        _ => {
+            let document = pem::parse(private_key).map_err(|e| invalid_key(&e))?;
+            let key = Ed25519KeyPair::from_pkcs8_maybe_unchecked(document.contents())
+                .map_err(|e| invalid_key(&e))?;
+            let parameters = AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: URL_SAFE_NO_PAD.encode(key.public_key().as_ref()),
+            });
+            (EncodingKey::from_ed_der(document.contents()), parameters)
+        }
+    };
+
+    let jwk = Jwk {
+        common: CommonParameters::default(),
+        algorithm: parameters,
+    };
+    Ok((algorithm, encoding_key, jwk))
+}
+
+fn with_key_id(mut jwk: Jwk, algorithm: Algorithm, kid: &str) -> Jwk {
+    jwk.common = CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_algorithm: Some(match algorithm {
+            Algorithm::RS256 => KeyAlgorithm::RS256,
+            _ => KeyAlgorithm::EdDSA,
+        }),
+        key_id: Some(kid.to_owned()),
+        ..CommonParameters::default()
+    };
+    jwk
+}
+
+// RFC 7638 JWK thumbprint: the hash of the required public members in lexicographic
+// order, so the same key always gets the same `kid`.
+fn thumbprint(jwk: &Jwk) -> String {
+    let members = match &jwk.algorithm {
+        AlgorithmParameters::RSA(rsa) => {
+            format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, rsa.e, rsa.n)
+        }
+        AlgorithmParameters::OctetKeyPair(okp) => {
+            format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, okp.x)
+        }
+        _ => unreachable!("only RSA and Ed25519 keys are supported"),
+    };
+    URL_SAFE_NO_PAD.encode(Sha256::digest(members.as_bytes()))
+}
+
+// Read the key set from the store. A store without a signing key, e.g. on the first
+// start, gets a freshly generated one.
+pub async fn load_jwt_keys(
+    store: &SigningKeyStoreType,
+    algorithm: &str,
+) -> Result<JwtKeys, JwtKeysError> {
+    let keys = store.read().await.get_keys().await?;
+    if keys
+        .iter()
+        .any(|key| key.status == SigningKeyStatus::Signing)
+    {
+        return JwtKeys::from_signing_keys(&keys);
+    }
+
+    let key = generate_signing_key(algorithm)?;
+    let mut store = store.write().await;
+    store.add_key(key.clone()).await?;
+    store.promote_key(&key.kid).await?;
+
+    JwtKeys::from_signing_keys(&store.get_keys().await?)
+}
+
+// Pick up keys generated, promoted or retired since the last load.
+pub async fn reload_jwt_keys(
+    store: &SigningKeyStoreType,
+    jwt_keys: &JwtKeysType,
+) -> Result<(), JwtKeysError> {
+    let keys = store.read().await.get_keys().await?;
+    let reloaded = JwtKeys::from_signing_keys(&keys)?;
+
+    *jwt_keys.write().await = reloaded;
+    Ok(())
+}
+
+// Create cookie with a new JWT auth token
+pub fn generate_auth_cookie(
+    email: &Email,
+    session_generation: u64,
+    keys: &JwtKeys,
+    ttl_seconds: u64,
+    secure: bool,
+) -> Result<Cookie<'static>, GenerateTokenError> {
+    let token = generate_auth_token(email, session_generation, keys, ttl_seconds)?;
+    Ok(create_auth_cookie(token, secure))
+}
+
+// Create cookie and set the value to the passed-in token string. `secure` is only
+// off for deployments served over plain http, where browsers drop Secure cookies.
+fn create_auth_cookie(token: String, secure: bool) -> Cookie<'static> {
+    Cookie::build((JWT_COOKIE_NAME, token))
+        .path("/") // apply cookie to all URLs on the server
+        .http_only(true) // prevent JavaScript from accessing the cookie
+        .same_site(SameSite::Lax) // send cookie with "same-site" requests, and with "cross-site" top-level navigations.
+        .secure(secure)
+        .build()
+}
+
+// Create a cookie that tells the browser to drop the auth cookie.
+// Attributes must match the original cookie for the removal to take effect.
+pub fn removal_auth_cookie(secure: bool) -> Cookie<'static> {
+    let mut cookie = create_auth_cookie(String::new(), secure);
+    cookie.make_removal();
+    cookie
+}
+
+// Create the refresh token cookie. Unlike the auth cookie it is never sent along
+// with cross-site requests and outlives the JWT.
+pub fn create_refresh_cookie(token: String, ttl_seconds: u64, secure: bool) -> Cookie<'static> {
+    Cookie::build((REFRESH_TOKEN_COOKIE_NAME, token))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .secure(secure)
+        .max_age(::time::Duration::seconds(
+            i64::try_from(ttl_seconds).unwrap_or(i64::MAX),
+        ))
+        .build()
+}
+
+// Create a cookie that tells the browser to drop the refresh token cookie.
+pub fn removal_refresh_cookie(secure: bool) -> Cookie<'static> {
+    let mut cookie = create_refresh_cookie(String::new(), 0, secure);
+    cookie.make_removal();
+    cookie
+}
+
+#[derive(Debug)]
+pub enum GenerateTokenError {
+    TokenError(jsonwebtoken::errors::Error),
+    UnexpectedError,
+}
+
+// Create JWT auth token, valid until it expires or the user's session generation
+// moves past `session_generation`.
+pub fn generate_auth_token(
+    email: &Email,
+    session_generation: u64,
+    keys: &JwtKeys,
+    ttl_seconds: u64,
+) -> Result<String, GenerateTokenError> {
+    let iat = unix_now();
+
+    let exp = iat
+        .checked_add(ttl_seconds)
+        .ok_or(GenerateTokenError::UnexpectedError)?;
+
+    let claims = Claims {
+        sub: email.as_ref().to_owned(),
+        iat: iat as usize,
+        exp: exp as usize,
+        jti: uuid::Uuid::new_v4().to_string(),
+        session_generation,
+    };
+
+    let mut header = Header::new(keys.algorithm);
+    header.kid = keys.signing_kid.clone();
+
+    encode(&header, &claims, &keys.encoding_key).map_err(GenerateTokenError::TokenError)
+}
+
+#[derive(Debug)]
+pub enum TokenError {
+    InvalidToken(jsonwebtoken::errors::Error),
+    BannedToken,
+    UnexpectedError,
+}
+
+// Check if JWT auth token is valid and has not been revoked, either on its own by a
+// logout or together with every other token of the user by a password reset.
+pub async fn validate_token(
+    token: &str,
+    keys: &JwtKeys,
+    banned_token_store: &BannedTokenStoreType,
+    user_store: &UserStoreType,
+) -> Result<Claims, TokenError> {
+    let claims = decode_token(token, keys).map_err(TokenError::InvalidToken)?;
+
+    match banned_token_store
+        .read()
+        .await
+        .contains_token(&claims.jti)
+        .await
+    {
+        Ok(false) => {}
+        Ok(true) => return Err(TokenError::BannedToken),
+        Err(_) => return Err(TokenError::UnexpectedError),
+    }
+
+    let email = Email::parse(claims.sub.clone())
+        .map_err(|_| TokenError::InvalidToken(ErrorKind::InvalidSubject.into()))?;
+    let session_generation = match user_store.read().await.get_user(&email).await {
+        Ok(user) => user.session_generation,
+        // The account was deleted; there is nothing left to revoke.
+        Err(UserStoreError::UserNotFound) => 0,
+        Err(_) => return Err(TokenError::UnexpectedError),
+    };
+    if claims.session_generation < session_generation {
+        return Err(TokenError::BannedToken);
+    }
+
+    Ok(claims)
+}
+
+// Decode JWT auth token with the verification key named by its `kid` header.
+// Malformed, expired, wrongly-signed and retired-key tokens are all rejected.
+pub fn decode_token(token: &str, keys: &JwtKeys) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let kid = decode_header(token)?.kid;
+    let key = keys
+        .verification_keys
+        .iter()
+        .find(|key| key.kid == kid)
+        .ok_or(ErrorKind::InvalidToken)?;
+
+    // Only the algorithm of the chosen key is accepted, whatever the header claims.
+    let mut validation = Validation::new(key.algorithm);
+    validation.set_required_spec_claims(&["sub", "iat", "exp", "jti"]);
+
+    decode::<Claims>(token, &key.decoding_key, &validation).map(|data| data.claims)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub iat: usize,
+    pub exp: usize,
+    pub jti: String,
+    // Session generation of the user when the token was issued. Tokens from before
+    // this claim existed count as generation 0.
+    #[serde(rename = "gen", default)]
+    pub session_generation: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::constants::TOKEN_TTL_SECONDS;
+
+    const ED25519_PRIVATE_KEY: &str = include_str!("../../tests/fixtures/ed25519_private.pem");
+    const ED25519_PUBLIC_KEY: &str = include_str!("../../tests/fixtures/ed25519_public.pem");
+    const RSA_PRIVATE_KEY: &str = include_str!("../../tests/fixtures/rsa_private.pem");
+    const RSA_PUBLIC_KEY: &str = include_str!("../../tests/fixtures/rsa_public.pem");
+
+    fn test_email() -> Email {
+        Email::parse("test@example.com".to_owned()).unwrap()
+    }
+
+    fn assert_round_trip(keys: &JwtKeys) {
+        let token = generate_auth_token(&test_email(), 3, keys, TOKEN_TTL_SECONDS).unwrap();
+        let claims = decode_token(&token, keys).unwrap();
+
+        assert_eq!(claims.sub, "test@example.com");
+        assert_eq!(claims.session_generation, 3);
+        assert_eq!(claims.exp - claims.iat, TOKEN_TTL_SECONDS as usize);
+        assert!(!claims.jti.is_empty());
+    }
+
+    #[test]
+    fn test_hs256_round_trip() {
+        assert_round_trip(&JwtKeys::hs256(b"secret"));
+    }
+
+    #[test]
+    fn test_rs256_round_trip() {
+        assert_round_trip(&JwtKeys::from_private_key_pem("RS256", RSA_PRIVATE_KEY).unwrap());
+    }
+
+    #[test]
+    fn test_eddsa_round_trip() {
+        assert_round_trip(&JwtKeys::from_private_key_pem("EdDSA", ED25519_PRIVATE_KEY).unwrap());
+    }
+
+    #[test]
+    fn test_jwks_publishes_public_keys() {
+        use rsa::{pkcs8::DecodePublicKey, RsaPublicKey};
+
+        let keys = JwtKeys::from_private_key_pem("RS256", RSA_PRIVATE_KEY).unwrap();
+        let public_key = RsaPublicKey::from_public_key_pem(RSA_PUBLIC_KEY).unwrap();
+        let jwk = &keys.jwks().keys[0];
+        let AlgorithmParameters::RSA(parameters) = &jwk.algorithm else {
+            panic!("expected an RSA key, got {:?}", jwk);
+        };
+        assert_eq!(
+            parameters.n,
+            URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be())
+        );
+        assert_eq!(jwk.common.key_id.as_deref(), keys.signing_kid());
+
+        let keys = JwtKeys::from_private_key_pem("EdDSA", ED25519_PRIVATE_KEY).unwrap();
+        let public_key = pem::parse(ED25519_PUBLIC_KEY).unwrap();
+        let jwk = &keys.jwks().keys[0];
+        let AlgorithmParameters::OctetKeyPair(parameters) = &jwk.algorithm else {
+            panic!("expected an Ed25519 key, got {:?}", jwk);
+        };
+        // The raw key is the tail of the SubjectPublicKeyInfo.
+        assert!(public_key
+            .contents()
+            .ends_with(&URL_SAFE_NO_PAD.decode(&parameters.x).unwrap()));
+
+        assert!(JwtKeys::hs256(b"secret").jwks().keys.is_empty());
+    }
+
+    #[test]
+    fn test_kid_is_the_rfc_7638_thumbprint() {
+        // Example key from RFC 8037, appendix A.3.
+        let jwk = Jwk {
+            common: CommonParameters::default(),
+            algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo".to_owned(),
+            }),
+        };
+
+        assert_eq!(
+            thumbprint(&jwk),
+            "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k"
+        );
+    }
+
+    #[test]
+    fn test_rotated_keys_verify_until_retired() {
+        let mut first = generate_signing_key("EdDSA").unwrap();
+        first.status = SigningKeyStatus::Signing;
+        let mut second = generate_signing_key("RS256").unwrap();
+        let first_keys = JwtKeys::from_signing_keys(&[first.clone(), second.clone()]).unwrap();
+        let token = generate_auth_token(&test_email(), 0, &first_keys, TOKEN_TTL_SECONDS).unwrap();
+
+        assert_eq!(
+            decode_header(&token).unwrap().kid.as_deref(),
+            Some(first.kid.as_str())
+        );
+        assert_eq!(first_keys.jwks().keys.len(), 2);
+
+        // Promote the second key: new tokens use it, old ones still verify.
+        first.status = SigningKeyStatus::Standby;
+        second.status = SigningKeyStatus::Signing;
+        let second_keys = JwtKeys::from_signing_keys(&[first, second.clone()]).unwrap();
+        let new_token =
+            generate_auth_token(&test_email(), 0, &second_keys, TOKEN_TTL_SECONDS).unwrap();
+
+        assert_eq!(second_keys.signing_kid(), Some(second.kid.as_str()));
+        assert_eq!(second_keys.algorithm(), Algorithm::RS256);
+        assert!(decode_token(&token, &second_keys).is_ok());
+        assert!(decode_token(&new_token, &second_keys).is_ok());
+
+        // Retire the first key.
+        let retired_keys = JwtKeys::from_signing_keys(&[second]).unwrap();
+        assert!(decode_token(&token, &retired_keys).is_err());
+        assert!(decode_token(&new_token, &retired_keys).is_ok());
+    }
+
+    #[test]
+    fn test_key_set_needs_a_signing_key() {
+        let key = generate_signing_key("EdDSA").unwrap();
+
+        assert!(matches!(
+            JwtKeys::from_signing_keys(&[key]),
+            Err(JwtKeysError::NoSigningKey)
+        ));
+        assert!(matches!(
+            generate_signing_key("HS256"),
+            Err(JwtKeysError::UnsupportedAlgorithm(_))
+        ));
+    }
+
+    #[test]
+    fn test_each_token_has_unique_jti() {
+        let keys = JwtKeys::hs256(b"secret");
+        let first = generate_auth_token(&test_email(), 0, &keys, TOKEN_TTL_SECONDS).unwrap();
+        let second = generate_auth_token(&test_email(), 0, &keys, TOKEN_TTL_SECONDS).unwrap();
+
+        assert_ne!(
+            decode_token(&first, &keys).unwrap().jti,
+            decode_token(&second, &keys).unwrap().jti
+        );
+    }
+
+    #[test]
+    fn test_rejects_token_signed_with_other_key() {
+        let token = generate_auth_token(&test_email(), 0, &JwtKeys::hs256(b"secret"), 60).unwrap();
+
+        assert!(decode_token(&token, &JwtKeys::hs256(b"other-secret")).is_err());
+        assert!(decode_token(
+            &token,
+            &JwtKeys::from_private_key_pem("EdDSA", ED25519_PRIVATE_KEY).unwrap()
+        )
+        .is_err());
+    }
+}
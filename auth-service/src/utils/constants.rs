@@ -0,0 +1,21 @@
+pub const JWT_COOKIE_NAME: &str = "jwt";
+pub const REFRESH_TOKEN_COOKIE_NAME: &str = "refresh_token";
+
+// Default lifetime of an issued JWT in seconds, see `jwt.token_ttl_seconds`.
+pub const TOKEN_TTL_SECONDS: u64 = 600;
+
+// Default lifetime of a refresh token in seconds, see `jwt.refresh_token_ttl_seconds`.
+pub const REFRESH_TOKEN_TTL_SECONDS: u64 = 14 * 24 * 60 * 60;
+
+// Lifetime of a pending 2FA code in seconds.
+pub const TWO_FA_CODE_TTL_SECONDS: u64 = 600;
+
+// Wrong 2FA codes after which a pending login attempt is dropped, so a 6-digit
+// code can't be guessed within its lifetime.
+pub const MAX_TWO_FA_ATTEMPTS: u32 = 5;
+
+// Modulus size of generated RS256 signing keys.
+pub const RSA_KEY_BITS: usize = 2048;
+
+// Size of each set of 2FA recovery codes handed to a user.
+pub const RECOVERY_CODE_COUNT: usize = 10;
@@ -0,0 +1,9 @@
+pub mod auth;
+pub mod constants;
+pub mod email_verification;
+pub mod opaque_token;
+pub mod password;
+pub mod recovery_code;
+pub mod time;
+pub mod totp;
+pub mod webauthn;
@@ -0,0 +1,34 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+// A new opaque token, such as a refresh token or a password reset token: 256 random
+// bits, URL-safe encoded.
+pub fn generate_opaque_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// Opaque tokens are only stored as this hash. They are random enough that a plain
+// SHA-256 can't be reversed, and unlike a salted hash it can be looked up.
+pub fn hash_opaque_token(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_are_random_and_hashed() {
+        let first = generate_opaque_token();
+        let second = generate_opaque_token();
+
+        assert_eq!(first.len(), 43);
+        assert_ne!(first, second);
+        assert_eq!(hash_opaque_token(&first), hash_opaque_token(&first));
+        assert_ne!(hash_opaque_token(&first), hash_opaque_token(&second));
+        assert_ne!(hash_opaque_token(&first), first);
+    }
+}
@@ -0,0 +1,162 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+
+use crate::domain::Password;
+
+// Hashes and verifies passwords with Argon2id, storing the result as a PHC string
+// (`$argon2id$v=19$m=...,t=...,p=...$salt$hash`) so the parameters travel with the hash.
+#[derive(Clone)]
+pub struct Argon2Hasher {
+    params: Params,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PasswordHashError {
+    InvalidParams,
+    InvalidHash,
+    UnexpectedError,
+}
+
+impl Argon2Hasher {
+    pub fn new(params: Params) -> Self {
+        Self { params }
+    }
+
+    fn argon2(params: Params) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+    }
+
+    // Hashing is CPU-bound and deliberately slow, so it runs on the blocking pool
+    // instead of stalling the async executor.
+    pub async fn hash(&self, password: Password) -> Result<String, PasswordHashError> {
+        let params = self.params.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let salt = SaltString::generate(&mut OsRng);
+            Self::argon2(params)
+                .hash_password(password.as_ref().as_bytes(), &salt)
+                .map(|hash| hash.to_string())
+                .map_err(|_| PasswordHashError::UnexpectedError)
+        })
+        .await
+        .map_err(|_| PasswordHashError::UnexpectedError)?
+    }
+
+    // Returns `Ok(true)` if the password matches the stored hash. The comparison of the
+    // derived output is constant-time; the cost parameters are taken from the hash itself.
+    pub async fn verify(
+        &self,
+        password: Password,
+        password_hash: String,
+    ) -> Result<bool, PasswordHashError> {
+        tokio::task::spawn_blocking(move || {
+            let expected =
+                PasswordHash::new(&password_hash).map_err(|_| PasswordHashError::InvalidHash)?;
+
+            match Argon2::default().verify_password(password.as_ref().as_bytes(), &expected) {
+                Ok(()) => Ok(true),
+                Err(argon2::password_hash::Error::Password) => Ok(false),
+                Err(_) => Err(PasswordHashError::InvalidHash),
+            }
+        })
+        .await
+        .map_err(|_| PasswordHashError::UnexpectedError)?
+    }
+
+    // Performs the same amount of work as a verification without a stored hash.
+    // Used when no user exists, so unknown emails take as long to reject as wrong passwords.
+    pub async fn verify_dummy(&self, password: Password) -> Result<(), PasswordHashError> {
+        self.hash(password).await.map(|_| ())
+    }
+
+    // A hash needs upgrading when it was produced by a different algorithm,
+    // version or set of cost parameters than the ones currently configured.
+    pub fn needs_rehash(&self, password_hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(password_hash) else {
+            return true;
+        };
+
+        if parsed.algorithm != Algorithm::Argon2id.ident()
+            || parsed.version != Some(Version::V0x13.into())
+        {
+            return true;
+        }
+
+        match Params::try_from(&parsed) {
+            Ok(params) => {
+                params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+impl Default for Argon2Hasher {
+    fn default() -> Self {
+        Self::new(Params::DEFAULT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn password(password: &str) -> Password {
+        Password::parse(password.to_owned()).unwrap()
+    }
+
+    fn cheap_hasher(t_cost: u32) -> Argon2Hasher {
+        Argon2Hasher::new(Params::new(Params::MIN_M_COST, t_cost, 1, None).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_hash_is_phc_argon2id() {
+        let hasher = cheap_hasher(1);
+        let hash = hasher.hash(password("password123")).await.unwrap();
+
+        assert!(hash.starts_with("$argon2id$v=19$"));
+        assert_ne!(hash, hasher.hash(password("password123")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let hasher = cheap_hasher(1);
+        let hash = hasher.hash(password("password123")).await.unwrap();
+
+        assert_eq!(
+            hasher.verify(password("password123"), hash.clone()).await,
+            Ok(true)
+        );
+        assert_eq!(
+            hasher.verify(password("wrong-password"), hash).await,
+            Ok(false)
+        );
+        assert_eq!(
+            hasher
+                .verify(password("password123"), "password123".to_owned())
+                .await,
+            Err(PasswordHashError::InvalidHash)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_needs_rehash_when_params_change() {
+        let old_hasher = cheap_hasher(1);
+        let new_hasher = cheap_hasher(2);
+        let hash = old_hasher.hash(password("password123")).await.unwrap();
+
+        assert!(!old_hasher.needs_rehash(&hash));
+        assert!(new_hasher.needs_rehash(&hash));
+        assert!(new_hasher.needs_rehash("not-a-phc-string"));
+
+        // A hash made under old parameters still verifies with the new hasher.
+        assert_eq!(
+            new_hasher.verify(password("password123"), hash).await,
+            Ok(true)
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use rand::Rng;
+
+use super::opaque_token::hash_opaque_token;
+
+// Crockford's base32 alphabet: no I, L, O or U, so codes survive being written down.
+const RECOVERY_CODE_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+// 16 characters carry 80 random bits, shown in groups of 4.
+const RECOVERY_CODE_LENGTH: usize = 16;
+const RECOVERY_CODE_GROUP: usize = 4;
+
+// A fresh code such as `7KQ2-M9XD-0T4B-RW8C`.
+pub fn generate_recovery_code() -> String {
+    let mut rng = rand::thread_rng();
+    let chars: Vec<char> = (0..RECOVERY_CODE_LENGTH)
+        .map(|_| RECOVERY_CODE_ALPHABET[rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+        .collect();
+
+    chars
+        .chunks(RECOVERY_CODE_GROUP)
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// The hash a recovery code is stored as. Case, dashes and whitespace are ignored, and
+// letters that are easily mistaken for digits are read as those digits. Returns
+// `None` if the input can't be a recovery code.
+pub fn hash_recovery_code(code: &str) -> Option<String> {
+    let normalized: String = code
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        })
+        .collect();
+
+    if normalized.len() != RECOVERY_CODE_LENGTH
+        || !normalized
+            .bytes()
+            .all(|b| RECOVERY_CODE_ALPHABET.contains(&b))
+    {
+        return None;
+    }
+
+    Some(hash_opaque_token(&normalized))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_codes_are_unique_and_hashable() {
+        let first = generate_recovery_code();
+        let second = generate_recovery_code();
+
+        assert_eq!(first.len(), 19);
+        assert_eq!(first.matches('-').count(), 3);
+        assert_ne!(first, second);
+        assert!(hash_recovery_code(&first).is_some());
+        assert_ne!(hash_recovery_code(&first), hash_recovery_code(&second));
+    }
+
+    #[test]
+    fn test_hash_ignores_formatting() {
+        let hash = hash_recovery_code("7KQ2-M9XD-0T4B-RW81");
+
+        assert!(hash.is_some());
+        assert_eq!(hash_recovery_code("7kq2m9xd 0t4b rw81"), hash);
+        assert_eq!(hash_recovery_code("7KQ2-M9XD-OT4B-RW8l"), hash);
+    }
+
+    #[test]
+    fn test_hash_rejects_malformed_codes() {
+        for code in [
+            "",
+            "7KQ2-M9XD-0T4B",
+            "7KQ2-M9XD-0T4B-RW8C-1",
+            "7KQ2-M9XD-0T4B-RW8U",
+        ] {
+            assert_eq!(hash_recovery_code(code), None, "{code:?}");
+        }
+    }
+}
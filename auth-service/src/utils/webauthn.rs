@@ -0,0 +1,602 @@
+use std::fmt;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ciborium::Value;
+use rand::RngCore;
+use ring::signature::{self, UnparsedPublicKey, VerificationAlgorithm};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::domain::Email;
+
+// WebAuthn Level 2 recommends at least 16 random bytes.
+const CHALLENGE_BYTES: usize = 32;
+
+// COSE algorithms (RFC 9053) of the credential keys that are accepted. Together they
+// cover every platform authenticator and security key in common use.
+pub const COSE_ALG_ES256: i64 = -7;
+pub const COSE_ALG_EDDSA: i64 = -8;
+
+// Authenticator data flags.
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+// RP ID hash, flags and signature counter.
+const AUTHENTICATOR_DATA_MIN_LEN: usize = 37;
+// AAGUID and credential id length preceding the credential id.
+const ATTESTED_CREDENTIAL_HEADER_LEN: usize = 18;
+
+#[derive(Debug, PartialEq)]
+pub enum WebauthnError {
+    Malformed(String),
+    UnsupportedAttestation(String),
+    UnsupportedAlgorithm,
+    // The credential id sent with an attestation isn't the one the authenticator attested.
+    CredentialIdMismatch,
+    WrongCeremony,
+    UntrustedOrigin(String),
+    RpIdMismatch,
+    UserNotPresent,
+    UserNotVerified,
+    InvalidSignature,
+    // The authenticator's counter didn't move forward, which hints at a cloned key.
+    SignCountNotIncreased,
+}
+
+impl fmt::Display for WebauthnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebauthnError::Malformed(what) => write!(f, "Malformed {}", what),
+            WebauthnError::UnsupportedAttestation(format) => {
+                write!(f, "Unsupported attestation format {:?}", format)
+            }
+            WebauthnError::UnsupportedAlgorithm => f.write_str("Unsupported credential algorithm"),
+            WebauthnError::CredentialIdMismatch => {
+                f.write_str("Credential id does not match the attested credential")
+            }
+            WebauthnError::WrongCeremony => f.write_str("Wrong WebAuthn ceremony"),
+            WebauthnError::UntrustedOrigin(origin) => write!(f, "Untrusted origin {:?}", origin),
+            WebauthnError::RpIdMismatch => f.write_str("Credential is for another relying party"),
+            WebauthnError::UserNotPresent => f.write_str("User presence was not confirmed"),
+            WebauthnError::UserNotVerified => f.write_str("User verification is required"),
+            WebauthnError::InvalidSignature => f.write_str("Invalid signature"),
+            WebauthnError::SignCountNotIncreased => {
+                f.write_str("Signature counter did not increase")
+            }
+        }
+    }
+}
+
+// The service as a WebAuthn relying party: credentials are scoped to `id` (a domain)
+// and ceremonies are only accepted from pages served by one of `origins`.
+pub struct RelyingParty<'a> {
+    pub id: &'a str,
+    pub origins: &'a [String],
+}
+
+// `clientDataJSON` as collected by the browser.
+#[derive(Debug, Deserialize)]
+pub struct ClientData {
+    #[serde(rename = "type")]
+    pub ceremony_type: String,
+    // Base64url encoded, as handed out in the options.
+    pub challenge: String,
+    pub origin: String,
+    #[serde(rename = "crossOrigin", default)]
+    pub cross_origin: bool,
+    // SHA-256 of the raw JSON, which assertions sign.
+    #[serde(skip)]
+    hash: [u8; 32],
+}
+
+impl ClientData {
+    pub fn parse(client_data_json: &[u8]) -> Result<Self, WebauthnError> {
+        let mut client_data: ClientData = serde_json::from_slice(client_data_json)
+            .map_err(|_| WebauthnError::Malformed("client data".to_owned()))?;
+        client_data.hash = Sha256::digest(client_data_json).into();
+
+        Ok(client_data)
+    }
+
+    fn check(&self, ceremony_type: &str, rp: &RelyingParty) -> Result<(), WebauthnError> {
+        if self.ceremony_type != ceremony_type {
+            return Err(WebauthnError::WrongCeremony);
+        }
+        if self.cross_origin || !rp.origins.iter().any(|origin| origin == &self.origin) {
+            return Err(WebauthnError::UntrustedOrigin(self.origin.clone()));
+        }
+        Ok(())
+    }
+}
+
+// A credential that passed the registration ceremony.
+#[derive(Debug, PartialEq)]
+pub struct NewCredential {
+    pub credential_id: Vec<u8>,
+    // COSE encoded.
+    pub public_key: Vec<u8>,
+    pub sign_count: u32,
+}
+
+// Check the response to `navigator.credentials.create()`. Only the "none" attestation
+// format is accepted: the service doesn't restrict which authenticators may be used,
+// so it has no use for a statement about the authenticator's make and model.
+pub fn verify_registration(
+    rp: &RelyingParty,
+    client_data: &ClientData,
+    attestation_object: &[u8],
+) -> Result<NewCredential, WebauthnError> {
+    client_data.check("webauthn.create", rp)?;
+
+    let authenticator_data = parse_attestation_object(attestation_object)?;
+    let authenticator_data = AuthenticatorData::parse(&authenticator_data)?;
+    authenticator_data.check(rp, false)?;
+
+    let Some((credential_id, public_key)) = authenticator_data.attested_credential else {
+        return Err(WebauthnError::Malformed(
+            "attested credential data".to_owned(),
+        ));
+    };
+    parse_public_key(&public_key)?;
+
+    Ok(NewCredential {
+        credential_id,
+        public_key,
+        sign_count: authenticator_data.sign_count,
+    })
+}
+
+// Check the response to `navigator.credentials.get()` against the stored credential,
+// returning the new signature counter.
+pub fn verify_assertion(
+    rp: &RelyingParty,
+    client_data: &ClientData,
+    authenticator_data: &[u8],
+    signature: &[u8],
+    public_key: &[u8],
+    stored_sign_count: u32,
+    require_user_verification: bool,
+) -> Result<u32, WebauthnError> {
+    client_data.check("webauthn.get", rp)?;
+
+    let parsed = AuthenticatorData::parse(authenticator_data)?;
+    parsed.check(rp, require_user_verification)?;
+
+    let (algorithm, public_key) = parse_public_key(public_key)?;
+    let signed_data = [authenticator_data, &client_data.hash].concat();
+    UnparsedPublicKey::new(algorithm, public_key)
+        .verify(&signed_data, signature)
+        .map_err(|_| WebauthnError::InvalidSignature)?;
+
+    check_sign_count(stored_sign_count, parsed.sign_count)?;
+    Ok(parsed.sign_count)
+}
+
+// Authenticators without a counter always report 0. Once either side is non-zero the
+// counter has to increase with every assertion.
+pub fn check_sign_count(stored: u32, received: u32) -> Result<(), WebauthnError> {
+    if (stored != 0 || received != 0) && received <= stored {
+        return Err(WebauthnError::SignCountNotIncreased);
+    }
+    Ok(())
+}
+
+pub fn generate_challenge() -> String {
+    let mut challenge = [0u8; CHALLENGE_BYTES];
+    rand::thread_rng().fill_bytes(&mut challenge);
+    URL_SAFE_NO_PAD.encode(challenge)
+}
+
+// Stable opaque user id stored with discoverable credentials. Derived from the email
+// so no extra column is needed; WebAuthn only requires it to not be the email itself.
+pub fn user_handle(email: &Email) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(email.as_ref().as_bytes()))
+}
+
+pub fn base64url_encode(data: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+// Some clients pad their base64url, so padding is tolerated.
+pub fn base64url_decode(data: &str, what: &str) -> Result<Vec<u8>, WebauthnError> {
+    URL_SAFE_NO_PAD
+        .decode(data.trim_end_matches('='))
+        .map_err(|_| WebauthnError::Malformed(what.to_owned()))
+}
+
+struct AuthenticatorData {
+    rp_id_hash: [u8; 32],
+    flags: u8,
+    sign_count: u32,
+    // Credential id and COSE public key, only present at registration.
+    attested_credential: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl AuthenticatorData {
+    fn parse(data: &[u8]) -> Result<Self, WebauthnError> {
+        let malformed = || WebauthnError::Malformed("authenticator data".to_owned());
+        if data.len() < AUTHENTICATOR_DATA_MIN_LEN {
+            return Err(malformed());
+        }
+
+        let rp_id_hash = data[..32].try_into().map_err(|_| malformed())?;
+        let flags = data[32];
+        let sign_count = u32::from_be_bytes(data[33..37].try_into().map_err(|_| malformed())?);
+
+        let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL_DATA != 0 {
+            let data = &data[AUTHENTICATOR_DATA_MIN_LEN..];
+            if data.len() < ATTESTED_CREDENTIAL_HEADER_LEN {
+                return Err(malformed());
+            }
+            let id_len = u16::from_be_bytes([data[16], data[17]]) as usize;
+            let rest = &data[ATTESTED_CREDENTIAL_HEADER_LEN..];
+            if rest.len() < id_len {
+                return Err(malformed());
+            }
+            let (credential_id, mut rest) = rest.split_at(id_len);
+
+            // The COSE key isn't length-prefixed; reading it as CBOR tells where it
+            // ends and any extension data begins.
+            let len = rest.len();
+            let _: Value = ciborium::from_reader(&mut rest).map_err(|_| malformed())?;
+            let public_key = &data[ATTESTED_CREDENTIAL_HEADER_LEN + id_len..][..len - rest.len()];
+
+            Some((credential_id.to_vec(), public_key.to_vec()))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            rp_id_hash,
+            flags,
+            sign_count,
+            attested_credential,
+        })
+    }
+
+    fn check(
+        &self,
+        rp: &RelyingParty,
+        require_user_verification: bool,
+    ) -> Result<(), WebauthnError> {
+        if self.rp_id_hash[..] != Sha256::digest(rp.id.as_bytes())[..] {
+            return Err(WebauthnError::RpIdMismatch);
+        }
+        if self.flags & FLAG_USER_PRESENT == 0 {
+            return Err(WebauthnError::UserNotPresent);
+        }
+        if require_user_verification && self.flags & FLAG_USER_VERIFIED == 0 {
+            return Err(WebauthnError::UserNotVerified);
+        }
+        Ok(())
+    }
+}
+
+// The authenticator data of a "none" attestation object.
+fn parse_attestation_object(attestation_object: &[u8]) -> Result<Vec<u8>, WebauthnError> {
+    let malformed = || WebauthnError::Malformed("attestation object".to_owned());
+
+    let value: Value = ciborium::from_reader(attestation_object).map_err(|_| malformed())?;
+    let map = value.as_map().ok_or_else(malformed)?;
+    let get = |key: &str| {
+        map.iter()
+            .find(|(k, _)| k.as_text() == Some(key))
+            .map(|(_, v)| v)
+    };
+
+    let format = get("fmt").and_then(Value::as_text).ok_or_else(malformed)?;
+    let statement = get("attStmt")
+        .and_then(Value::as_map)
+        .ok_or_else(malformed)?;
+    if format != "none" || !statement.is_empty() {
+        return Err(WebauthnError::UnsupportedAttestation(format.to_owned()));
+    }
+
+    get("authData")
+        .and_then(Value::as_bytes)
+        .cloned()
+        .ok_or_else(malformed)
+}
+
+// Verification algorithm and raw public key of a COSE encoded credential key.
+fn parse_public_key(
+    cose_key: &[u8],
+) -> Result<(&'static dyn VerificationAlgorithm, Vec<u8>), WebauthnError> {
+    let malformed = || WebauthnError::Malformed("credential public key".to_owned());
+
+    let value: Value = ciborium::from_reader(cose_key).map_err(|_| malformed())?;
+    let map = value.as_map().ok_or_else(malformed)?;
+    let get = |key: i64| {
+        map.iter()
+            .find(|(k, _)| k.as_integer().map(i128::from) == Some(key.into()))
+            .map(|(_, v)| v)
+    };
+    let integer = |key: i64| {
+        get(key)
+            .and_then(Value::as_integer)
+            .map(i128::from)
+            .ok_or_else(malformed)
+    };
+    let coordinate = |key: i64| {
+        get(key)
+            .and_then(Value::as_bytes)
+            .filter(|bytes| bytes.len() == 32)
+            .ok_or_else(malformed)
+    };
+
+    // Key type (1), algorithm (3) and curve (-1): EC2 with P-256, or OKP with Ed25519.
+    match (integer(1)?, integer(3)?, integer(-1)?) {
+        (2, alg, 1) if alg == COSE_ALG_ES256.into() => {
+            let point = [&[0x04][..], coordinate(-2)?, coordinate(-3)?].concat();
+            Ok((&signature::ECDSA_P256_SHA256_ASN1, point))
+        }
+        (1, alg, 6) if alg == COSE_ALG_EDDSA.into() => {
+            Ok((&signature::ED25519, coordinate(-2)?.clone()))
+        }
+        _ => Err(WebauthnError::UnsupportedAlgorithm),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::{
+        rand::SystemRandom,
+        signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING},
+    };
+
+    use super::*;
+
+    const RP_ID: &str = "example.com";
+    const ORIGIN: &str = "https://example.com";
+
+    fn origins() -> Vec<String> {
+        vec![ORIGIN.to_owned()]
+    }
+
+    fn new_key_pair() -> EcdsaKeyPair {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng).unwrap()
+    }
+
+    fn cose_key(key_pair: &EcdsaKeyPair) -> Vec<u8> {
+        let point = key_pair.public_key().as_ref();
+        let key = Value::Map(vec![
+            (1.into(), 2.into()),
+            (3.into(), COSE_ALG_ES256.into()),
+            ((-1).into(), 1.into()),
+            ((-2).into(), Value::Bytes(point[1..33].to_vec())),
+            ((-3).into(), Value::Bytes(point[33..].to_vec())),
+        ]);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&key, &mut bytes).unwrap();
+        bytes
+    }
+
+    fn client_data_json(ceremony_type: &str, origin: &str) -> Vec<u8> {
+        serde_json::json!({
+            "type": ceremony_type,
+            "challenge": generate_challenge(),
+            "origin": origin,
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn authenticator_data(rp_id: &str, flags: u8, sign_count: u32) -> Vec<u8> {
+        [
+            &Sha256::digest(rp_id.as_bytes())[..],
+            &[flags],
+            &sign_count.to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    fn attestation_object(format: &str, cose_key: &[u8]) -> Vec<u8> {
+        let credential_id = [7u8; 16];
+        let auth_data = [
+            &authenticator_data(RP_ID, FLAG_USER_PRESENT | FLAG_ATTESTED_CREDENTIAL_DATA, 0)[..],
+            &[0u8; 16],
+            &(credential_id.len() as u16).to_be_bytes(),
+            &credential_id,
+            cose_key,
+        ]
+        .concat();
+        let object = Value::Map(vec![
+            ("fmt".into(), format.into()),
+            ("attStmt".into(), Value::Map(vec![])),
+            ("authData".into(), Value::Bytes(auth_data)),
+        ]);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&object, &mut bytes).unwrap();
+        bytes
+    }
+
+    // Authenticator data and signature of an assertion over `client_data`.
+    fn sign(
+        key_pair: &EcdsaKeyPair,
+        client_data: &[u8],
+        flags: u8,
+        sign_count: u32,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let auth_data = authenticator_data(RP_ID, flags, sign_count);
+        let signed_data = [&auth_data[..], &Sha256::digest(client_data)[..]].concat();
+        let signature = key_pair.sign(&SystemRandom::new(), &signed_data).unwrap();
+        (auth_data, signature.as_ref().to_vec())
+    }
+
+    #[test]
+    fn test_registration_with_none_attestation() {
+        let origins = origins();
+        let rp = RelyingParty {
+            id: RP_ID,
+            origins: &origins,
+        };
+        let cose_key = cose_key(&new_key_pair());
+        let client_data = ClientData::parse(&client_data_json("webauthn.create", ORIGIN)).unwrap();
+
+        assert_eq!(
+            verify_registration(&rp, &client_data, &attestation_object("none", &cose_key)),
+            Ok(NewCredential {
+                credential_id: vec![7u8; 16],
+                public_key: cose_key.clone(),
+                sign_count: 0,
+            })
+        );
+        assert_eq!(
+            verify_registration(&rp, &client_data, &attestation_object("packed", &cose_key)),
+            Err(WebauthnError::UnsupportedAttestation("packed".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_client_data_must_match_ceremony_and_origin() {
+        let origins = origins();
+        let rp = RelyingParty {
+            id: RP_ID,
+            origins: &origins,
+        };
+        let attestation_object = attestation_object("none", &cose_key(&new_key_pair()));
+
+        let cases = [
+            ("webauthn.get", ORIGIN, WebauthnError::WrongCeremony),
+            (
+                "webauthn.create",
+                "https://evil.example",
+                WebauthnError::UntrustedOrigin("https://evil.example".to_owned()),
+            ),
+        ];
+        for (ceremony_type, origin, expected) in cases {
+            let client_data = ClientData::parse(&client_data_json(ceremony_type, origin)).unwrap();
+            assert_eq!(
+                verify_registration(&rp, &client_data, &attestation_object),
+                Err(expected)
+            );
+        }
+
+        let other_rp = RelyingParty {
+            id: "other.example",
+            origins: &origins,
+        };
+        let client_data = ClientData::parse(&client_data_json("webauthn.create", ORIGIN)).unwrap();
+        assert_eq!(
+            verify_registration(&other_rp, &client_data, &attestation_object),
+            Err(WebauthnError::RpIdMismatch)
+        );
+    }
+
+    #[test]
+    fn test_assertion_signature_is_verified() {
+        let origins = origins();
+        let rp = RelyingParty {
+            id: RP_ID,
+            origins: &origins,
+        };
+        let key_pair = new_key_pair();
+        let raw_client_data = client_data_json("webauthn.get", ORIGIN);
+        let client_data = ClientData::parse(&raw_client_data).unwrap();
+        let (auth_data, signature) = sign(&key_pair, &raw_client_data, FLAG_USER_PRESENT, 5);
+
+        assert_eq!(
+            verify_assertion(
+                &rp,
+                &client_data,
+                &auth_data,
+                &signature,
+                &cose_key(&key_pair),
+                4,
+                false
+            ),
+            Ok(5)
+        );
+        assert_eq!(
+            verify_assertion(
+                &rp,
+                &client_data,
+                &auth_data,
+                &signature,
+                &cose_key(&new_key_pair()),
+                4,
+                false
+            ),
+            Err(WebauthnError::InvalidSignature)
+        );
+
+        // The signature covers the client data, so a different challenge invalidates it.
+        let other_client_data =
+            ClientData::parse(&client_data_json("webauthn.get", ORIGIN)).unwrap();
+        assert_eq!(
+            verify_assertion(
+                &rp,
+                &other_client_data,
+                &auth_data,
+                &signature,
+                &cose_key(&key_pair),
+                4,
+                false
+            ),
+            Err(WebauthnError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_user_verification_is_enforced_when_required() {
+        let origins = origins();
+        let rp = RelyingParty {
+            id: RP_ID,
+            origins: &origins,
+        };
+        let key_pair = new_key_pair();
+        let raw_client_data = client_data_json("webauthn.get", ORIGIN);
+        let client_data = ClientData::parse(&raw_client_data).unwrap();
+        let (auth_data, signature) = sign(&key_pair, &raw_client_data, FLAG_USER_PRESENT, 0);
+
+        assert_eq!(
+            verify_assertion(
+                &rp,
+                &client_data,
+                &auth_data,
+                &signature,
+                &cose_key(&key_pair),
+                0,
+                true
+            ),
+            Err(WebauthnError::UserNotVerified)
+        );
+
+        let (auth_data, signature) = sign(
+            &key_pair,
+            &raw_client_data,
+            FLAG_USER_PRESENT | FLAG_USER_VERIFIED,
+            0,
+        );
+        assert_eq!(
+            verify_assertion(
+                &rp,
+                &client_data,
+                &auth_data,
+                &signature,
+                &cose_key(&key_pair),
+                0,
+                true
+            ),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn test_sign_count_must_increase_once_used() {
+        assert_eq!(check_sign_count(0, 0), Ok(()));
+        assert_eq!(check_sign_count(0, 1), Ok(()));
+        assert_eq!(check_sign_count(5, 6), Ok(()));
+        assert_eq!(
+            check_sign_count(5, 5),
+            Err(WebauthnError::SignCountNotIncreased)
+        );
+        assert_eq!(
+            check_sign_count(5, 0),
+            Err(WebauthnError::SignCountNotIncreased)
+        );
+    }
+}
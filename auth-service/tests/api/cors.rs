@@ -0,0 +1,36 @@
+use crate::helpers::{TestApp, TEST_ALLOWED_ORIGIN};
+
+#[tokio::test]
+async fn should_allow_configured_origin() {
+    let app = TestApp::new().await;
+
+    let response = app.preflight("/login", TEST_ALLOWED_ORIGIN).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .unwrap(),
+        TEST_ALLOWED_ORIGIN
+    );
+    assert_eq!(
+        response
+            .headers()
+            .get("access-control-allow-credentials")
+            .unwrap(),
+        "true"
+    );
+}
+
+#[tokio::test]
+async fn should_not_allow_unknown_origin() {
+    let app = TestApp::new().await;
+
+    let response = app.preflight("/login", "http://evil.example.com").await;
+
+    assert!(response
+        .headers()
+        .get("access-control-allow-origin")
+        .is_none());
+}
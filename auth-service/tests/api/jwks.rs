@@ -0,0 +1,113 @@
+use auth_service::utils::{
+    auth::{generate_signing_key, reload_jwt_keys},
+    constants::JWT_COOKIE_NAME,
+};
+use jsonwebtoken::{decode_header, jwk::JwkSet};
+
+use crate::helpers::{cookie_value, TestApp};
+
+fn kid(token: &str) -> String {
+    decode_header(token)
+        .expect("Failed to decode header")
+        .kid
+        .expect("Token has no kid")
+}
+
+async fn get_kids(app: &TestApp) -> Vec<String> {
+    let response = app.get_jwks().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let jwks: JwkSet = response.json().await.expect("Failed to parse JWKS");
+    jwks.keys
+        .into_iter()
+        .map(|jwk| jwk.common.key_id.expect("Key has no kid"))
+        .collect()
+}
+
+async fn reload(app: &TestApp) {
+    reload_jwt_keys(&app.signing_key_store, &app.jwt_keys)
+        .await
+        .expect("Failed to reload keys");
+}
+
+async fn verify_token_status(app: &TestApp, token: &str) -> u16 {
+    app.post_verify_token(&serde_json::json!({ "token": token }))
+        .await
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn should_publish_the_signing_key() {
+    let app = TestApp::new().await;
+
+    let token = cookie_value(&app.login_new_user().await, JWT_COOKIE_NAME);
+
+    let response = app.get_jwks().await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.headers()["cache-control"]
+        .to_str()
+        .unwrap()
+        .starts_with("public, max-age="));
+
+    let jwks: JwkSet = response.json().await.expect("Failed to parse JWKS");
+    let jwk = jwks.find(&kid(&token)).expect("Signing key not published");
+    let decoding_key =
+        jsonwebtoken::DecodingKey::from_jwk(jwk).expect("Failed to read published key");
+    let mut validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::EdDSA);
+    validation.set_required_spec_claims(&["sub", "exp"]);
+    assert!(jsonwebtoken::decode::<serde_json::Value>(&token, &decoding_key, &validation).is_ok());
+}
+
+#[tokio::test]
+async fn should_rotate_keys_without_invalidating_tokens() {
+    let app = TestApp::new().await;
+
+    let old_token = cookie_value(&app.login_new_user().await, JWT_COOKIE_NAME);
+    let old_kid = kid(&old_token);
+
+    // Generate: the new key is published but doesn't sign yet.
+    let new_key = generate_signing_key("EdDSA").expect("Failed to generate key");
+    let new_kid = new_key.kid.clone();
+    app.signing_key_store
+        .write()
+        .await
+        .add_key(new_key)
+        .await
+        .expect("Failed to add key");
+    reload(&app).await;
+
+    let mut kids = get_kids(&app).await;
+    kids.sort();
+    let mut expected = vec![old_kid.clone(), new_kid.clone()];
+    expected.sort();
+    assert_eq!(kids, expected);
+    let token = cookie_value(&app.login_new_user().await, JWT_COOKIE_NAME);
+    assert_eq!(kid(&token), old_kid);
+
+    // Promote: new tokens are signed with the new key, old tokens stay valid.
+    app.signing_key_store
+        .write()
+        .await
+        .promote_key(&new_kid)
+        .await
+        .expect("Failed to promote key");
+    reload(&app).await;
+
+    let new_token = cookie_value(&app.login_new_user().await, JWT_COOKIE_NAME);
+    assert_eq!(kid(&new_token), new_kid);
+    assert_eq!(verify_token_status(&app, &old_token).await, 200);
+
+    // Retire: tokens signed with the old key are no longer accepted.
+    app.signing_key_store
+        .write()
+        .await
+        .retire_key(&old_kid)
+        .await
+        .expect("Failed to retire key");
+    reload(&app).await;
+
+    assert_eq!(get_kids(&app).await, vec![new_kid]);
+    assert_eq!(verify_token_status(&app, &old_token).await, 401);
+    assert_eq!(verify_token_status(&app, &new_token).await, 200);
+}
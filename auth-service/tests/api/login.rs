@@ -1,13 +1,211 @@
-use crate::helpers::TestApp;
+use auth_service::{
+    domain::{Email, TwoFAMethod},
+    routes::TwoFactorAuthResponse,
+    utils::constants::JWT_COOKIE_NAME,
+};
+
+use crate::helpers::{get_random_email, TestApp};
 
 #[tokio::test]
-async fn login_returns_200() {
+async fn should_return_422_if_malformed_credentials() {
     let app = TestApp::new().await;
-    let body = serde_json::json!({
-        "email": "test@email.com",
-        "password": "password"
+
+    let test_cases = [
+        serde_json::json!({
+            "password": "password123"
+        }),
+        serde_json::json!({
+            "email": get_random_email()
+        }),
+        serde_json::json!({
+            "email": 12345,
+            "password": "password123"
+        }),
+        serde_json::json!({
+            "email": get_random_email(),
+            "password": null
+        }),
+        serde_json::json!({}),
+    ];
+
+    for test_case in test_cases.iter() {
+        let response = app.post_login(test_case).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            422,
+            "Failed for input: {:?}",
+            test_case
+        );
+    }
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_input() {
+    let app = TestApp::new().await;
+
+    let test_cases = [
+        (
+            serde_json::json!({
+                "email": "",
+                "password": "password123"
+            }),
+            "Email cannot be empty",
+        ),
+        (
+            serde_json::json!({
+                "email": get_random_email(),
+                "password": ""
+            }),
+            "Password cannot be empty",
+        ),
+    ];
+
+    for (test_case, expected_error) in test_cases.iter() {
+        let response = app.post_login(test_case).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "Failed for input: {:?}",
+            test_case
+        );
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .expect("Failed to parse response body");
+
+        assert_eq!(json["error"], *expected_error);
+    }
+}
+
+#[tokio::test]
+async fn should_return_401_if_incorrect_credentials() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": false
     });
-    let response = app.post_login(&body).await;
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let test_cases = [
+        // Wrong password
+        serde_json::json!({
+            "email": random_email,
+            "password": "wrong-password"
+        }),
+        // Unknown user
+        serde_json::json!({
+            "email": get_random_email(),
+            "password": "password123"
+        }),
+    ];
+
+    for test_case in test_cases.iter() {
+        let response = app.post_login(test_case).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            401,
+            "Failed for input: {:?}",
+            test_case
+        );
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .expect("Failed to parse response body");
+
+        assert_eq!(json["error"], "Incorrect credentials");
+    }
+}
 
+#[tokio::test]
+async fn should_return_200_if_valid_credentials_and_2fa_disabled() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
     assert_eq!(response.status().as_u16(), 200);
+
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+
+    assert!(!auth_cookie.value().is_empty());
+    assert!(auth_cookie.http_only());
+    assert!(auth_cookie.secure());
+}
+
+#[tokio::test]
+async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": true
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    assert!(response
+        .cookies()
+        .all(|cookie| cookie.name() != JWT_COOKIE_NAME));
+
+    let json_body = response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse");
+
+    assert_eq!(json_body.message, "2FA required".to_owned());
+    assert_eq!(json_body.two_fa_method, TwoFAMethod::Email);
+
+    let (login_attempt_id, code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(random_email.clone()).unwrap())
+        .await
+        .expect("No 2FA code stored for login attempt");
+
+    assert_eq!(login_attempt_id.as_ref(), json_body.login_attempt_id);
+
+    let email = app
+        .email_client
+        .last_email_to(&random_email)
+        .expect("No 2FA email sent");
+    assert!(email.content.contains(code.as_ref()));
 }
@@ -1,9 +1,99 @@
-use crate::helpers::TestApp;
+use auth_service::utils::{auth::decode_token, constants::JWT_COOKIE_NAME};
+use reqwest::Url;
+
+use crate::helpers::{cookie_value, TestApp};
 
 #[tokio::test]
-async fn logout_returns_200() {
+async fn should_return_400_if_jwt_cookie_missing() {
     let app = TestApp::new().await;
+
     let response = app.post_logout().await;
 
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let app = TestApp::new().await;
+
+    // add invalid cookie
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}=invalid; HttpOnly; SameSite=Lax; Secure; Path=/",
+            JWT_COOKIE_NAME
+        ),
+        &Url::parse(&app.address).expect("Failed to parse URL"),
+    );
+
+    let response = app.post_logout().await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_200_if_valid_jwt_cookie() {
+    let app = TestApp::new().await;
+
+    let token = cookie_value(&app.login_new_user().await, JWT_COOKIE_NAME);
+
+    let response = app.post_logout().await;
     assert_eq!(response.status().as_u16(), 200);
+
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+    assert!(auth_cookie.value().is_empty());
+
+    // The token must be rejected from now on, even though it hasn't expired.
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let jti = decode_token(&token, &*app.jwt_keys.read().await)
+        .expect("Failed to decode token")
+        .jti;
+    assert!(app
+        .banned_token_store
+        .read()
+        .await
+        .contains_token(&jti)
+        .await
+        .expect("Failed to check banned token store"));
+}
+
+#[tokio::test]
+async fn should_return_400_if_logout_called_twice_in_a_row() {
+    let app = TestApp::new().await;
+
+    app.login_new_user().await;
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The browser dropped the cookie, so the second call has no token at all.
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_banned_token_is_reused() {
+    let app = TestApp::new().await;
+
+    let token = cookie_value(&app.login_new_user().await, JWT_COOKIE_NAME);
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Secure; Path=/",
+            JWT_COOKIE_NAME, token
+        ),
+        &Url::parse(&app.address).expect("Failed to parse URL"),
+    );
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 401);
 }
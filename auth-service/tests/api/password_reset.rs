@@ -0,0 +1,241 @@
+use auth_service::{
+    utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME},
+    ErrorResponse,
+};
+
+use crate::helpers::{get_random_email, TestApp, TEST_COMMON_PASSWORDS};
+
+const NEW_PASSWORD: &str = "correct-horse-battery";
+
+// Sign up a user without 2FA and request a reset link for them, returning their
+// email and the token from the link.
+async fn signup_and_request_reset(app: &TestApp) -> (String, String) {
+    let email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&signup_body).await.status().as_u16(), 201);
+    app.verify_email(&email).await;
+
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let token = app.password_reset_token(&email);
+    (email, token)
+}
+
+async fn confirm(app: &TestApp, token: &str, new_password: &str) -> reqwest::Response {
+    let body = serde_json::json!({ "token": token, "newPassword": new_password });
+    app.post_password_reset_confirm(&body).await
+}
+
+async fn login_status(app: &TestApp, email: &str, password: &str) -> u16 {
+    let body = serde_json::json!({ "email": email, "password": password });
+    app.post_login(&body).await.status().as_u16()
+}
+
+#[tokio::test]
+async fn request_should_email_a_reset_link() {
+    let app = TestApp::new().await;
+    let (email, _) = signup_and_request_reset(&app).await;
+
+    let sent = app
+        .email_client
+        .last_email_to(&email)
+        .expect("No reset email sent");
+
+    assert_eq!(sent.subject, "Reset your password");
+    assert!(sent
+        .content
+        .contains("http://localhost:3000/reset-password.html?token="));
+}
+
+#[tokio::test]
+async fn request_should_not_reveal_unknown_accounts() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": get_random_email() }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(app.email_client.sent_emails().is_empty());
+}
+
+#[tokio::test]
+async fn request_should_return_400_or_422_for_invalid_input() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": "not-an-email" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app
+        .post_password_reset_request(&serde_json::json!({}))
+        .await;
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn confirm_should_change_the_password_and_end_every_session() {
+    let app = TestApp::new().await;
+    let (email, token) = signup_and_request_reset(&app).await;
+
+    let body = serde_json::json!({ "email": email, "password": "password123" });
+    let response = app.post_login(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let auth_token = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+    assert!(response
+        .cookies()
+        .any(|cookie| cookie.name() == REFRESH_TOKEN_COOKIE_NAME));
+
+    let response = confirm(&app, &token, NEW_PASSWORD).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": auth_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(app.post_refresh().await.status().as_u16(), 401);
+
+    let sent = app
+        .email_client
+        .last_email_to(&email)
+        .expect("No notification sent");
+    assert_eq!(sent.subject, "Your password was changed");
+
+    assert_eq!(login_status(&app, &email, "password123").await, 401);
+
+    // A session started right after the reset, in the same second, is valid.
+    let body = serde_json::json!({ "email": email, "password": NEW_PASSWORD });
+    let response = app.post_login(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let auth_token = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": auth_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn confirm_should_accept_a_token_once() {
+    let app = TestApp::new().await;
+    let (email, token) = signup_and_request_reset(&app).await;
+
+    assert_eq!(
+        confirm(&app, &token, NEW_PASSWORD).await.status().as_u16(),
+        200
+    );
+
+    let response = confirm(&app, &token, "another-new-password").await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "Invalid or expired reset token"
+    );
+    assert_eq!(login_status(&app, &email, NEW_PASSWORD).await, 200);
+}
+
+#[tokio::test]
+async fn confirm_should_invalidate_earlier_reset_tokens() {
+    let app = TestApp::new().await;
+    let (email, earlier_token) = signup_and_request_reset(&app).await;
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = app.password_reset_token(&email);
+    assert_ne!(token, earlier_token);
+
+    assert_eq!(
+        confirm(&app, &token, NEW_PASSWORD).await.status().as_u16(),
+        200
+    );
+
+    let response = confirm(&app, &earlier_token, "another-new-password").await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "Invalid or expired reset token"
+    );
+    assert_eq!(login_status(&app, &email, NEW_PASSWORD).await, 200);
+}
+
+#[tokio::test]
+async fn confirm_should_enforce_the_password_policy_and_keep_the_token() {
+    let app = TestApp::new().await;
+    let (email, token) = signup_and_request_reset(&app).await;
+
+    let response = confirm(&app, &token, TEST_COMMON_PASSWORDS[0]).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["error"], "Password does not meet the password policy");
+    assert_eq!(json["codes"], serde_json::json!(["password_too_common"]));
+    assert_eq!(login_status(&app, &email, "password123").await, 200);
+
+    assert_eq!(
+        confirm(&app, &token, NEW_PASSWORD).await.status().as_u16(),
+        200
+    );
+}
+
+#[tokio::test]
+async fn confirm_should_return_400_for_unknown_token() {
+    let app = TestApp::new().await;
+    let (_, token) = signup_and_request_reset(&app).await;
+
+    for token in ["", "unknown", &token[1..]] {
+        let response = confirm(&app, token, NEW_PASSWORD).await;
+        assert_eq!(response.status().as_u16(), 400, "Failed for {:?}", token);
+        assert_eq!(
+            response.json::<ErrorResponse>().await.unwrap().error,
+            "Invalid or expired reset token"
+        );
+    }
+
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({ "token": token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 422);
+}
+
+#[tokio::test]
+async fn confirm_should_verify_the_email_address() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&signup_body).await.status().as_u16(), 201);
+    assert_eq!(login_status(&app, &email, "password123").await, 403);
+
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = app.password_reset_token(&email);
+    assert_eq!(
+        confirm(&app, &token, NEW_PASSWORD).await.status().as_u16(),
+        200
+    );
+
+    assert_eq!(login_status(&app, &email, NEW_PASSWORD).await, 200);
+}
@@ -0,0 +1,230 @@
+use auth_service::{
+    routes::{
+        ConfirmTotpResponse, RecoveryCodeCountResponse, RecoveryCodesResponse,
+        TotpEnrollmentResponse,
+    },
+    utils::{
+        constants::{JWT_COOKIE_NAME, MAX_TWO_FA_ATTEMPTS},
+        time::unix_now,
+        totp::totp_code,
+    },
+    ErrorResponse,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn verify_with_recovery_code(
+    app: &TestApp,
+    email: &str,
+    recovery_code: &str,
+) -> reqwest::Response {
+    let (login_attempt_id, _) = app.start_2fa_login(email).await;
+
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "recoveryCode": recovery_code,
+    });
+    app.post_verify_2fa(&body).await
+}
+
+async fn remaining_codes(app: &TestApp) -> usize {
+    let response = app.get_recovery_codes().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<RecoveryCodeCountResponse>()
+        .await
+        .expect("Could not deserialize response body to RecoveryCodeCountResponse")
+        .remaining
+}
+
+#[tokio::test]
+async fn signup_should_only_return_recovery_codes_with_2fa() {
+    let app = TestApp::new().await;
+
+    let (_, recovery_codes) = app.signup_user(true).await;
+    assert_eq!(recovery_codes.len(), 10);
+
+    let signup_body = serde_json::json!({
+        "email": get_random_email(),
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    let json = response.json::<serde_json::Value>().await.unwrap();
+    assert!(json.get("recoveryCodes").is_none());
+}
+
+#[tokio::test]
+async fn verify_2fa_should_accept_each_recovery_code_once() {
+    let app = TestApp::new().await;
+    let (email, recovery_codes) = app.signup_user(true).await;
+
+    let response = verify_with_recovery_code(&app, &email, &recovery_codes[0]).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .cookies()
+        .any(|cookie| cookie.name() == JWT_COOKIE_NAME && !cookie.value().is_empty()));
+    assert_eq!(remaining_codes(&app).await, 9);
+
+    let response = verify_with_recovery_code(&app, &email, &recovery_codes[0]).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // Codes are accepted however they were typed in.
+    let retyped = recovery_codes[1].replace('-', " ").to_lowercase();
+    let response = verify_with_recovery_code(&app, &email, &retyped).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(remaining_codes(&app).await, 8);
+}
+
+#[tokio::test]
+async fn verify_2fa_should_not_accept_another_users_recovery_code() {
+    let app = TestApp::new().await;
+    let (email, _) = app.signup_user(true).await;
+    let (_, other_codes) = app.signup_user(true).await;
+
+    let response = verify_with_recovery_code(&app, &email, &other_codes[0]).await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn verify_2fa_should_count_wrong_recovery_codes_against_the_login_attempt() {
+    let app = TestApp::new().await;
+    let (email, recovery_codes) = app.signup_user(true).await;
+    let (_, other_codes) = app.signup_user(true).await;
+    let (login_attempt_id, _) = app.start_2fa_login(&email).await;
+
+    for other_code in &other_codes[..MAX_TWO_FA_ATTEMPTS as usize] {
+        let body = serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id,
+            "recoveryCode": other_code,
+        });
+        assert_eq!(app.post_verify_2fa(&body).await.status().as_u16(), 401);
+    }
+
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "recoveryCode": recovery_codes[0],
+    });
+    assert_eq!(app.post_verify_2fa(&body).await.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn verify_2fa_should_return_400_for_malformed_recovery_code() {
+    let app = TestApp::new().await;
+    let (email, _) = app.signup_user(true).await;
+
+    let response = verify_with_recovery_code(&app, &email, "1234-5678").await;
+
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "Invalid recovery code"
+    );
+}
+
+#[tokio::test]
+async fn regenerate_should_invalidate_the_old_codes() {
+    let app = TestApp::new().await;
+    let (email, old_codes) = app.signup_user(true).await;
+    let response = verify_with_recovery_code(&app, &email, &old_codes[0]).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_recovery_codes_regenerate().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let new_codes = response
+        .json::<RecoveryCodesResponse>()
+        .await
+        .unwrap()
+        .recovery_codes;
+    assert_eq!(new_codes.len(), 10);
+    assert!(new_codes.iter().all(|code| !old_codes.contains(code)));
+    assert_eq!(remaining_codes(&app).await, 10);
+
+    let response = verify_with_recovery_code(&app, &email, &old_codes[1]).await;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = verify_with_recovery_code(&app, &email, &new_codes[0]).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn recovery_code_endpoints_should_return_400_if_not_logged_in() {
+    let app = TestApp::new().await;
+
+    for response in [
+        app.get_recovery_codes().await,
+        app.post_recovery_codes_regenerate().await,
+    ] {
+        assert_eq!(response.status().as_u16(), 400);
+        assert_eq!(
+            response.json::<ErrorResponse>().await.unwrap().error,
+            "Missing auth token"
+        );
+    }
+}
+
+#[tokio::test]
+async fn regenerate_should_return_400_without_2fa() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+    app.verify_email(&email).await;
+    let body = serde_json::json!({ "email": email, "password": "password123" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 200);
+
+    let response = app.post_recovery_codes_regenerate().await;
+
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "Two-factor authentication is not enabled"
+    );
+    assert_eq!(remaining_codes(&app).await, 0);
+}
+
+#[tokio::test]
+async fn confirming_totp_should_issue_codes_only_if_none_are_left() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+    app.verify_email(&email).await;
+    let body = serde_json::json!({ "email": email, "password": "password123" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 200);
+
+    for expected_codes in [Some(10), None] {
+        let enrollment = app
+            .post_totp_enroll()
+            .await
+            .json::<TotpEnrollmentResponse>()
+            .await
+            .unwrap();
+        let body = serde_json::json!({
+            "2FACode": totp_code(&enrollment.secret, unix_now()).unwrap(),
+        });
+        let response = app.post_totp_confirm(&body).await;
+        assert_eq!(response.status().as_u16(), 200);
+
+        let recovery_codes = response
+            .json::<ConfirmTotpResponse>()
+            .await
+            .unwrap()
+            .recovery_codes;
+        assert_eq!(recovery_codes.map(|codes| codes.len()), expected_codes);
+    }
+    assert_eq!(remaining_codes(&app).await, 10);
+}
@@ -0,0 +1,102 @@
+use auth_service::utils::constants::{JWT_COOKIE_NAME, REFRESH_TOKEN_COOKIE_NAME};
+use reqwest::Url;
+
+use crate::helpers::{cookie_value, TestApp};
+
+// Put a refresh token back into the client's jar, as a stolen copy would be replayed.
+fn set_refresh_cookie(app: &TestApp, token: &str) {
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Strict; Secure; Path=/",
+            REFRESH_TOKEN_COOKIE_NAME, token
+        ),
+        &Url::parse(&app.address).expect("Failed to parse URL"),
+    );
+}
+
+#[tokio::test]
+async fn login_should_set_refresh_cookie() {
+    let app = TestApp::new().await;
+
+    let token = cookie_value(&app.login_new_user().await, REFRESH_TOKEN_COOKIE_NAME);
+
+    assert!(!token.is_empty());
+}
+
+#[tokio::test]
+async fn should_return_400_if_refresh_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.post_refresh().await;
+
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_refresh_token() {
+    let app = TestApp::new().await;
+
+    set_refresh_cookie(&app, "invalid");
+
+    let response = app.post_refresh().await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_200_and_rotate_tokens() {
+    let app = TestApp::new().await;
+
+    let first_token = cookie_value(&app.login_new_user().await, REFRESH_TOKEN_COOKIE_NAME);
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let auth_token = cookie_value(&response, JWT_COOKIE_NAME);
+    let second_token = cookie_value(&response, REFRESH_TOKEN_COOKIE_NAME);
+    assert_ne!(first_token, second_token);
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": auth_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The rotated token is in the jar and can be used for the next refresh.
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn should_revoke_session_if_refresh_token_is_reused() {
+    let app = TestApp::new().await;
+
+    let first_token = cookie_value(&app.login_new_user().await, REFRESH_TOKEN_COOKIE_NAME);
+
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let second_token = cookie_value(&response, REFRESH_TOKEN_COOKIE_NAME);
+
+    set_refresh_cookie(&app, &first_token);
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // The replay ended the session, so the newest token is dead as well.
+    set_refresh_cookie(&app, &second_token);
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_after_logout() {
+    let app = TestApp::new().await;
+
+    let token = cookie_value(&app.login_new_user().await, REFRESH_TOKEN_COOKIE_NAME);
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(cookie_value(&response, REFRESH_TOKEN_COOKIE_NAME).is_empty());
+
+    set_refresh_cookie(&app, &token);
+    let response = app.post_refresh().await;
+    assert_eq!(response.status().as_u16(), 401);
+}
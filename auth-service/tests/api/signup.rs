@@ -1,4 +1,4 @@
-use crate::helpers::{get_random_email, TestApp};
+use crate::helpers::{get_random_email, TestApp, TEST_COMMON_PASSWORDS, TEST_DENIED_DOMAIN};
 
 #[tokio::test]
 async fn should_return_201_if_valid_input() {
@@ -20,6 +20,36 @@ async fn should_return_201_if_valid_input() {
             "password": "P@ssw0rd!",
             "requires2FA": true
         }),
+        serde_json::json!({
+            "email": "user@domain.org",
+            "password": "password123",
+            "requires2FA": false
+        }),
+        serde_json::json!({
+            "email": "test@example.net",
+            "password": "password123",
+            "requires2FA": false
+        }),
+        serde_json::json!({
+            "email": "colleague@startup.io",
+            "password": "password123",
+            "requires2FA": false
+        }),
+        serde_json::json!({
+            "email": "kollege@firma.de",
+            "password": "password123",
+            "requires2FA": false
+        }),
+        serde_json::json!({
+            "email": "student@university.edu",
+            "password": "password123",
+            "requires2FA": false
+        }),
+        serde_json::json!({
+            "email": "jörg@bücher.de",
+            "password": "password123",
+            "requires2FA": false
+        }),
     ];
 
     for test_case in test_cases.iter() {
@@ -123,33 +153,42 @@ async fn should_return_400_if_invalid_input() {
             }),
             "Password cannot be empty"
         ),
-        // Invalid email - doesn't match .com or .com.XX pattern
+        // Invalid email - consecutive dots in local part
         (
             serde_json::json!({
-                "email": "qSdy6asQjL8siKA@m.fegl",
+                "email": "test..user@example.com",
                 "password": "password123",
                 "requires2FA": true
             }),
             "Invalid email format"
         ),
-        // Invalid email - .net domain
+        // Invalid email - numeric top-level domain
         (
             serde_json::json!({
-                "email": "test@example.net",
+                "email": "test@127.0.0.1",
                 "password": "password123",
                 "requires2FA": true
             }),
             "Invalid email format"
         ),
-        // Invalid email - .org domain
+        // Invalid email - domain label starting with a hyphen
         (
             serde_json::json!({
-                "email": "user@domain.org",
+                "email": "test@-example.org",
                 "password": "password123",
                 "requires2FA": true
             }),
             "Invalid email format"
         ),
+        // Valid email - domain on the deny list
+        (
+            serde_json::json!({
+                "email": format!("test@{}", TEST_DENIED_DOMAIN),
+                "password": "password123",
+                "requires2FA": true
+            }),
+            "Email domain is not permitted"
+        ),
     ];
 
     for (test_case, expected_error) in test_cases.iter() {
@@ -332,5 +371,164 @@ async fn should_return_422_if_malformed_input() {
             "Failed for input: {:?}",
             test_case
         );
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .expect("Failed to parse response body");
+
+        assert!(
+            json["error"].is_string(),
+            "Missing error message for input: {:?}",
+            test_case
+        );
+    }
+}
+
+#[tokio::test]
+async fn should_return_422_with_json_error_if_body_is_not_valid_json() {
+    let app = TestApp::new().await;
+
+    let test_cases = ["", "{", "not json", r#"{"email": "a@example.com",}"#];
+
+    for test_case in test_cases {
+        let response = app.post_raw("/signup", test_case).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            422,
+            "Failed for input: {:?}",
+            test_case
+        );
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .expect("Failed to parse response body");
+
+        assert!(
+            json["error"].is_string(),
+            "Missing error message for input: {:?}",
+            test_case
+        );
+    }
+}
+
+#[tokio::test]
+async fn should_return_409_if_email_already_exists() {
+    let app = TestApp::new().await;
+
+    let body = serde_json::json!({
+        "email": get_random_email(),
+        "password": "password123",
+        "requires2FA": true
+    });
+
+    let response = app.post_signup(&body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = app.post_signup(&body).await;
+    assert_eq!(response.status().as_u16(), 409);
+
+    let json: serde_json::Value = response
+        .json()
+        .await
+        .expect("Failed to parse response body");
+
+    assert_eq!(json["error"], "User already exists");
+}
+
+#[tokio::test]
+async fn should_return_409_if_email_differs_only_in_domain_case() {
+    let app = TestApp::new().await;
+
+    let body = serde_json::json!({
+        "email": "user@Example.org",
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let test_cases = [
+        serde_json::json!({
+            "email": "user@EXAMPLE.ORG",
+            "password": "password123",
+            "requires2FA": false
+        }),
+        serde_json::json!({
+            "email": "user@example.org",
+            "password": "password123",
+            "requires2FA": false
+        }),
+    ];
+
+    for test_case in test_cases.iter() {
+        let response = app.post_signup(test_case).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            409,
+            "Failed for input: {:?}",
+            test_case
+        );
+    }
+}
+
+#[tokio::test]
+async fn should_return_400_with_codes_if_password_violates_policy() {
+    let app = TestApp::new().await;
+
+    let test_cases = [
+        ("short", "alice@example.com", vec!["password_too_short"]),
+        (
+            &*"a".repeat(129),
+            "alice@example.com",
+            vec!["password_too_long"],
+        ),
+        (
+            TEST_COMMON_PASSWORDS[0],
+            "alice@example.com",
+            vec!["password_too_common"],
+        ),
+        (
+            "alice-rocks-2024",
+            "alice@example.com",
+            vec!["password_contains_email"],
+        ),
+        (
+            "Alice",
+            "alice@example.com",
+            vec!["password_too_short", "password_contains_email"],
+        ),
+    ];
+
+    for (password, email, expected_codes) in test_cases.iter() {
+        let body = serde_json::json!({
+            "email": email,
+            "password": password,
+            "requires2FA": false
+        });
+        let response = app.post_signup(&body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "Failed for password: {:?}",
+            password
+        );
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .expect("Failed to parse response body");
+
+        assert_eq!(json["error"], "Password does not meet the password policy");
+        assert_eq!(
+            json["codes"],
+            serde_json::json!(expected_codes),
+            "Codes mismatch for password: {:?}",
+            password
+        );
     }
 }
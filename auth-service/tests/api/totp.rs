@@ -0,0 +1,208 @@
+use auth_service::{
+    domain::TwoFAMethod,
+    routes::{TotpEnrollmentResponse, TwoFactorAuthResponse},
+    utils::{constants::JWT_COOKIE_NAME, time::unix_now, totp::totp_code},
+    ErrorResponse,
+};
+
+use crate::helpers::TestApp;
+
+async fn enroll(app: &TestApp) -> TotpEnrollmentResponse {
+    let response = app.post_totp_enroll().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<TotpEnrollmentResponse>()
+        .await
+        .expect("Could not deserialize response body to TotpEnrollmentResponse")
+}
+
+// Enroll and confirm an authenticator app, returning its secret. The confirmation
+// uses the previous step's code, so the current one is still unused for logging in.
+async fn enable_totp(app: &TestApp) -> String {
+    let enrollment = enroll(app).await;
+
+    let body = serde_json::json!({
+        "2FACode": totp_code(&enrollment.secret, unix_now() - 30).unwrap(),
+    });
+    assert_eq!(app.post_totp_confirm(&body).await.status().as_u16(), 200);
+
+    enrollment.secret
+}
+
+async fn start_totp_login(app: &TestApp, email: &str) -> String {
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let body = response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse");
+    assert_eq!(body.two_fa_method, TwoFAMethod::Totp);
+
+    body.login_attempt_id
+}
+
+#[tokio::test]
+async fn enroll_should_return_400_if_not_logged_in() {
+    let app = TestApp::new().await;
+
+    let response = app.post_totp_enroll().await;
+
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "Missing auth token"
+    );
+}
+
+#[tokio::test]
+async fn enroll_should_return_secret_uri_and_qr_codes() {
+    let app = TestApp::new().await;
+    let email = app.signup_and_login(false).await;
+
+    let enrollment = enroll(&app).await;
+
+    assert!(enrollment.otpauth_uri.starts_with("otpauth://totp/"));
+    assert!(enrollment
+        .otpauth_uri
+        .contains(&format!("secret={}", enrollment.secret)));
+    assert!(enrollment.otpauth_uri.contains(&email.replace('@', "%40")));
+    assert!(!enrollment.qr_code_png.is_empty());
+    assert!(enrollment.qr_code_svg.contains("<svg"));
+}
+
+#[tokio::test]
+async fn confirm_should_reject_wrong_code_and_missing_enrollment() {
+    let app = TestApp::new().await;
+    app.signup_and_login(false).await;
+
+    let body = serde_json::json!({ "2FACode": "123456" });
+    let response = app.post_totp_confirm(&body).await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "No pending TOTP enrollment"
+    );
+
+    let enrollment = enroll(&app).await;
+    let wrong_code = totp_code(&enrollment.secret, unix_now() - 600).unwrap();
+    let body = serde_json::json!({ "2FACode": wrong_code });
+    assert_eq!(app.post_totp_confirm(&body).await.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn login_should_require_totp_code_once_confirmed() {
+    let app = TestApp::new().await;
+    let email = app.signup_and_login(false).await;
+    let secret = enable_totp(&app).await;
+
+    let login_attempt_id = start_totp_login(&app, &email).await;
+
+    // No code is emailed to users of an authenticator app.
+    assert!(app
+        .email_client
+        .sent_emails()
+        .iter()
+        .all(|sent| sent.subject != "2FA Code"));
+
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": totp_code(&secret, unix_now()).unwrap(),
+    });
+    let response = app.post_verify_2fa(&body).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .cookies()
+        .any(|cookie| cookie.name() == JWT_COOKIE_NAME && !cookie.value().is_empty()));
+}
+
+#[tokio::test]
+async fn verify_2fa_should_accept_codes_within_drift_window_only() {
+    let app = TestApp::new().await;
+    let email = app.signup_and_login(false).await;
+    let secret = enable_totp(&app).await;
+
+    for (offset, expected_status) in [(-300, 401), (300, 401), (30, 200)] {
+        let login_attempt_id = start_totp_login(&app, &email).await;
+        let time = (unix_now() as i64 + offset) as u64;
+
+        let body = serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id,
+            "2FACode": totp_code(&secret, time).unwrap(),
+        });
+
+        assert_eq!(
+            app.post_verify_2fa(&body).await.status().as_u16(),
+            expected_status,
+            "offset {}s",
+            offset
+        );
+    }
+}
+
+#[tokio::test]
+async fn verify_2fa_should_reject_the_same_code_twice() {
+    let app = TestApp::new().await;
+    let email = app.signup_and_login(false).await;
+    let secret = enable_totp(&app).await;
+    let code = totp_code(&secret, unix_now() + 30).unwrap();
+
+    for expected_status in [200, 401] {
+        let login_attempt_id = start_totp_login(&app, &email).await;
+        let body = serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id,
+            "2FACode": code,
+        });
+
+        assert_eq!(
+            app.post_verify_2fa(&body).await.status().as_u16(),
+            expected_status
+        );
+    }
+}
+
+#[tokio::test]
+async fn two_fa_method_can_switch_back_to_email() {
+    let app = TestApp::new().await;
+    let email = app.signup_and_login(false).await;
+
+    // TOTP can't be chosen before an app is confirmed.
+    let body = serde_json::json!({ "twoFAMethod": "totp" });
+    assert_eq!(app.post_two_fa_method(&body).await.status().as_u16(), 400);
+
+    let body = serde_json::json!({ "twoFAMethod": "sms" });
+    assert_eq!(app.post_two_fa_method(&body).await.status().as_u16(), 400);
+
+    enable_totp(&app).await;
+
+    let body = serde_json::json!({ "twoFAMethod": "email" });
+    assert_eq!(app.post_two_fa_method(&body).await.status().as_u16(), 200);
+
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+    assert_eq!(
+        response
+            .json::<TwoFactorAuthResponse>()
+            .await
+            .unwrap()
+            .two_fa_method,
+        TwoFAMethod::Email
+    );
+    assert_eq!(
+        app.email_client.last_email_to(&email).unwrap().subject,
+        "2FA Code"
+    );
+}
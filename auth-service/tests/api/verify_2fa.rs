@@ -1,14 +1,264 @@
-use crate::helpers::TestApp;
+use auth_service::utils::constants::{JWT_COOKIE_NAME, MAX_TWO_FA_ATTEMPTS};
+
+use crate::helpers::{get_random_email, TestApp};
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let app = TestApp::new().await;
+
+    let test_cases = [
+        serde_json::json!({
+            "loginAttemptId": uuid::Uuid::new_v4().to_string(),
+            "2FACode": "123456"
+        }),
+        serde_json::json!({
+            "email": get_random_email(),
+            "2FACode": "123456"
+        }),
+        serde_json::json!({
+            "email": get_random_email(),
+            "loginAttemptId": uuid::Uuid::new_v4().to_string()
+        }),
+        serde_json::json!({
+            "email": get_random_email(),
+            "loginAttemptId": uuid::Uuid::new_v4().to_string(),
+            "2FACode": 123456
+        }),
+        serde_json::json!({}),
+    ];
+
+    for test_case in test_cases.iter() {
+        let response = app.post_verify_2fa(test_case).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            422,
+            "Failed for input: {:?}",
+            test_case
+        );
+    }
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_input() {
+    let app = TestApp::new().await;
+
+    let test_cases = [
+        (
+            serde_json::json!({
+                "email": "",
+                "loginAttemptId": uuid::Uuid::new_v4().to_string(),
+                "2FACode": "123456"
+            }),
+            "Email cannot be empty",
+        ),
+        (
+            serde_json::json!({
+                "email": get_random_email(),
+                "loginAttemptId": "some-id",
+                "2FACode": "123456"
+            }),
+            "Invalid login attempt id",
+        ),
+        (
+            serde_json::json!({
+                "email": get_random_email(),
+                "loginAttemptId": uuid::Uuid::new_v4().to_string(),
+                "2FACode": "12345"
+            }),
+            "Invalid 2FA code",
+        ),
+        (
+            serde_json::json!({
+                "email": get_random_email(),
+                "loginAttemptId": uuid::Uuid::new_v4().to_string(),
+                "2FACode": "abcdef"
+            }),
+            "Invalid 2FA code",
+        ),
+    ];
+
+    for (test_case, expected_error) in test_cases.iter() {
+        let response = app.post_verify_2fa(test_case).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "Failed for input: {:?}",
+            test_case
+        );
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .expect("Failed to parse response body");
+
+        assert_eq!(json["error"], *expected_error);
+    }
+}
+
+#[tokio::test]
+async fn should_return_401_if_incorrect_credentials() {
+    let app = TestApp::new().await;
+
+    let (email, _) = app.signup_user(true).await;
+    let (login_attempt_id, code) = app.start_2fa_login(&email).await;
+    let wrong_code = if code == "000000" { "111111" } else { "000000" };
+
+    let test_cases = [
+        // Wrong code
+        serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id,
+            "2FACode": wrong_code
+        }),
+        // Wrong login attempt id
+        serde_json::json!({
+            "email": email,
+            "loginAttemptId": uuid::Uuid::new_v4().to_string(),
+            "2FACode": code
+        }),
+        // No pending login for this email
+        serde_json::json!({
+            "email": get_random_email(),
+            "loginAttemptId": login_attempt_id,
+            "2FACode": code
+        }),
+    ];
+
+    for test_case in test_cases.iter() {
+        let response = app.post_verify_2fa(test_case).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            401,
+            "Failed for input: {:?}",
+            test_case
+        );
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .expect("Failed to parse response body");
+
+        assert_eq!(json["error"], "Incorrect credentials");
+    }
+}
+
+#[tokio::test]
+async fn should_return_401_if_old_code() {
+    let app = TestApp::new().await;
+
+    let (email, _) = app.signup_user(true).await;
+    let (old_login_attempt_id, old_code) = app.start_2fa_login(&email).await;
+
+    // Logging in again replaces the pending code.
+    app.start_2fa_login(&email).await;
+
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": old_login_attempt_id,
+        "2FACode": old_code
+    });
+    let response = app.post_verify_2fa(&body).await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
 
 #[tokio::test]
-async fn verify_2fa_returns_200() {
+async fn should_return_200_if_correct_code() {
     let app = TestApp::new().await;
+
+    let (email, _) = app.signup_user(true).await;
+    let (login_attempt_id, code) = app.start_2fa_login(&email).await;
+
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": code
+    });
+    let response = app.post_verify_2fa(&body).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+
+    assert!(!auth_cookie.value().is_empty());
+}
+
+#[tokio::test]
+async fn should_return_401_if_same_code_twice() {
+    let app = TestApp::new().await;
+
+    let (email, _) = app.signup_user(true).await;
+    let (login_attempt_id, code) = app.start_2fa_login(&email).await;
+
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": code
+    });
+
+    let response = app.post_verify_2fa(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_verify_2fa(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_401_for_correct_code_after_too_many_wrong_ones() {
+    let app = TestApp::new().await;
+
+    let (email, _) = app.signup_user(true).await;
+    let (login_attempt_id, code) = app.start_2fa_login(&email).await;
+    let wrong_code = if code == "000000" { "111111" } else { "000000" };
+
+    for _ in 0..MAX_TWO_FA_ATTEMPTS {
+        let body = serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id,
+            "2FACode": wrong_code
+        });
+        let response = app.post_verify_2fa(&body).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
     let body = serde_json::json!({
-        "email": "test@email.com",
-        "loginAttemptId": "some-id",
-        "2FACode": "123456"
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": code
     });
     let response = app.post_verify_2fa(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_return_200_for_correct_code_after_fewer_wrong_ones() {
+    let app = TestApp::new().await;
+
+    let (email, _) = app.signup_user(true).await;
+    let (login_attempt_id, code) = app.start_2fa_login(&email).await;
+    let wrong_code = if code == "000000" { "111111" } else { "000000" };
 
+    for _ in 1..MAX_TWO_FA_ATTEMPTS {
+        let body = serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id,
+            "2FACode": wrong_code
+        });
+        let response = app.post_verify_2fa(&body).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": code
+    });
+    let response = app.post_verify_2fa(&body).await;
     assert_eq!(response.status().as_u16(), 200);
 }
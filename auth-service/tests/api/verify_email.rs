@@ -0,0 +1,215 @@
+use auth_service::ErrorResponse;
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn signup(app: &TestApp) -> String {
+    let email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&signup_body).await.status().as_u16(), 201);
+
+    email
+}
+
+#[tokio::test]
+async fn signup_should_send_a_verification_link() {
+    let app = TestApp::new().await;
+    let email = signup(&app).await;
+
+    let sent = app
+        .email_client
+        .last_email_to(&email)
+        .expect("No verification email sent");
+
+    assert_eq!(sent.subject, "Verify your email address");
+    assert!(sent
+        .content
+        .contains("http://localhost:3000/verify-email?token="));
+}
+
+#[tokio::test]
+async fn signup_should_succeed_if_the_verification_email_fails() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    app.email_client.set_failing(true);
+
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert!(json["message"]
+        .as_str()
+        .unwrap()
+        .contains("/verify-email/resend"));
+    assert!(app.email_client.sent_emails().is_empty());
+
+    // The link can be requested again right away.
+    app.email_client.set_failing(false);
+    let response = app
+        .post_verify_email_resend(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(app.email_client.last_email_to(&email).is_some());
+
+    app.verify_email(&email).await;
+    let body = serde_json::json!({ "email": email, "password": "password123" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn login_should_return_403_until_email_is_verified() {
+    let app = TestApp::new().await;
+    let email = signup(&app).await;
+
+    // A wrong password is still just a wrong password.
+    let body = serde_json::json!({ "email": email, "password": "wrong-password" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 401);
+
+    let body = serde_json::json!({ "email": email, "password": "password123" });
+    let response = app.post_login(&body).await;
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "Email address not verified"
+    );
+
+    app.verify_email(&email).await;
+
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn verify_email_should_accept_a_link_more_than_once() {
+    let app = TestApp::new().await;
+    let email = signup(&app).await;
+    let token = app.verification_token(&email);
+
+    for _ in 0..2 {
+        let response = app.get_verify_email(&token).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+}
+
+#[tokio::test]
+async fn verify_email_should_return_400_for_missing_or_tampered_token() {
+    let app = TestApp::new().await;
+    let email = signup(&app).await;
+    let token = app.verification_token(&email);
+    let (payload, signature) = token.rsplit_once('.').unwrap();
+    let tampered = format!(
+        "{}.{}",
+        payload,
+        signature.chars().rev().collect::<String>()
+    );
+
+    let test_cases = [
+        ("", "Invalid verification link"),
+        ("garbage", "Invalid verification link"),
+        (tampered.as_str(), "Invalid verification link"),
+    ];
+    for (token, error) in test_cases {
+        let response = app.get_verify_email(token).await;
+        assert_eq!(response.status().as_u16(), 400, "Failed for {:?}", token);
+        assert_eq!(response.json::<ErrorResponse>().await.unwrap().error, error);
+    }
+
+    let response = app
+        .http_client
+        .get(format!("{}/verify-email", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "Missing verification token"
+    );
+
+    let body = serde_json::json!({ "email": email, "password": "password123" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn resend_should_be_throttled() {
+    let app = TestApp::new().await;
+    let email = signup(&app).await;
+
+    let response = app
+        .post_verify_email_resend(&serde_json::json!({ "email": email }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(app.email_client.sent_emails().len(), 1);
+}
+
+#[tokio::test]
+async fn resend_should_not_reveal_unknown_or_verified_accounts() {
+    let app = TestApp::new().await;
+    let email = signup(&app).await;
+    app.verify_email(&email).await;
+
+    for email in [email, get_random_email()] {
+        let response = app
+            .post_verify_email_resend(&serde_json::json!({ "email": email }))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    assert_eq!(app.email_client.sent_emails().len(), 1);
+}
+
+#[tokio::test]
+async fn resend_should_not_reveal_a_failed_delivery() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    app.email_client.set_failing(true);
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&signup_body).await.status().as_u16(), 201);
+
+    let unknown_response = app
+        .post_verify_email_resend(&serde_json::json!({ "email": get_random_email() }))
+        .await
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    let response = app
+        .post_verify_email_resend(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.json::<serde_json::Value>().await.unwrap(),
+        unknown_response
+    );
+
+    // The failed attempt doesn't count against the throttle.
+    app.email_client.set_failing(false);
+    let response = app
+        .post_verify_email_resend(&serde_json::json!({ "email": email }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(app.email_client.last_email_to(&email).is_some());
+}
+
+#[tokio::test]
+async fn resend_should_return_400_or_422_for_invalid_input() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .post_verify_email_resend(&serde_json::json!({ "email": "not-an-email" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app.post_verify_email_resend(&serde_json::json!({})).await;
+    assert_eq!(response.status().as_u16(), 422);
+}
@@ -1,12 +1,167 @@
-use crate::helpers::TestApp;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use auth_service::{
+    domain::{SigningKey, SigningKeyStatus},
+    utils::{
+        auth::{generate_signing_key, Claims},
+        constants::JWT_COOKIE_NAME,
+    },
+};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+
+use crate::helpers::{get_random_email, TestApp};
+
+fn sign_claims(claims: &Claims, key: &SigningKey, kid: &str) -> String {
+    let mut header = Header::new(Algorithm::EdDSA);
+    header.kid = Some(kid.to_owned());
+    let encoding_key =
+        EncodingKey::from_ed_pem(key.private_key.as_bytes()).expect("Invalid signing key");
+
+    encode(&header, claims, &encoding_key).expect("Failed to sign claims")
+}
+
+async fn signing_key(app: &TestApp) -> SigningKey {
+    app.signing_key_store
+        .read()
+        .await
+        .get_keys()
+        .await
+        .expect("Failed to get signing keys")
+        .into_iter()
+        .find(|key| key.status == SigningKeyStatus::Signing)
+        .expect("No signing key")
+}
+
+fn claims_expiring_in(seconds: i64) -> Claims {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    Claims {
+        sub: get_random_email(),
+        iat: (now - 3600) as usize,
+        exp: (now + seconds) as usize,
+        jti: uuid::Uuid::new_v4().to_string(),
+        session_generation: 0,
+    }
+}
 
 #[tokio::test]
-async fn verify_token_returns_200() {
+async fn should_return_200_valid_token() {
     let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+    app.verify_email(&random_email).await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found");
+
     let body = serde_json::json!({
-        "token": "some-token"
+        "token": auth_cookie.value()
     });
     let response = app.post_verify_token(&body).await;
 
     assert_eq!(response.status().as_u16(), 200);
 }
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let app = TestApp::new().await;
+
+    let key = signing_key(&app).await;
+    let other_key = generate_signing_key("EdDSA").expect("Failed to generate key");
+
+    let test_cases = [
+        // Malformed token
+        "invalid".to_owned(),
+        String::new(),
+        // Expired token
+        sign_claims(&claims_expiring_in(-3600), &key, &key.kid),
+        // Token signed with an unpublished key
+        sign_claims(&claims_expiring_in(600), &other_key, &other_key.kid),
+        // Token signed with a different key under the signing key's `kid`
+        sign_claims(&claims_expiring_in(600), &other_key, &key.kid),
+        // Token signed with a shared secret and no `kid`
+        encode(
+            &Header::default(),
+            &claims_expiring_in(600),
+            &EncodingKey::from_secret(b"some-other-secret"),
+        )
+        .expect("Failed to sign claims"),
+    ];
+
+    for token in test_cases.iter() {
+        let body = serde_json::json!({
+            "token": token
+        });
+        let response = app.post_verify_token(&body).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            401,
+            "Failed for token: {:?}",
+            token
+        );
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .expect("Failed to parse response body");
+
+        assert_eq!(json["error"], "Invalid auth token");
+    }
+}
+
+#[tokio::test]
+async fn should_return_422_if_malformed_input() {
+    let app = TestApp::new().await;
+
+    let test_cases = [
+        serde_json::json!({}),
+        serde_json::json!({
+            "jwt": "some-token"
+        }),
+        serde_json::json!({
+            "token": 12345
+        }),
+        serde_json::json!({
+            "token": null
+        }),
+    ];
+
+    for test_case in test_cases.iter() {
+        let response = app.post_verify_token(test_case).await;
+
+        assert_eq!(
+            response.status().as_u16(),
+            422,
+            "Failed for input: {:?}",
+            test_case
+        );
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .expect("Failed to parse response body");
+
+        assert!(json["error"].is_string());
+    }
+}
@@ -0,0 +1,549 @@
+use auth_service::{
+    domain::{Email, TwoFAMethod},
+    routes::{CredentialCreationOptions, CredentialRequestOptions, TwoFactorAuthResponse},
+    utils::constants::JWT_COOKIE_NAME,
+    ErrorResponse,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ciborium::Value;
+use ring::{
+    rand::{SecureRandom, SystemRandom},
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING},
+};
+use sha2::{Digest, Sha256};
+
+use crate::helpers::{get_random_email, TestApp};
+
+// The page origin the default `webauthn` settings accept.
+const ORIGIN: &str = "http://localhost:3000";
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+// Plays the part of the browser and a security key: holds one P-256 credential and
+// answers the service's options with the JSON a browser would post.
+struct SoftwareAuthenticator {
+    key_pair: EcdsaKeyPair,
+    credential_id: Vec<u8>,
+    sign_count: u32,
+    // Whether the authenticator checked a PIN or biometric.
+    user_verified: bool,
+    attestation_format: &'static str,
+    origin: &'static str,
+    user_handle: Option<String>,
+}
+
+impl SoftwareAuthenticator {
+    fn new() -> Self {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+                .unwrap();
+        let mut credential_id = vec![0u8; 32];
+        rng.fill(&mut credential_id).unwrap();
+
+        Self {
+            key_pair,
+            credential_id,
+            sign_count: 0,
+            user_verified: true,
+            attestation_format: "none",
+            origin: ORIGIN,
+            user_handle: None,
+        }
+    }
+
+    fn flags(&self) -> u8 {
+        if self.user_verified {
+            FLAG_USER_PRESENT | FLAG_USER_VERIFIED
+        } else {
+            FLAG_USER_PRESENT
+        }
+    }
+
+    fn client_data(&self, ceremony_type: &str, challenge: &str) -> Vec<u8> {
+        serde_json::json!({
+            "type": ceremony_type,
+            "challenge": challenge,
+            "origin": self.origin,
+            "crossOrigin": false,
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn cose_key(&self) -> Vec<u8> {
+        let point = self.key_pair.public_key().as_ref();
+        let key = Value::Map(vec![
+            (1.into(), 2.into()),
+            (3.into(), (-7).into()),
+            ((-1).into(), 1.into()),
+            ((-2).into(), Value::Bytes(point[1..33].to_vec())),
+            ((-3).into(), Value::Bytes(point[33..].to_vec())),
+        ]);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&key, &mut bytes).unwrap();
+        bytes
+    }
+
+    // `navigator.credentials.create()`
+    fn create(&mut self, options: &CredentialCreationOptions) -> serde_json::Value {
+        self.user_handle = Some(options.user.id.clone());
+
+        let client_data = self.client_data("webauthn.create", &options.challenge);
+        let authenticator_data = [
+            &Sha256::digest(options.rp.id.as_bytes())[..],
+            &[self.flags() | FLAG_ATTESTED_CREDENTIAL_DATA],
+            &self.sign_count.to_be_bytes(),
+            &[0u8; 16],
+            &(self.credential_id.len() as u16).to_be_bytes(),
+            &self.credential_id,
+            &self.cose_key(),
+        ]
+        .concat();
+        let attestation_object = Value::Map(vec![
+            ("fmt".into(), self.attestation_format.into()),
+            ("attStmt".into(), Value::Map(vec![])),
+            ("authData".into(), Value::Bytes(authenticator_data)),
+        ]);
+        let mut attestation_object_bytes = Vec::new();
+        ciborium::into_writer(&attestation_object, &mut attestation_object_bytes).unwrap();
+
+        serde_json::json!({
+            "id": URL_SAFE_NO_PAD.encode(&self.credential_id),
+            "rawId": URL_SAFE_NO_PAD.encode(&self.credential_id),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": URL_SAFE_NO_PAD.encode(client_data),
+                "attestationObject": URL_SAFE_NO_PAD.encode(attestation_object_bytes),
+            },
+            "clientExtensionResults": {},
+        })
+    }
+
+    // `navigator.credentials.get()`
+    fn get(&mut self, options: &CredentialRequestOptions) -> serde_json::Value {
+        self.sign_count += 1;
+
+        let client_data = self.client_data("webauthn.get", &options.challenge);
+        let authenticator_data = [
+            &Sha256::digest(options.rp_id.as_bytes())[..],
+            &[self.flags()],
+            &self.sign_count.to_be_bytes(),
+        ]
+        .concat();
+        let signed_data = [&authenticator_data[..], &Sha256::digest(&client_data)[..]].concat();
+        let signature = self
+            .key_pair
+            .sign(&SystemRandom::new(), &signed_data)
+            .unwrap();
+
+        serde_json::json!({
+            "id": URL_SAFE_NO_PAD.encode(&self.credential_id),
+            "rawId": URL_SAFE_NO_PAD.encode(&self.credential_id),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": URL_SAFE_NO_PAD.encode(client_data),
+                "authenticatorData": URL_SAFE_NO_PAD.encode(authenticator_data),
+                "signature": URL_SAFE_NO_PAD.encode(signature.as_ref()),
+                "userHandle": self.user_handle,
+            },
+            "clientExtensionResults": {},
+        })
+    }
+}
+
+async fn creation_options(app: &TestApp) -> CredentialCreationOptions {
+    let response = app.post_webauthn_register_start().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<CredentialCreationOptions>()
+        .await
+        .expect("Could not deserialize response body to CredentialCreationOptions")
+}
+
+async fn register(app: &TestApp, authenticator: &mut SoftwareAuthenticator) {
+    let options = creation_options(app).await;
+    let response = app
+        .post_webauthn_register_finish(&authenticator.create(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+async fn login_options(app: &TestApp, body: &serde_json::Value) -> CredentialRequestOptions {
+    let response = app.post_webauthn_login_start(body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    response
+        .json::<CredentialRequestOptions>()
+        .await
+        .expect("Could not deserialize response body to CredentialRequestOptions")
+}
+
+fn has_auth_cookie(response: &reqwest::Response) -> bool {
+    response
+        .cookies()
+        .any(|cookie| cookie.name() == JWT_COOKIE_NAME && !cookie.value().is_empty())
+}
+
+#[tokio::test]
+async fn register_start_should_return_400_if_not_logged_in() {
+    let app = TestApp::new().await;
+
+    let response = app.post_webauthn_register_start().await;
+
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "Missing auth token"
+    );
+}
+
+#[tokio::test]
+async fn register_start_should_return_creation_options() {
+    let app = TestApp::new().await;
+    let email = app.signup_and_login(false).await;
+
+    let options = creation_options(&app).await;
+
+    assert_eq!(options.rp.id, "localhost");
+    assert_eq!(options.user.name, email);
+    assert_ne!(options.user.id, email);
+    assert_eq!(options.attestation, "none");
+    assert!(options.exclude_credentials.is_empty());
+
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&app, &mut authenticator).await;
+
+    let options = creation_options(&app).await;
+    assert_eq!(options.exclude_credentials.len(), 1);
+    assert_eq!(
+        options.exclude_credentials[0].id,
+        URL_SAFE_NO_PAD.encode(&authenticator.credential_id)
+    );
+}
+
+#[tokio::test]
+async fn register_should_reject_attestation_other_than_none() {
+    let app = TestApp::new().await;
+    app.signup_and_login(false).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    authenticator.attestation_format = "packed";
+
+    let options = creation_options(&app).await;
+    let response = app
+        .post_webauthn_register_finish(&authenticator.create(&options))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "Unsupported attestation format \"packed\""
+    );
+}
+
+#[tokio::test]
+async fn register_should_reject_credential_id_other_than_the_attested_one() {
+    let app = TestApp::new().await;
+    app.signup_and_login(false).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+
+    let options = creation_options(&app).await;
+    let mut credential = authenticator.create(&options);
+    credential["id"] = URL_SAFE_NO_PAD.encode([9u8; 16]).into();
+    let response = app.post_webauthn_register_finish(&credential).await;
+
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "Credential id does not match the attested credential"
+    );
+}
+
+#[tokio::test]
+async fn register_should_reject_untrusted_origin() {
+    let app = TestApp::new().await;
+    app.signup_and_login(false).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    authenticator.origin = "https://evil.example.com";
+
+    let options = creation_options(&app).await;
+    let response = app
+        .post_webauthn_register_finish(&authenticator.create(&options))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn register_should_reject_reused_challenge() {
+    let app = TestApp::new().await;
+    app.signup_and_login(false).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+
+    let options = creation_options(&app).await;
+    let credential = authenticator.create(&options);
+    assert_eq!(
+        app.post_webauthn_register_finish(&credential)
+            .await
+            .status()
+            .as_u16(),
+        201
+    );
+
+    let response = app.post_webauthn_register_finish(&credential).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn should_log_in_without_password() {
+    let app = TestApp::new().await;
+    let email = app.signup_and_login(false).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&app, &mut authenticator).await;
+    assert_eq!(app.post_logout().await.status().as_u16(), 200);
+
+    // Discoverable credential: the user doesn't even type their email.
+    let options = login_options(&app, &serde_json::json!({})).await;
+    assert!(options.allow_credentials.is_empty());
+    assert_eq!(options.user_verification, "required");
+    let response = app
+        .post_webauthn_login_finish(&authenticator.get(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(has_auth_cookie(&response));
+
+    let options = login_options(&app, &serde_json::json!({ "email": email })).await;
+    assert_eq!(options.allow_credentials.len(), 1);
+    let response = app
+        .post_webauthn_login_finish(&authenticator.get(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(has_auth_cookie(&response));
+}
+
+#[tokio::test]
+async fn login_start_should_not_reveal_unknown_emails() {
+    let app = TestApp::new().await;
+
+    let options = login_options(&app, &serde_json::json!({ "email": get_random_email() })).await;
+
+    assert!(options.allow_credentials.is_empty());
+}
+
+#[tokio::test]
+async fn login_should_require_user_verification() {
+    let app = TestApp::new().await;
+    app.signup_and_login(false).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&app, &mut authenticator).await;
+    authenticator.user_verified = false;
+
+    let options = login_options(&app, &serde_json::json!({})).await;
+    let response = app
+        .post_webauthn_login_finish(&authenticator.get(&options))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn login_should_reject_replayed_assertion() {
+    let app = TestApp::new().await;
+    app.signup_and_login(false).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&app, &mut authenticator).await;
+
+    let options = login_options(&app, &serde_json::json!({})).await;
+    let assertion = authenticator.get(&options);
+    assert_eq!(
+        app.post_webauthn_login_finish(&assertion)
+            .await
+            .status()
+            .as_u16(),
+        200
+    );
+
+    let response = app.post_webauthn_login_finish(&assertion).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn login_should_reject_sign_count_that_did_not_increase() {
+    let app = TestApp::new().await;
+    app.signup_and_login(false).await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&app, &mut authenticator).await;
+
+    for _ in 0..2 {
+        let options = login_options(&app, &serde_json::json!({})).await;
+        let response = app
+            .post_webauthn_login_finish(&authenticator.get(&options))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    // A clone of the key would continue from an older counter value.
+    authenticator.sign_count = 0;
+    let options = login_options(&app, &serde_json::json!({})).await;
+    let response = app
+        .post_webauthn_login_finish(&authenticator.get(&options))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn login_should_reject_unregistered_credential() {
+    let app = TestApp::new().await;
+    let mut authenticator = SoftwareAuthenticator::new();
+
+    let options = login_options(&app, &serde_json::json!({})).await;
+    let response = app
+        .post_webauthn_login_finish(&authenticator.get(&options))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn two_fa_method_should_return_400_without_credential() {
+    let app = TestApp::new().await;
+    app.signup_and_login(true).await;
+
+    let response = app
+        .post_two_fa_method(&serde_json::json!({ "twoFAMethod": "webauthn" }))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "No WebAuthn credential registered"
+    );
+}
+
+// Sign up a 2FA user with a registered credential as their second factor, log out and
+// start a password login, returning the login attempt id and the assertion options.
+async fn start_webauthn_2fa_login(
+    app: &TestApp,
+    authenticator: &mut SoftwareAuthenticator,
+) -> (String, String, CredentialRequestOptions) {
+    let email = app.signup_and_login(true).await;
+    register(app, authenticator).await;
+    let response = app
+        .post_two_fa_method(&serde_json::json!({ "twoFAMethod": "webauthn" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(app.post_logout().await.status().as_u16(), 200);
+
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let body = response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse");
+    assert_eq!(body.two_fa_method, TwoFAMethod::Webauthn);
+    let options = body
+        .webauthn_options
+        .expect("No WebAuthn options in response");
+    assert_eq!(options.allow_credentials.len(), 1);
+
+    (email, body.login_attempt_id, options)
+}
+
+#[tokio::test]
+async fn two_fa_method_should_enable_2fa_for_user_without_it() {
+    let app = TestApp::new().await;
+    let email = app.signup_and_login(false).await;
+    register(&app, &mut SoftwareAuthenticator::new()).await;
+
+    let response = app
+        .post_two_fa_method(&serde_json::json!({ "twoFAMethod": "webauthn" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(app.post_logout().await.status().as_u16(), 200);
+
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+    assert!(!has_auth_cookie(&response));
+    assert_eq!(
+        response
+            .json::<TwoFactorAuthResponse>()
+            .await
+            .unwrap()
+            .two_fa_method,
+        TwoFAMethod::Webauthn
+    );
+}
+
+#[tokio::test]
+async fn should_verify_2fa_with_webauthn() {
+    let app = TestApp::new().await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    // A second factor doesn't need user verification.
+    authenticator.user_verified = false;
+    let (email, login_attempt_id, options) =
+        start_webauthn_2fa_login(&app, &mut authenticator).await;
+
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "webauthnAssertion": authenticator.get(&options),
+    });
+    let response = app.post_verify_2fa(&body).await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(has_auth_cookie(&response));
+}
+
+#[tokio::test]
+async fn verify_2fa_should_reject_code_for_webauthn_user() {
+    let app = TestApp::new().await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    let (email, login_attempt_id, _) = start_webauthn_2fa_login(&app, &mut authenticator).await;
+
+    // The login attempt still has a generated code, but it was never sent.
+    let (_, code) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(email.clone()).unwrap())
+        .await
+        .unwrap();
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": code.as_ref(),
+    });
+    let response = app.post_verify_2fa(&body).await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn verify_2fa_should_reject_passwordless_login_challenge() {
+    let app = TestApp::new().await;
+    let mut authenticator = SoftwareAuthenticator::new();
+    let (email, login_attempt_id, _) = start_webauthn_2fa_login(&app, &mut authenticator).await;
+
+    let options = login_options(&app, &serde_json::json!({})).await;
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": login_attempt_id,
+        "webauthnAssertion": authenticator.get(&options),
+    });
+    let response = app.post_verify_2fa(&body).await;
+
+    assert_eq!(response.status().as_u16(), 401);
+}